@@ -123,4 +123,16 @@ pub enum WUSDError {
     /// 非所有者
     #[msg("Not an owner")]
     NotAnOwner,
+    /// 奖励金库余额不足
+    #[msg("Insufficient reward vault balance")]
+    InsufficientRewardBalance,
+    /// 锁定期已结束
+    #[msg("Lock period already ended")]
+    LockExpired,
+    /// 状态账户已是当前布局
+    #[msg("State account already migrated")]
+    AlreadyMigrated,
+    /// 账户数据无效
+    #[msg("Invalid account data")]
+    InvalidAccountData,
 }
\ No newline at end of file
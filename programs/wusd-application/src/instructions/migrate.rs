@@ -0,0 +1,200 @@
+use anchor_lang::prelude::*;
+use anchor_lang::{system_program, Discriminator};
+use crate::state::{
+    StateAccount, StakingPool, PoolStatus, TokenConfig, StakingConfig, MAX_STAKING_POOLS, STATE_VERSION,
+};
+use crate::error::WUSDError;
+use crate::instructions::swap::Rate;
+use crate::instructions::softstake::{SoftStakeAccount, SoftStakingStatus};
+
+/// 当前布局的账户总长度，含判别符
+const ACCOUNT_SIZE: usize = 8 + StateAccount::LEN;
+
+/// 将版本 0 的全局状态账户迁移到当前布局
+/// 旧布局的质押配置缺少复投小费，质押池只有基础参数，质押数据缺少奖励累计基数，
+/// 新增字段插在中间导致之后的字段整体偏移，因此按旧布局完整读出后重新写入
+pub fn migrate_state(ctx: Context<MigrateState>) -> Result<()> {
+    let state_info = ctx.accounts.state.to_account_info();
+    require_keys_eq!(*state_info.owner, crate::ID, WUSDError::InvalidAccountData);
+    require!(state_info.data_len() < ACCOUNT_SIZE, WUSDError::AlreadyMigrated);
+
+    let legacy = {
+        let data = state_info.try_borrow_data()?;
+        require!(
+            data.len() >= 8 && data[..8] == StateAccount::DISCRIMINATOR,
+            WUSDError::InvalidAccountData
+        );
+        LegacyStateAccount::deserialize(&mut &data[8..])?
+    };
+    require_keys_eq!(legacy.authority, ctx.accounts.authority.key(), WUSDError::Unauthorized);
+
+    let now = Clock::get()?.unix_timestamp;
+    let state = legacy.into_current(now)?;
+
+    // 补足新长度所需的租金后扩容
+    let rent = Rent::get()?.minimum_balance(ACCOUNT_SIZE);
+    let lamports_needed = rent.saturating_sub(state_info.lamports());
+    if lamports_needed > 0 {
+        system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                system_program::Transfer {
+                    from: ctx.accounts.authority.to_account_info(),
+                    to: state_info.clone(),
+                },
+            ),
+            lamports_needed,
+        )?;
+    }
+    state_info.realloc(ACCOUNT_SIZE, true)?;
+
+    let mut data = state_info.try_borrow_mut_data()?;
+    let mut writer: &mut [u8] = &mut data;
+    state.try_serialize(&mut writer)?;
+
+    emit!(StateMigrated {
+        authority: ctx.accounts.authority.key(),
+        from_version: 0,
+        to_version: STATE_VERSION,
+        timestamp: now,
+    });
+
+    Ok(())
+}
+
+/// 版本 0 的质押配置，没有 `compound_tip_bps`
+#[derive(AnchorDeserialize)]
+struct LegacyStakingConfig {
+    total_staked: u64,
+    reward_rate: u64,
+    last_update_time: i64,
+    emergency_withdraw_penalty: u64,
+    emergency_cooldown_duration: i64,
+    total_staking_plans: u64,
+}
+
+/// 版本 0 的质押池，只有基础参数
+#[derive(AnchorDeserialize, Clone, Copy)]
+struct LegacyStakingPool {
+    id: u64,
+    apy: u64,
+    duration: i64,
+    min_stake_amount: u64,
+    status: PoolStatus,
+}
+
+/// 版本 0 的质押数据，没有 `reward_debt`
+#[derive(AnchorDeserialize, Clone, Copy)]
+struct LegacySoftStakeAccount {
+    owner: Pubkey,
+    staking_pool_id: u64,
+    amount: u64,
+    apy: u64,
+    rewards_earned: u64,
+    start_time: i64,
+    end_time: i64,
+    claimable_timestamp: i64,
+    last_update_time: i64,
+    status: SoftStakingStatus,
+    access_key: [u8; 32],
+}
+
+/// 版本 0 的全局状态账户，不含判别符
+#[derive(AnchorDeserialize)]
+struct LegacyStateAccount {
+    authority: Pubkey,
+    token_config: Box<TokenConfig>,
+    staking_config: LegacyStakingConfig,
+    paused: bool,
+    token_whitelist: Box<[(Pubkey, bool); 3]>,
+    exchange_rates: Box<[(Pubkey, Pubkey, Rate); 3]>,
+    staking_pools: Box<[LegacyStakingPool; MAX_STAKING_POOLS]>,
+    claims: Box<[(Pubkey, LegacySoftStakeAccount); 16]>,
+    claims_count: u32,
+    owners: Box<[Pubkey; 5]>,
+    pool_address: Pubkey,
+}
+
+impl LegacyStateAccount {
+    /// 转换为当前布局，新增字段取默认值，质押池的累计奖励从迁移时开始计算
+    fn into_current(self, now: i64) -> Result<StateAccount> {
+        let staking_pools: Box<[StakingPool]> = self.staking_pools.iter()
+            .map(|pool| StakingPool {
+                id: pool.id,
+                apy: pool.apy,
+                duration: pool.duration,
+                min_stake_amount: pool.min_stake_amount,
+                status: pool.status,
+                last_update_time: now,
+                ..Default::default()
+            })
+            .collect();
+        let claims: Box<[(Pubkey, SoftStakeAccount)]> = self.claims.iter()
+            .map(|(key, claim)| (*key, SoftStakeAccount {
+                owner: claim.owner,
+                staking_pool_id: claim.staking_pool_id,
+                amount: claim.amount,
+                apy: claim.apy,
+                rewards_earned: claim.rewards_earned,
+                start_time: claim.start_time,
+                end_time: claim.end_time,
+                claimable_timestamp: claim.claimable_timestamp,
+                last_update_time: claim.last_update_time,
+                status: claim.status,
+                access_key: claim.access_key,
+                reward_debt: 0,
+            }))
+            .collect();
+        let config = self.staking_config;
+
+        Ok(StateAccount {
+            authority: self.authority,
+            token_config: self.token_config,
+            staking_config: Box::new(StakingConfig {
+                total_staked: config.total_staked,
+                reward_rate: config.reward_rate,
+                last_update_time: config.last_update_time,
+                emergency_withdraw_penalty: config.emergency_withdraw_penalty,
+                emergency_cooldown_duration: config.emergency_cooldown_duration,
+                total_staking_plans: config.total_staking_plans,
+                compound_tip_bps: 0,
+            }),
+            paused: self.paused,
+            token_whitelist: self.token_whitelist,
+            exchange_rates: self.exchange_rates,
+            staking_pools: staking_pools.try_into().map_err(|_| WUSDError::InvalidAccountData)?,
+            claims: claims.try_into().map_err(|_| WUSDError::InvalidAccountData)?,
+            claims_count: self.claims_count,
+            owners: self.owners,
+            pool_address: self.pool_address,
+            version: STATE_VERSION,
+        })
+    }
+}
+
+/// 迁移全局状态账户的账户参数
+#[derive(Accounts)]
+pub struct MigrateState<'info> {
+    /// 状态账户中记录的管理员，支付扩容所需的租金
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    /// CHECK: 可能仍是旧布局，在指令中按旧布局读取并校验
+    #[account(
+        mut,
+        seeds = [b"state"],
+        bump
+    )]
+    pub state: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// 状态账户迁移事件
+#[event]
+pub struct StateMigrated {
+    pub authority: Pubkey,
+    pub from_version: u8,
+    pub to_version: u8,
+    pub timestamp: i64,
+}
@@ -1,3 +1,4 @@
 pub mod stake; 
 pub mod swap;
-pub mod softstake;
\ No newline at end of file
+pub mod softstake;
+pub mod migrate;
@@ -1,7 +1,7 @@
 #![allow(dead_code)]
 use anchor_lang::prelude::*;
 use anchor_spl::token::{self, Mint, Token, TokenAccount, Transfer};
-use crate::state::{StateAccount, PoolStatus};
+use crate::state::{StateAccount, StakingPool, PoolStatus, TIER_MULTIPLIER_SCALE};
 use crate::error::WUSDError; 
 use crate::instructions::stake::calculate_rewards;  
 
@@ -15,7 +15,7 @@ pub fn calculate_soft_stake_rewards(
     let now = Clock::get()?.unix_timestamp;
     
     // 计算基础奖励
-    let base_rewards = calculate_rewards(amount, apy, duration);
+    let base_rewards = calculate_rewards(amount, apy, duration, TIER_MULTIPLIER_SCALE);
     
     // 计算额外奖励
     let bonus_rewards = amount
@@ -39,41 +39,181 @@ pub fn calculate_soft_stake_rewards(
     })
 }
 
+/// 软质押，将 WUSD 转入质押金库并按质押池的累计奖励计息，无锁定期，可随时领取奖励或取回本金
+/// 已有软质押时追加本金，须为同一质押池
+/// * `amount` - 质押金额
+/// * `staking_pool_id` - 质押池ID
+/// * `access_key` - 访问密钥
 pub fn soft_stake_handler(ctx: Context<SoftStake>, amount: u64, staking_pool_id: u64, access_key: [u8; 32]) -> Result<()> {
-    let stake_account = &mut ctx.accounts.stake_account;
     let now = Clock::get()?.unix_timestamp;
-    
-    stake_account.owner = ctx.accounts.user.key();
-    stake_account.staking_pool_id = staking_pool_id;
-    stake_account.amount = amount;
-    stake_account.apy = 0;
-    stake_account.rewards_earned = 0;
-    stake_account.start_time = now;
-    stake_account.end_time = 0;
-    stake_account.claimable_timestamp = 0;
+    require!(amount > 0, WUSDError::InvalidAmount);
+
+    let pool = ctx.accounts.state.get_staking_pool(staking_pool_id)?;
+    require!(pool.status == PoolStatus::Active, WUSDError::InvalidPoolStatus);
+    require!(amount >= pool.min_stake_amount, WUSDError::StakingAmountTooLow);
+    require!(
+        ctx.accounts.user_wusd.amount >= amount,
+        WUSDError::InsufficientBalance
+    );
+
+    let stake_account = &ctx.accounts.stake_account;
+    let topping_up = stake_account.amount > 0;
+    require!(
+        !topping_up || stake_account.staking_pool_id == staking_pool_id,
+        WUSDError::InvalidPoolId
+    );
+
+    token::transfer(
+        CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            Transfer {
+                from: ctx.accounts.user_wusd.to_account_info(),
+                to: ctx.accounts.stake_vault.to_account_info(),
+                authority: ctx.accounts.user.to_account_info(),
+            },
+        ),
+        amount,
+    )?;
+
+    let state = &mut ctx.accounts.state;
+    let pool = state.get_staking_pool_mut(staking_pool_id)?;
+    pool.update_rewards(now)?;
+
+    // 追加前先按原本金结算奖励，之后按新本金重新记录累计基数
+    let stake_account = &mut ctx.accounts.stake_account;
+    if topping_up {
+        stake_account.settle_rewards(pool)?;
+    } else {
+        stake_account.owner = ctx.accounts.user.key();
+        stake_account.staking_pool_id = staking_pool_id;
+        stake_account.start_time = now;
+        stake_account.end_time = 0;
+        stake_account.claimable_timestamp = 0;
+    }
+    pool.stake_in(amount, TIER_MULTIPLIER_SCALE)?;
+    stake_account.amount = stake_account.amount
+        .checked_add(amount)
+        .ok_or(WUSDError::MathOverflow)?;
+    stake_account.apy = pool.apy;
+    stake_account.reward_debt = pool.accumulated_rewards(stake_account.amount)?;
     stake_account.last_update_time = now;
     stake_account.status = SoftStakingStatus::Active;
     stake_account.access_key = access_key;
-    
+
+    // 软质押没有锁定期，奖励金库须覆盖已累计的奖励和至少 `MIN_REWARD_HORIZON` 的发放量
+    require!(
+        pool.projected_obligations(now)? <= ctx.accounts.reward_vault.amount as u128,
+        WUSDError::InsufficientRewardBalance
+    );
+
+    state.staking_config.total_staked = state.staking_config.total_staked
+        .checked_add(amount)
+        .ok_or(WUSDError::MathOverflow)?;
+    state.staking_config.last_update_time = now;
+
+    emit!(SoftStakeEvent {
+        user: ctx.accounts.user.key(),
+        staking_pool_id,
+        amount,
+        apy: stake_account.apy,
+        start_time: stake_account.start_time,
+        end_time: stake_account.end_time,
+        access_key,
+    });
+
     Ok(())
 }
 
-pub fn soft_claim_handler(ctx: Context<SoftClaim>) -> Result<()> {
-    let stake_account = &mut ctx.accounts.stake_account;
+/// 领取软质押奖励，可部分领取，本金保持质押
+/// * `amount` - 领取金额，不超过已累计的奖励
+pub fn soft_claim_handler(ctx: Context<SoftClaim>, amount: u64) -> Result<()> {
+    let now = Clock::get()?.unix_timestamp;
+    require!(amount > 0, WUSDError::InvalidAmount);
+
     let state = &mut ctx.accounts.state;
+    let stake_account = &mut ctx.accounts.stake_account;
+    let pool = state.get_staking_pool_mut(stake_account.staking_pool_id)?;
+    pool.update_rewards(now)?;
+    stake_account.settle_rewards(pool)?;
+
+    let rewards_earned = stake_account.rewards_earned;
+    require!(rewards_earned > 0, WUSDError::NoRewardsToClaim);
+    require!(amount <= rewards_earned, WUSDError::InsufficientBalance);
+    require!(
+        ctx.accounts.reward_vault.amount >= amount,
+        WUSDError::InsufficientRewardBalance
+    );
 
-    // 验证质押状态
-    require!(stake_account.amount > 0, WUSDError::InvalidAmount);
+    token::transfer(
+        CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            Transfer {
+                from: ctx.accounts.reward_vault.to_account_info(),
+                to: ctx.accounts.user_wusd.to_account_info(),
+                authority: ctx.accounts.state.to_account_info(),
+            },
+            &[&[b"state".as_ref(), &[ctx.bumps["state"]]]],
+        ),
+        amount,
+    )?;
+
+    let stake_account = &mut ctx.accounts.stake_account;
+    stake_account.rewards_earned = rewards_earned - amount;
+    stake_account.last_update_time = now;
+    ctx.accounts.state
+        .get_staking_pool_mut(stake_account.staking_pool_id)?
+        .release_rewards(amount);
+
+    emit!(SoftClaimEvent {
+        user: ctx.accounts.user.key(),
+        amount,
+        access_key: stake_account.access_key,
+    });
+
+    Ok(())
+}
+
+/// 取回软质押本金，可部分取回，已累计的奖励保留在账户中可继续领取
+/// * `amount` - 取回金额
+pub fn soft_unstake_handler(ctx: Context<SoftUnstake>, amount: u64) -> Result<()> {
+    let now = Clock::get()?.unix_timestamp;
+    require!(amount > 0, WUSDError::InvalidAmount);
+    require!(amount <= ctx.accounts.stake_account.amount, WUSDError::InsufficientBalance);
+
+    token::transfer(
+        CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            Transfer {
+                from: ctx.accounts.stake_vault.to_account_info(),
+                to: ctx.accounts.user_wusd.to_account_info(),
+                authority: ctx.accounts.state.to_account_info(),
+            },
+            &[&[b"state".as_ref(), &[ctx.bumps["state"]]]],
+        ),
+        amount,
+    )?;
+
+    let state = &mut ctx.accounts.state;
+    let stake_account = &mut ctx.accounts.stake_account;
+    let pool = state.get_staking_pool_mut(stake_account.staking_pool_id)?;
+    pool.update_rewards(now)?;
+    stake_account.settle_rewards(pool)?;
+    pool.stake_out(amount, TIER_MULTIPLIER_SCALE)?;
+    stake_account.amount -= amount;
+    stake_account.reward_debt = pool.accumulated_rewards(stake_account.amount)?;
+    stake_account.last_update_time = now;
 
-    // 计算奖励
-    let current_time = Clock::get()?.unix_timestamp;
-    let time_staked = current_time - stake_account.start_time;
-    let rewards = (stake_account.amount as f64 * state.staking_config.reward_rate as f64 * time_staked as f64) as u64;
+    state.staking_config.total_staked = state.staking_config.total_staked
+        .checked_sub(amount)
+        .ok_or(WUSDError::MathOverflow)?;
+    state.staking_config.last_update_time = now;
 
-    // 更新账户
-    stake_account.rewards_earned = rewards;
-    stake_account.amount = 0;
-    stake_account.status = SoftStakingStatus::Claimed;
+    emit!(SoftUnstakeEvent {
+        user: ctx.accounts.user.key(),
+        staking_pool_id: stake_account.staking_pool_id,
+        amount,
+        remaining: stake_account.amount,
+    });
 
     Ok(())
 }
@@ -174,6 +314,7 @@ pub fn create_claim_data(
         last_update_time: now,
         status: SoftStakingStatus::Active,
         access_key,
+        reward_debt: 0,
     };
     
     // 存储质押数据
@@ -211,6 +352,7 @@ pub fn create_claim_data_multiple(
             last_update_time: Clock::get()?.unix_timestamp,
             status: SoftStakingStatus::Active,
             access_key: claim_data.access_key,
+            reward_debt: 0,
         };
         
         // 存储质押账户信息
@@ -243,6 +385,7 @@ pub fn claims(
             last_update_time: now,
             status: SoftStakingStatus::Active,
             access_key: *access_key,
+            reward_debt: 0,
         };
         
         require!(claim.claimable_timestamp <= now, WUSDError::ClaimableTimestampNotReached);
@@ -303,7 +446,7 @@ pub fn validate_soft_stake(
     require!(stake_account.status == SoftStakingStatus::Active, WUSDError::InvalidStakingStatus);
     
     // 验证质押池状态
-    let staking_pool = StateAccount::get_staking_pool(stake_account.staking_pool_id)?;
+    let staking_pool = state.get_staking_pool(stake_account.staking_pool_id)?;
     require!(staking_pool.status == PoolStatus::Active, WUSDError::InvalidPoolStatus);
     
     // 验证合约状态
@@ -387,11 +530,20 @@ pub enum SoftStakingStatus {
 
 /// 软质押指令的账户参数
 #[derive(Accounts)]
+#[instruction(amount: u64, staking_pool_id: u64)]
 pub struct SoftStake<'info> {
     #[account(mut)]
     pub user: Signer<'info>,
-    #[account(mut)]
+    #[account(
+        mut,
+        constraint = user_wusd.owner == user.key() @ WUSDError::InvalidOwner,
+        constraint = user_wusd.mint == wusd_mint.key() @ WUSDError::InvalidAddress
+    )]
     pub user_wusd: Box<Account<'info, TokenAccount>>,
+    #[account(
+        constraint = wusd_mint.key() == state.token_config.wusd_mint @ WUSDError::InvalidAddress
+    )]
+    pub wusd_mint: Box<Account<'info, Mint>>,
     #[account(
         init_if_needed,
         payer = user,
@@ -400,8 +552,22 @@ pub struct SoftStake<'info> {
         bump
     )]
     pub stake_account: Box<Account<'info, SoftStakeAccount>>,
-    #[account(mut)]
+    /// 质押金库，与普通质押共用，由状态账户 PDA 控制
+    #[account(
+        init_if_needed,
+        payer = user,
+        seeds = [b"stake_vault", wusd_mint.key().as_ref()],
+        bump,
+        token::mint = wusd_mint,
+        token::authority = state
+    )]
     pub stake_vault: Box<Account<'info, TokenAccount>>,
+    /// 质押池奖励金库，用于检查奖励是否足以覆盖新增的质押
+    #[account(
+        seeds = [b"reward_vault", staking_pool_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub reward_vault: Box<Account<'info, TokenAccount>>,
     #[account(
         mut,
         seeds = [b"state"],
@@ -411,8 +577,10 @@ pub struct SoftStake<'info> {
     pub state: Box<Account<'info, StateAccount>>,
     pub system_program: Program<'info, System>,
     pub token_program: Program<'info, Token>,
+    pub rent: Sysvar<'info, Rent>,
 }
 
+/// 领取软质押奖励的账户参数
 #[derive(Accounts)]
 pub struct SoftClaim<'info> {
     pub user: Signer<'info>,
@@ -423,10 +591,60 @@ pub struct SoftClaim<'info> {
         constraint = stake_account.owner == user.key()
     )]
     pub stake_account: Box<Account<'info, SoftStakeAccount>>,
-    #[account(mut)]
+    #[account(
+        mut,
+        constraint = user_wusd.owner == user.key() @ WUSDError::InvalidOwner,
+        constraint = user_wusd.mint == wusd_mint.key() @ WUSDError::InvalidAddress
+    )]
     pub user_wusd: Box<Account<'info, TokenAccount>>,
-    #[account(mut)]
+    #[account(
+        constraint = wusd_mint.key() == state.token_config.wusd_mint @ WUSDError::InvalidAddress
+    )]
+    pub wusd_mint: Box<Account<'info, Mint>>,
+    #[account(
+        mut,
+        seeds = [b"state"],
+        bump,
+        constraint = !state.paused @ WUSDError::ContractPaused
+    )]
+    pub state: Box<Account<'info, StateAccount>>,
+    /// 质押池奖励金库
+    #[account(
+        mut,
+        seeds = [b"reward_vault", stake_account.staking_pool_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub reward_vault: Box<Account<'info, TokenAccount>>,
+    pub token_program: Program<'info, Token>,
+}
+
+/// 取回软质押本金的账户参数
+#[derive(Accounts)]
+pub struct SoftUnstake<'info> {
+    pub user: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"soft_stake_account", user.key().as_ref()],
+        bump,
+        constraint = stake_account.owner == user.key()
+    )]
+    pub stake_account: Box<Account<'info, SoftStakeAccount>>,
+    #[account(
+        mut,
+        constraint = user_wusd.owner == user.key() @ WUSDError::InvalidOwner,
+        constraint = user_wusd.mint == wusd_mint.key() @ WUSDError::InvalidAddress
+    )]
+    pub user_wusd: Box<Account<'info, TokenAccount>>,
+    #[account(
+        constraint = wusd_mint.key() == state.token_config.wusd_mint @ WUSDError::InvalidAddress
+    )]
     pub wusd_mint: Box<Account<'info, Mint>>,
+    #[account(
+        mut,
+        seeds = [b"stake_vault", wusd_mint.key().as_ref()],
+        bump
+    )]
+    pub stake_vault: Box<Account<'info, TokenAccount>>,
     #[account(
         mut,
         seeds = [b"state"],
@@ -477,7 +695,9 @@ pub struct SoftStakeAccount {
     pub last_update_time: i64,
     pub status: SoftStakingStatus,
     pub access_key: [u8; 32],
-}  
+    /// 上次结算时本金对应的累计奖励，按质押池的每单位质押累计奖励计算
+    pub reward_debt: u128,
+}
 
 /// 质押数据输入结构体
 #[derive(AnchorSerialize, AnchorDeserialize)]
@@ -512,7 +732,19 @@ pub struct PoolAddressSet {
 }
 
 impl SoftStakeAccount {
-    pub const LEN: usize = 32 + 8 + 8 + 8 + 8 + 8 + 8 + 8 + 1 + 32 + 8 + 32; // 增加额外的空间
+    pub const LEN: usize = 32 + 8 + 8 + 8 + 8 + 8 + 8 + 8 + 1 + 32 + 16 + 8 + 32; // 增加额外的空间
+
+    /// 按质押池当前的累计值结算本金对应的新增奖励，调用前须先更新质押池
+    pub fn settle_rewards(&mut self, pool: &StakingPool) -> Result<()> {
+        let accumulated = pool.accumulated_rewards(self.amount)?;
+        let pending = accumulated.saturating_sub(self.reward_debt);
+        self.rewards_earned = u64::try_from(pending)
+            .ok()
+            .and_then(|pending| self.rewards_earned.checked_add(pending))
+            .ok_or(WUSDError::MathOverflow)?;
+        self.reward_debt = accumulated;
+        Ok(())
+    }
 }
 
 /// 软质押事件，记录软质押操作的详细信息
@@ -535,6 +767,15 @@ pub struct SoftClaimEvent {
     pub access_key: [u8; 32],
 }
 
+/// 软质押本金取回事件
+#[event]
+pub struct SoftUnstakeEvent {
+    pub user: Pubkey,
+    pub staking_pool_id: u64,
+    pub amount: u64,
+    pub remaining: u64,
+}
+
 /// 软质押状态更新事件
 #[event]
 pub struct SoftStakeStatusUpdated {
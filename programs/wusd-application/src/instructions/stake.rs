@@ -1,10 +1,13 @@
-#![allow(dead_code)]
 use anchor_lang::prelude::*;
 use anchor_spl::token::{self, Mint, Token, TokenAccount, Transfer};
-use crate::state::{StateAccount, StakingPool, PoolStatus};
+use crate::state::{
+    StateAccount, StakingPool, PoolStatus, PoolTierTable, ApyTier,
+    APY_SCALE, SECONDS_PER_YEAR, MAX_STAKING_POOLS, MAX_APY_TIERS, TIER_MULTIPLIER_SCALE,
+    MAX_COMPOUND_TIP_BPS,
+};
 use crate::error::WUSDError;
-use crate::instructions::softstake::{SoftStakeAccount, SoftStakingStatus};
 
+/// 初始化用户质押账户的账户参数，用户的各笔质押仓位按该账户中的计数器编号
 #[derive(Accounts)]
 pub struct InitializeStakeAccount<'info> {
     #[account(mut)]
@@ -13,42 +16,117 @@ pub struct InitializeStakeAccount<'info> {
     #[account(
         init,
         payer = user,
-        space = 8 + StakeAccount::SIZE,
-        seeds = [b"stake", user.key().as_ref()],
+        space = 8 + UserStakeState::LEN,
+        seeds = [b"user_stake", user.key().as_ref()],
         bump
     )]
-    pub stake_account: Account<'info, StakeAccount>,
+    pub user_stake: Account<'info, UserStakeState>,
     
     pub system_program: Program<'info, System>,
 }
 
 
-/// 执行质押操作
+/// 执行质押操作，将用户的 WUSD 转入质押金库，每次质押创建一个新的仓位
 /// * `ctx` - 质押上下文
 /// * `amount` - 质押金额
 /// * `staking_pool_id` - 质押池ID
 pub fn stake(ctx: Context<Stake>, amount: u64, staking_pool_id: u64) -> Result<()> {
-    let stake_account = &mut ctx.accounts.stake_account;
     let now = Clock::get()?.unix_timestamp;
-    
+    require!(amount > 0, WUSDError::InvalidAmount);
+
     // 从状态账户中获取质押池信息
-    let state = &ctx.accounts.state;
-    let pool = state.staking_pools.get(staking_pool_id as usize)
-        .ok_or(WUSDError::InvalidPoolId)?;
-    
-    stake_account.stake_info = Box::new(StakeInfo {
+    let pool = ctx.accounts.state.get_staking_pool(staking_pool_id)?;
+    require!(pool.status == PoolStatus::Active, WUSDError::InvalidPoolStatus);
+    require!(amount >= pool.min_stake_amount, WUSDError::StakingAmountTooLow);
+
+    // 紧急提取后的冷却期内不能再次质押
+    require!(
+        now >= ctx.accounts.user_stake.emergency_cooldown,
+        WUSDError::EmergencyWithdrawCooldown
+    );
+    require!(
+        ctx.accounts.user_wusd.amount >= amount,
+        WUSDError::InsufficientBalance
+    );
+
+    token::transfer(
+        CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            Transfer {
+                from: ctx.accounts.user_wusd.to_account_info(),
+                to: ctx.accounts.stake_vault.to_account_info(),
+                authority: ctx.accounts.user.to_account_info(),
+            },
+        ),
+        amount,
+    )?;
+
+    let end_time = now.checked_add(pool.duration).ok_or(WUSDError::MathOverflow)?;
+    let (apy_tier, apy_multiplier) = PoolTierTable::select_from(
+        &ctx.accounts.pool_tiers.to_account_info(),
+        amount,
+        pool.duration,
+    )?;
+
+    let state = &mut ctx.accounts.state;
+    let pool = state.get_staking_pool_mut(staking_pool_id)?;
+    pool.update_rewards(now)?;
+    pool.stake_in(amount, apy_multiplier)?;
+    pool.record_lock_end(end_time);
+    let reward_debt = pool.accumulated_rewards(amount)?;
+
+    // 奖励金库须足以覆盖已累计的奖励和到发放结束（未设置发放计划时为池内最晚的锁定结束）为止的发放量
+    require!(
+        pool.projected_obligations(now)? <= ctx.accounts.reward_vault.amount as u128,
+        WUSDError::InsufficientRewardBalance
+    );
+    let pool = *pool;
+
+    state.staking_config.total_staked = state.staking_config.total_staked
+        .checked_add(amount)
+        .ok_or(WUSDError::MathOverflow)?;
+    state.staking_config.last_update_time = now;
+
+    let user_stake = &mut ctx.accounts.user_stake;
+    let position_id = user_stake.position_count;
+    user_stake.position_count = position_id
+        .checked_add(1)
+        .ok_or(WUSDError::MathOverflow)?;
+
+    let stake_account = &mut ctx.accounts.stake_account;
+    stake_account.owner = ctx.accounts.user.key();
+    stake_account.position_id = position_id;
+    stake_account.staking_pool_id = staking_pool_id;
+    *stake_account.stake_info = StakeInfo {
         amount,
         apy: pool.apy,
         rewards_earned: 0,
-        apy_tier: 0,
-    });
-    stake_account.time_info = Box::new(TimeInfo {
+        rewards_paid: 0,
+        apy_tier,
+        apy_multiplier,
+        reward_debt,
+    };
+    *stake_account.time_info = TimeInfo {
         start_time: now,
-        end_time: now + pool.duration,
+        end_time,
         claimable_timestamp: 0,
         last_update_time: now,
-    });
+    };
     stake_account.status = StakingStatus::Active;
+    stake_account.claim_type = ClaimType::Unclaimed;
+    stake_account.auto_compound = false;
+
+    emit!(StakeEvent {
+        user: ctx.accounts.user.key(),
+        staking_plan_id: position_id,
+        staking_pool_id,
+        amount,
+        apy: pool.apy,
+        start_time: now,
+        end_time,
+        staked_at: now,
+    });
+
     Ok(())
 }
 
@@ -56,126 +134,316 @@ pub fn stake(ctx: Context<Stake>, amount: u64, staking_pool_id: u64) -> Result<(
 /// * `amount` - 质押金额
 /// * `apy` - 年化收益率
 /// * `duration` - 质押时长（秒）
-pub fn calculate_rewards(amount: u64, apy: u64, duration: i64) -> u64 {
-    let seconds_per_year: u128 = 365 * 24 * 60 * 60;
-    let scale: u128 = 1_000_000;
-    ((amount as u128) * (apy as u128) * (duration as u128) / (seconds_per_year * scale)) as u64
+/// * `multiplier` - APY 档位倍数
+pub fn calculate_rewards(amount: u64, apy: u64, duration: i64, multiplier: u64) -> u64 {
+    let base = (amount as u128) * (apy as u128) * (duration.max(0) as u128) / (SECONDS_PER_YEAR * APY_SCALE);
+    apply_tier_multiplier(base, multiplier) as u64
 }
 
-/// 领取质押奖励
-/// * `ctx` - 领取奖励的上下文
-pub fn claim(ctx: Context<Claim>) -> Result<()> {
-    let stake_account = &mut ctx.accounts.stake_account;
-    let state = &mut ctx.accounts.state;
+/// 按 APY 档位倍数放大奖励
+pub fn apply_tier_multiplier(rewards: u128, multiplier: u64) -> u128 {
+    rewards.saturating_mul(multiplier as u128) / TIER_MULTIPLIER_SCALE as u128
+}
 
-    // 验证质押状态
-    require!(stake_account.stake_info.amount > 0, WUSDError::InvalidAmount);
-    require!(stake_account.status == StakingStatus::Active, WUSDError::InvalidStakingStatus);
+/// 设置质押池的 APY 档位表，只影响之后分配或调整的档位
+/// * `staking_pool_id` - 质押池ID
+/// * `tiers` - 档位列表，倍数不低于 1 倍
+pub fn set_pool_tiers(ctx: Context<SetPoolTiers>, staking_pool_id: u64, tiers: Vec<ApyTier>) -> Result<()> {
+    ctx.accounts.state.get_staking_pool(staking_pool_id)?;
+    require!(tiers.len() <= MAX_APY_TIERS, WUSDError::InvalidInput);
+    require!(
+        tiers.iter().all(|tier| tier.multiplier >= TIER_MULTIPLIER_SCALE && tier.min_lock_duration >= 0),
+        WUSDError::InvalidInput
+    );
 
-    // 计算奖励
-    let current_time = Clock::get()?.unix_timestamp;
-    let time_staked = current_time - stake_account.time_info.start_time;
-    let rewards = (stake_account.stake_info.amount as f64 * state.staking_config.reward_rate as f64 * time_staked as f64) as u64;
+    let table = &mut ctx.accounts.pool_tiers;
+    table.staking_pool_id = staking_pool_id;
+    table.tier_count = tiers.len() as u8;
+    table.tiers = [ApyTier::default(); MAX_APY_TIERS];
+    table.tiers[..tiers.len()].copy_from_slice(&tiers);
 
-    // 更新账户
-    stake_account.stake_info.rewards_earned = rewards;
-    stake_account.status = StakingStatus::Claimed;
+    emit!(PoolTiersUpdated {
+        staking_pool_id,
+        tiers,
+    });
 
     Ok(())
 }
 
-/// 标准化质押金额和 APY
-pub fn normalize_staking(
-    staked_amount: u64,
-    apy: u64,
-    start_time: i64,
-    upgraded_timestamp: i64,
-    is_termination: bool
-) -> (u64, u64) {
-    let decimal_compensation = 1_000_000_000_000; // 1e12
-    
-    let mut normalized_staked_amount = staked_amount;
-    let mut normalized_apy = if is_termination {
-        // 使用基础 APY
-        apy
-    } else {
-        apy
-    };
-    
-    // 如果是升级前创建的质押，需要标准化金额
-    if start_time < upgraded_timestamp {
-        normalized_staked_amount = normalized_staked_amount.checked_mul(decimal_compensation).unwrap_or(staked_amount);
-        normalized_apy = normalized_apy.checked_mul(decimal_compensation).unwrap_or(apy);
+/// 延长仓位的锁定期，满足更高档位条件时提升 APY 倍数，档位不会下降
+/// * `new_end_time` - 新的锁定结束时间
+pub fn extend_lock(ctx: Context<ExtendLock>, new_end_time: i64) -> Result<()> {
+    let now = Clock::get()?.unix_timestamp;
+    let stake_account = &mut ctx.accounts.stake_account;
+    require!(stake_account.status == StakingStatus::Active, WUSDError::InvalidStakingStatus);
+    require!(
+        new_end_time > stake_account.time_info.end_time && new_end_time > now,
+        WUSDError::InvalidLockDuration
+    );
+
+    // 先按原倍数结算已累计的奖励
+    let pool = ctx.accounts.state.get_staking_pool_mut(stake_account.staking_pool_id)?;
+    require!(pool.status == PoolStatus::Active, WUSDError::InvalidPoolStatus);
+    pool.update_rewards(now)?;
+    stake_account.settle_rewards(pool)?;
+
+    let lock_duration = new_end_time - stake_account.time_info.start_time;
+    let (apy_tier, apy_multiplier) = PoolTierTable::select_from(
+        &ctx.accounts.pool_tiers.to_account_info(),
+        stake_account.stake_info.amount,
+        lock_duration,
+    )?;
+    let old_end_time = stake_account.time_info.end_time;
+    pool.record_lock_end(new_end_time);
+    stake_account.time_info.end_time = new_end_time;
+    stake_account.time_info.last_update_time = now;
+    if apy_multiplier > stake_account.stake_info.apy_multiplier {
+        let amount = stake_account.stake_info.amount;
+        pool.stake_out(amount, stake_account.stake_info.apy_multiplier)?;
+        pool.stake_in(amount, apy_multiplier)?;
+        stake_account.stake_info.apy_tier = apy_tier;
+        stake_account.stake_info.apy_multiplier = apy_multiplier;
     }
-    
-    (normalized_staked_amount, normalized_apy)
+
+    emit!(LockExtended {
+        user: ctx.accounts.user.key(),
+        position_id: stake_account.position_id,
+        old_end_time,
+        new_end_time,
+        apy_tier: stake_account.stake_info.apy_tier,
+        apy_multiplier: stake_account.stake_info.apy_multiplier,
+    });
+
+    Ok(())
 }
 
-/// 计算收益
-pub fn calculate_yield(staked_amount: u64, apy: u64, staking_period: u64) -> u64 {
-    let seconds_per_year: u128 = 365 * 24 * 60 * 60;
-    let scale: u128 = 1_000_000;
-    
-    ((staked_amount as u128)
-        .checked_mul(apy as u128).unwrap_or(0)
-        .checked_mul(staking_period as u128).unwrap_or(0)
-        .checked_div(seconds_per_year.checked_mul(scale).unwrap_or(1)).unwrap_or(0)) as u64
-} 
+/// 领取质押奖励，可部分领取，本金保持质押
+/// * `ctx` - 领取奖励的上下文
+/// * `amount` - 领取金额，不超过已累计的奖励
+pub fn claim(ctx: Context<Claim>, amount: u64) -> Result<()> {
+    let now = Clock::get()?.unix_timestamp;
+    require!(amount > 0, WUSDError::InvalidAmount);
 
-/// 提取质押的代币
-/// * `ctx` - 提取上下文
-/// * `amount` - 提取金额
-/// * `is_emergency` - 是否紧急提取
-pub fn withdraw(
-    ctx: Context<Withdraw>,
-    amount: u64,
-    is_emergency: bool,
-) -> Result<()> {
+    let state = &mut ctx.accounts.state;
     let stake_account = &mut ctx.accounts.stake_account;
-    require!(stake_account.stake_info.amount >= amount, WUSDError::InsufficientBalance);
-    require!(stake_account.status == StakingStatus::Active, WUSDError::InvalidStakingStatus);
+    let pool = state.staking_pools.get_mut(stake_account.staking_pool_id as usize)
+        .ok_or(WUSDError::InvalidPoolId)?;
+    pool.update_rewards(now)?;
+    stake_account.settle_rewards(pool)?;
 
-    let now = Clock::get()?.unix_timestamp;
-    let rewards = if !is_emergency {
-        calculate_rewards(
-            amount,
-            stake_account.stake_info.apy,
-            now - stake_account.time_info.start_time,
-        )
-    } else { 0 };
+    let rewards_earned = stake_account.stake_info.rewards_earned;
+    require!(rewards_earned > 0, WUSDError::NoRewardsToClaim);
+    require!(amount <= rewards_earned, WUSDError::InsufficientBalance);
+    require!(
+        ctx.accounts.reward_vault.amount >= amount,
+        WUSDError::InsufficientRewardBalance
+    );
 
     token::transfer(
         CpiContext::new_with_signer(
             ctx.accounts.token_program.to_account_info(),
             Transfer {
-                from: ctx.accounts.stake_vault.to_account_info(),
+                from: ctx.accounts.reward_vault.to_account_info(),
                 to: ctx.accounts.user_wusd.to_account_info(),
                 authority: ctx.accounts.state.to_account_info(),
             },
             &[&[b"state".as_ref(), &[ctx.bumps["state"]]]],
         ),
-        amount
+        amount,
     )?;
 
-    stake_account.stake_info.amount = stake_account.stake_info.amount.checked_sub(amount).unwrap();
+    let stake_account = &mut ctx.accounts.stake_account;
+    stake_account.stake_info.rewards_earned = rewards_earned - amount;
+    stake_account.stake_info.rewards_paid = stake_account.stake_info.rewards_paid
+        .checked_add(amount)
+        .ok_or(WUSDError::MathOverflow)?;
+    stake_account.time_info.last_update_time = now;
+    ctx.accounts.state
+        .get_staking_pool_mut(stake_account.staking_pool_id)?
+        .release_rewards(amount);
+
+    emit!(ClaimEvent {
+        user: ctx.accounts.user.key(),
+        position_id: stake_account.position_id,
+        amount,
+    });
+
+    Ok(())
+}
+
+/// 申请解除质押，锁定期结束（或质押池关闭）后可申请，仓位停止累计奖励，
+/// 等待质押池的 `unbonding_period` 后才能提取
+pub fn request_unstake(ctx: Context<RequestUnstake>) -> Result<()> {
+    let now = Clock::get()?.unix_timestamp;
+    let stake_account = &mut ctx.accounts.stake_account;
+    require!(stake_account.status == StakingStatus::Active, WUSDError::InvalidStakingStatus);
+    require!(stake_account.stake_info.amount > 0, WUSDError::InvalidAmount);
+
+    let pool = ctx.accounts.state.get_staking_pool_mut(stake_account.staking_pool_id)?;
+    require!(
+        now >= stake_account.time_info.end_time || pool.status == PoolStatus::Closed,
+        WUSDError::StakeLocked
+    );
+
+    // 结算至当前后移出质押池，之后不再累计奖励
+    pool.update_rewards(now)?;
+    stake_account.settle_rewards(pool)?;
+    pool.stake_out(stake_account.stake_info.amount, stake_account.stake_info.apy_multiplier)?;
+
+    let claimable_timestamp = now
+        .checked_add(pool.unbonding_period)
+        .ok_or(WUSDError::MathOverflow)?;
+    stake_account.status = StakingStatus::Unlocked;
+    stake_account.time_info.claimable_timestamp = claimable_timestamp;
+    stake_account.time_info.last_update_time = now;
+
+    emit!(UnstakeRequested {
+        user: ctx.accounts.user.key(),
+        position_id: stake_account.position_id,
+        amount: stake_account.stake_info.amount,
+        claimable_timestamp,
+    });
+
+    Ok(())
+}
+
+/// 设置质押池的解除质押等待期，只影响之后的申请
+/// * `staking_pool_id` - 质押池ID
+/// * `unbonding_period` - 等待期（秒）
+pub fn set_unbonding_period(
+    ctx: Context<StakingPoolAccounts>,
+    staking_pool_id: u64,
+    unbonding_period: i64,
+) -> Result<()> {
+    require!(unbonding_period >= 0, WUSDError::InvalidLockDuration);
+    ctx.accounts.state.get_staking_pool_mut(staking_pool_id)?.unbonding_period = unbonding_period;
+
+    emit!(UnbondingPeriodSet {
+        staking_pool_id,
+        unbonding_period,
+    });
+
+    Ok(())
+}
+
+/// 提取质押的代币
+/// 正常提取须先申请解除质押并等到 `claimable_timestamp`；紧急提取可随时进行，
+/// 但按 `emergency_withdraw_penalty` 扣除罚金转入金库，且在冷却期内不能再次质押或紧急提取
+/// * `ctx` - 提取上下文
+/// * `amount` - 提取金额
+/// * `is_emergency` - 是否紧急提取
+pub fn withdraw(
+    ctx: Context<Withdraw>,
+    amount: u64,
+    is_emergency: bool,
+) -> Result<()> {
+    let now = Clock::get()?.unix_timestamp;
+    let stake_account = &ctx.accounts.stake_account;
+    require!(amount > 0, WUSDError::InvalidAmount);
+    require!(stake_account.stake_info.amount >= amount, WUSDError::InsufficientBalance);
+    require!(
+        stake_account.status == StakingStatus::Active || stake_account.status == StakingStatus::Unlocked,
+        WUSDError::InvalidStakingStatus
+    );
+    let unbonding = stake_account.status == StakingStatus::Unlocked;
+
+    let penalty = if is_emergency {
+        // 冷却期按用户记录，避免通过其他仓位绕过
+        require!(
+            now >= ctx.accounts.user_stake.emergency_cooldown,
+            WUSDError::EmergencyWithdrawCooldown
+        );
+        let penalty = (amount as u128)
+            .checked_mul(ctx.accounts.state.staking_config.emergency_withdraw_penalty as u128)
+            .ok_or(WUSDError::MathOverflow)?
+            / 1_000_000;
+        penalty.min(amount as u128) as u64
+    } else {
+        require!(unbonding, WUSDError::StakeLocked);
+        require!(
+            now >= stake_account.time_info.claimable_timestamp,
+            WUSDError::ClaimableTimestampNotReached
+        );
+        0
+    };
+
+    let signer_seeds: &[&[&[u8]]] = &[&[b"state".as_ref(), &[ctx.bumps["state"]]]];
+    let payout = amount - penalty;
+    if payout > 0 {
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.stake_vault.to_account_info(),
+                    to: ctx.accounts.user_wusd.to_account_info(),
+                    authority: ctx.accounts.state.to_account_info(),
+                },
+                signer_seeds,
+            ),
+            payout,
+        )?;
+    }
+    if penalty > 0 {
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.stake_vault.to_account_info(),
+                    to: ctx.accounts.treasury.to_account_info(),
+                    authority: ctx.accounts.state.to_account_info(),
+                },
+                signer_seeds,
+            ),
+            penalty,
+        )?;
+    }
+
+    // 先按变更前的本金结算奖励，紧急提取放弃未领取的奖励
+    let state = &mut ctx.accounts.state;
+    let stake_account = &mut ctx.accounts.stake_account;
+    let pool = state.staking_pools.get_mut(stake_account.staking_pool_id as usize)
+        .ok_or(WUSDError::InvalidPoolId)?;
+    pool.update_rewards(now)?;
+    stake_account.settle_rewards(pool)?;
+    if is_emergency {
+        pool.release_rewards(stake_account.stake_info.rewards_earned);
+        stake_account.stake_info.rewards_earned = 0;
+    }
+    let rewards = stake_account.stake_info.rewards_earned;
+
+    // 解除质押等待中的本金已在申请时移出质押池
+    if !unbonding {
+        pool.stake_out(amount, stake_account.stake_info.apy_multiplier)?;
+    }
+    stake_account.stake_info.amount = stake_account.stake_info.amount
+        .checked_sub(amount)
+        .ok_or(WUSDError::MathOverflow)?;
+    stake_account.stake_info.reward_debt = pool.accumulated_rewards(stake_account.stake_info.amount)?;
     if stake_account.stake_info.amount == 0 {
         stake_account.status = StakingStatus::Claimed;
         stake_account.claim_type = if is_emergency { ClaimType::Emergency } else { ClaimType::Claimed };
     }
-
     if is_emergency {
-        stake_account.emergency_cooldown = now + ctx.accounts.state.staking_config.emergency_cooldown_duration;
+        stake_account.emergency_cooldown = now
+            .checked_add(ctx.accounts.state.staking_config.emergency_cooldown_duration)
+            .ok_or(WUSDError::MathOverflow)?;
+        ctx.accounts.user_stake.emergency_cooldown = stake_account.emergency_cooldown;
     }
+    stake_account.time_info.last_update_time = now;
 
     let state = &mut ctx.accounts.state;
-    state.staking_config.total_staked = state.staking_config.total_staked.checked_sub(amount).unwrap();
+    state.staking_config.total_staked = state.staking_config.total_staked
+        .checked_sub(amount)
+        .ok_or(WUSDError::MathOverflow)?;
     state.staking_config.last_update_time = now;
 
     emit!(WithdrawEvent {
         user: ctx.accounts.user.key(),
+        position_id: stake_account.position_id,
         staking_pool_id: stake_account.staking_pool_id,
         amount,
         rewards,
+        penalty,
         withdraw_time: now,
         is_emergency,
     });
@@ -183,138 +451,670 @@ pub fn withdraw(
     Ok(())
 }
 
-/// 设置已终止质押计划的冷却期
-/// * `ctx` - 设置冷却期的上下文
-/// * `user` - 用户地址
-/// * `staking_plan_id` - 质押计划ID
-/// * `cooldown` - 冷却期时长（秒）
-pub fn set_terminated_plan_cooldown(
-    ctx: Context<TerminatedPlanCooldown>,
-    user: Pubkey,
-    staking_plan_id: u64,
-    cooldown: i64,
-) -> Result<()> {
-    require!(!user.eq(&Pubkey::default()), WUSDError::InvalidAddress);
-    require!(cooldown > 0, WUSDError::InvalidLockDuration);
-    require!(staking_plan_id > 0, WUSDError::InvalidPoolId);
-    
-    let state = &mut ctx.accounts.state;
+/// 在锁定期内提前退出整个仓位
+/// 奖励按已服务时间占锁定期的比例折算，即按降低后的 APY 结算：未领取的奖励中超出部分被没收，
+/// 已领取或已复投的奖励超出应得部分时从本金扣回。质押池设置了 `early_exit_haircut` 时，
+/// 另按剩余锁定比例扣除本金。没收和扣除的金额全部留在或转入质押池的奖励金库
+pub fn early_exit(ctx: Context<EarlyExit>) -> Result<()> {
     let now = Clock::get()?.unix_timestamp;
-    
-    if let Some(index) = state.claims.iter().position(|(_, c)| c.owner == Pubkey::default()) {
-        state.claims[index] = (user, SoftStakeAccount {
-            owner: ctx.accounts.authority.key(),
-            staking_pool_id: staking_plan_id,
-            amount: 0,
-            apy: 0,
-            rewards_earned: 0,
-            start_time: now,
-            end_time: cooldown,
-            claimable_timestamp: cooldown,
-            last_update_time: now,
-            status: SoftStakingStatus::Active,
-            access_key: [0; 32]
-        });
-        Ok(())
-    } else {
-        err!(WUSDError::NoAvailableWhitelistSlot)
+    let state = &mut ctx.accounts.state;
+    let stake_account = &mut ctx.accounts.stake_account;
+    require!(stake_account.status == StakingStatus::Active, WUSDError::InvalidStakingStatus);
+    require!(stake_account.stake_info.amount > 0, WUSDError::InvalidAmount);
+
+    let pool = state.get_staking_pool_mut(stake_account.staking_pool_id)?;
+    let start_time = stake_account.time_info.start_time;
+    let end_time = stake_account.time_info.end_time;
+    require!(now < end_time && pool.status != PoolStatus::Closed, WUSDError::LockExpired);
+
+    pool.update_rewards(now)?;
+    stake_account.settle_rewards(pool)?;
+
+    let principal = stake_account.stake_info.amount;
+    let pending = stake_account.stake_info.rewards_earned;
+    let lock_duration = (end_time - start_time) as u128;
+    let time_served = (now - start_time) as u128;
+    let remaining = (end_time - now) as u128;
+
+    // 整个仓位的应得奖励按已服务时间折算
+    let rewards_earned = (stake_account.stake_info.rewards_paid as u128)
+        .checked_add(pending as u128)
+        .ok_or(WUSDError::MathOverflow)?;
+    let entitled = rewards_earned
+        .checked_mul(time_served)
+        .ok_or(WUSDError::MathOverflow)?
+        / lock_duration;
+    let rewards_lost = rewards_earned - entitled;
+    let rewards_forfeited = rewards_lost.min(pending as u128) as u64;
+    let rewards_kept = pending - rewards_forfeited;
+
+    let principal_haircut = (principal as u128)
+        .checked_mul(pool.early_exit_haircut as u128)
+        .and_then(|v| v.checked_mul(remaining))
+        .ok_or(WUSDError::MathOverflow)?
+        / lock_duration
+        / APY_SCALE;
+    let principal_haircut = principal_haircut.min(principal as u128) as u64;
+    let rewards_clawback = (rewards_lost - rewards_forfeited as u128)
+        .min((principal - principal_haircut) as u128) as u64;
+    let principal_returned = principal - principal_haircut - rewards_clawback;
+
+    require!(
+        ctx.accounts.reward_vault.amount >= rewards_kept,
+        WUSDError::InsufficientRewardBalance
+    );
+
+    pool.stake_out(principal, stake_account.stake_info.apy_multiplier)?;
+    pool.release_rewards(pending);
+    state.staking_config.total_staked = state.staking_config.total_staked
+        .checked_sub(principal)
+        .ok_or(WUSDError::MathOverflow)?;
+    state.staking_config.last_update_time = now;
+
+    stake_account.stake_info.amount = 0;
+    stake_account.stake_info.rewards_earned = 0;
+    stake_account.stake_info.rewards_paid = stake_account.stake_info.rewards_paid
+        .checked_add(rewards_kept)
+        .ok_or(WUSDError::MathOverflow)?;
+    stake_account.stake_info.reward_debt = 0;
+    stake_account.status = StakingStatus::Claimed;
+    stake_account.claim_type = ClaimType::EarlyExit;
+    stake_account.time_info.last_update_time = now;
+
+    let signer_seeds: &[&[&[u8]]] = &[&[b"state".as_ref(), &[ctx.bumps["state"]]]];
+    if principal_returned > 0 {
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.stake_vault.to_account_info(),
+                    to: ctx.accounts.user_wusd.to_account_info(),
+                    authority: ctx.accounts.state.to_account_info(),
+                },
+                signer_seeds,
+            ),
+            principal_returned,
+        )?;
     }
+    let principal_deducted = principal_haircut + rewards_clawback;
+    if principal_deducted > 0 {
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.stake_vault.to_account_info(),
+                    to: ctx.accounts.reward_vault.to_account_info(),
+                    authority: ctx.accounts.state.to_account_info(),
+                },
+                signer_seeds,
+            ),
+            principal_deducted,
+        )?;
+    }
+    if rewards_kept > 0 {
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.reward_vault.to_account_info(),
+                    to: ctx.accounts.user_wusd.to_account_info(),
+                    authority: ctx.accounts.state.to_account_info(),
+                },
+                signer_seeds,
+            ),
+            rewards_kept,
+        )?;
+    }
+
+    let stake_account = &ctx.accounts.stake_account;
+    emit!(EarlyExitEvent {
+        user: ctx.accounts.user.key(),
+        position_id: stake_account.position_id,
+        staking_pool_id: stake_account.staking_pool_id,
+        principal,
+        principal_haircut,
+        rewards_clawback,
+        principal_returned,
+        rewards_earned: rewards_earned as u64,
+        rewards_forfeited,
+        rewards_paid: rewards_kept,
+        time_served: time_served as i64,
+        lock_duration: lock_duration as i64,
+        exit_time: now,
+    });
+
+    Ok(())
 }
 
-/// 获取已终止质押计划的冷却期
-/// * `ctx` - 获取冷却期的上下文
-/// * `user` - 用户地址
-/// * `staking_plan_id` - 质押计划ID
-pub fn get_terminated_plan_cooldown(
-    ctx: Context<TerminatedPlanCooldown>,
-    user: Pubkey,
-    staking_plan_id: u64,
-) -> Result<i64> {
-    let state = &ctx.accounts.state;
-    require!(staking_plan_id > 0, WUSDError::InvalidPoolId);
-    if let Some((_, claim)) = state.claims.iter().find(|(_, c)| c.owner == user && c.staking_pool_id == staking_plan_id) {
-        Ok(claim.end_time)
-    } else {
-        err!(WUSDError::InvalidStakingPlan)
-    }
+/// 设置质押池提前退出时的本金扣除比例
+/// * `staking_pool_id` - 质押池ID
+/// * `early_exit_haircut` - 扣除比例，按 `APY_SCALE` 表示
+pub fn set_early_exit_haircut(
+    ctx: Context<StakingPoolAccounts>,
+    staking_pool_id: u64,
+    early_exit_haircut: u64,
+) -> Result<()> {
+    require!(early_exit_haircut as u128 <= APY_SCALE, WUSDError::InvalidInput);
+    ctx.accounts.state.get_staking_pool_mut(staking_pool_id)?.early_exit_haircut = early_exit_haircut;
+
+    emit!(EarlyExitHaircutSet {
+        staking_pool_id,
+        early_exit_haircut,
+    });
+
+    Ok(())
 }
 
-/// 获取所有活跃的质押池信息
-pub fn get_general_staking(ctx: Context<TerminatedPlanCooldown>) -> Result<Vec<StakingPoolDetail>> {
-    let state = &ctx.accounts.state;
-    state.claims.iter().enumerate()
-        .filter(|(_, pool)| pool.1.status == SoftStakingStatus::Active)
-        .map(|(i, pool)| Ok(StakingPoolDetail {
-            staking_pool_id: i as u64,
-            staking_pool: StakingPool {
-                id: i as u64,
-                min_stake_amount: pool.1.amount,
-                apy: pool.1.apy,
-                duration: pool.1.end_time - pool.1.start_time,
-                status: PoolStatus::Active,
+/// 从金库账户向质押池奖励金库注入奖励
+/// * `staking_pool_id` - 质押池ID
+/// * `amount` - 注入金额
+pub fn fund_rewards(ctx: Context<FundRewards>, staking_pool_id: u64, amount: u64) -> Result<()> {
+    require!(amount > 0, WUSDError::InvalidAmount);
+    ctx.accounts.state.get_staking_pool(staking_pool_id)?;
+
+    token::transfer(
+        CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            Transfer {
+                from: ctx.accounts.treasury.to_account_info(),
+                to: ctx.accounts.reward_vault.to_account_info(),
+                authority: ctx.accounts.funder.to_account_info(),
             },
-        }))
-        .collect()
+        ),
+        amount,
+    )?;
+    ctx.accounts.reward_vault.reload()?;
+
+    emit!(RewardsFunded {
+        staking_pool_id,
+        funder: ctx.accounts.funder.key(),
+        amount,
+        vault_balance: ctx.accounts.reward_vault.amount,
+    });
+
+    Ok(())
+}
+
+/// 设置质押池的奖励发放计划，修改前先按旧计划结算累计奖励
+/// * `staking_pool_id` - 质押池ID
+/// * `emission_rate` - 整个质押池每秒最多发放的奖励，0 表示只按 APY 发放
+/// * `emission_start` - 发放开始时间
+/// * `emission_end` - 发放结束时间，0 表示不设结束时间
+pub fn set_emission_schedule(
+    ctx: Context<StakingPoolAccounts>,
+    staking_pool_id: u64,
+    emission_rate: u64,
+    emission_start: i64,
+    emission_end: i64,
+) -> Result<()> {
+    require!(
+        emission_start >= 0 && (emission_end == 0 || emission_end > emission_start),
+        WUSDError::InvalidInput
+    );
+
+    let now = Clock::get()?.unix_timestamp;
+    let pool = ctx.accounts.state.get_staking_pool_mut(staking_pool_id)?;
+    pool.update_rewards(now)?;
+    pool.emission_rate = emission_rate;
+    pool.emission_start = emission_start;
+    pool.emission_end = emission_end;
+
+    emit!(EmissionScheduleSet {
+        staking_pool_id,
+        emission_rate,
+        emission_start,
+        emission_end,
+    });
+
+    Ok(())
 }
 
-/// 获取指定质押池的详细信息
-pub fn get_staking_pool_details(ctx: Context<TerminatedPlanCooldown>, staking_pool_id: u64) -> Result<StakingPool> {
-    let state = &ctx.accounts.state;
-    require!(staking_pool_id < state.staking_pools.len() as u64, WUSDError::InvalidPoolId);
-    Ok(state.staking_pools[staking_pool_id as usize].clone())
+/// 查询质押池奖励金库按当前发放速率剩余的可支撑秒数
+/// * `staking_pool_id` - 质押池ID
+pub fn get_reward_runway(ctx: Context<RewardRunway>, staking_pool_id: u64) -> Result<u64> {
+    let now = Clock::get()?.unix_timestamp;
+    let mut pool = ctx.accounts.state.get_staking_pool(staking_pool_id)?;
+    pool.update_rewards(now)?;
+    Ok(pool.runway(now, ctx.accounts.reward_vault.amount))
 }
 
-/// 获取用户的所有质押计划
-pub fn get_user_staking_plans(ctx: Context<TerminatedPlanCooldown>, user: Pubkey) -> Result<Vec<StakingPlanDetail>> {
-    let state = &ctx.accounts.state;
-    let mut user_plans = Vec::new();
-    
-    for (i, claim) in state.claims.iter().enumerate() {
-        if claim.1.owner == user {
-            user_plans.push(StakingPlanDetail {
-                staking_plan_id: i as u64,
-                staking_plan: StakingPlan {
-                    min_stake_amount: claim.1.amount,
-                    apy: claim.1.apy,
-                    duration: claim.1.end_time - claim.1.start_time,
-                    status: PoolStatus::Active,
+/// 设置仓位是否允许自动复投
+/// * `enabled` - 是否开启
+pub fn set_auto_compound(ctx: Context<SetAutoCompound>, enabled: bool) -> Result<()> {
+    let stake_account = &mut ctx.accounts.stake_account;
+    stake_account.auto_compound = enabled;
+
+    emit!(AutoCompoundSet {
+        user: ctx.accounts.user.key(),
+        position_id: stake_account.position_id,
+        enabled,
+    });
+
+    Ok(())
+}
+
+/// 设置复投小费比例
+/// * `tip_bps` - 小费比例（基点），不超过 `MAX_COMPOUND_TIP_BPS`
+pub fn set_compound_tip(ctx: Context<StakingPoolAccounts>, tip_bps: u64) -> Result<()> {
+    require!(tip_bps <= MAX_COMPOUND_TIP_BPS, WUSDError::InvalidInput);
+    ctx.accounts.state.staking_config.compound_tip_bps = tip_bps;
+
+    emit!(CompoundTipSet { tip_bps });
+
+    Ok(())
+}
+
+/// 将开启自动复投的仓位的已累计奖励转入本金，任何人都可以调用
+/// 奖励从奖励金库转入质押金库，调用者按 `compound_tip_bps` 从奖励中获得小费
+pub fn compound(ctx: Context<Compound>) -> Result<()> {
+    let now = Clock::get()?.unix_timestamp;
+    let stake_account = &mut ctx.accounts.stake_account;
+    require!(stake_account.auto_compound, WUSDError::InvalidStakingStatus);
+    require!(stake_account.status == StakingStatus::Active, WUSDError::InvalidStakingStatus);
+
+    let state = &mut ctx.accounts.state;
+    let tip_bps = state.staking_config.compound_tip_bps;
+    let pool = state.get_staking_pool_mut(stake_account.staking_pool_id)?;
+    pool.update_rewards(now)?;
+    stake_account.settle_rewards(pool)?;
+
+    let rewards = stake_account.stake_info.rewards_earned;
+    require!(rewards > 0, WUSDError::NoRewardsToClaim);
+    require!(
+        ctx.accounts.reward_vault.amount >= rewards,
+        WUSDError::InsufficientRewardBalance
+    );
+    let tip = ((rewards as u128) * (tip_bps as u128) / 10_000) as u64;
+    let compounded = rewards - tip;
+
+    // 本金增加后重置累计奖励基线
+    stake_account.stake_info.amount = stake_account.stake_info.amount
+        .checked_add(compounded)
+        .ok_or(WUSDError::MathOverflow)?;
+    stake_account.stake_info.rewards_earned = 0;
+    stake_account.stake_info.rewards_paid = stake_account.stake_info.rewards_paid
+        .checked_add(rewards)
+        .ok_or(WUSDError::MathOverflow)?;
+    stake_account.stake_info.reward_debt = pool.accumulated_rewards(stake_account.stake_info.amount)?;
+    stake_account.time_info.last_update_time = now;
+    pool.release_rewards(rewards);
+    pool.stake_in(compounded, stake_account.stake_info.apy_multiplier)?;
+
+    // 与质押相同，复投后的本金也须有奖励覆盖；奖励和小费转出后金库余额减少 `rewards`
+    require!(
+        pool.projected_obligations(now)? <= (ctx.accounts.reward_vault.amount - rewards) as u128,
+        WUSDError::InsufficientRewardBalance
+    );
+    state.staking_config.total_staked = state.staking_config.total_staked
+        .checked_add(compounded)
+        .ok_or(WUSDError::MathOverflow)?;
+    state.staking_config.last_update_time = now;
+
+    let signer_seeds: &[&[&[u8]]] = &[&[b"state".as_ref(), &[ctx.bumps["state"]]]];
+    if compounded > 0 {
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.reward_vault.to_account_info(),
+                    to: ctx.accounts.stake_vault.to_account_info(),
+                    authority: ctx.accounts.state.to_account_info(),
                 },
-            });
-        }
+                signer_seeds,
+            ),
+            compounded,
+        )?;
     }
-    
-    Ok(user_plans)
+    if tip > 0 {
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.reward_vault.to_account_info(),
+                    to: ctx.accounts.cranker_wusd.to_account_info(),
+                    authority: ctx.accounts.state.to_account_info(),
+                },
+                signer_seeds,
+            ),
+            tip,
+        )?;
+    }
+
+    let stake_account = &ctx.accounts.stake_account;
+    emit!(CompoundEvent {
+        user: stake_account.owner,
+        position_id: stake_account.position_id,
+        cranker: ctx.accounts.cranker.key(),
+        compounded,
+        tip,
+        new_principal: stake_account.stake_info.amount,
+        compounded_at: now,
+    });
+
+    Ok(())
 }
 
-/// 更新质押池时长
-pub fn update_pool_duration(
-    ctx: Context<TerminatedPlanCooldown>,
-    pool_id: u64,
-    new_duration: i64,
+/// 创建质押池，编号按创建顺序递增
+/// * `apy` - 年化收益率，1_000_000 表示 100%
+/// * `duration` - 锁定期（秒），0 表示随时可提取
+/// * `min_stake_amount` - 最小质押金额
+pub fn create_staking_pool(
+    ctx: Context<StakingPoolAccounts>,
+    apy: u64,
+    duration: i64,
+    min_stake_amount: u64,
 ) -> Result<()> {
-    require!(new_duration > 0, WUSDError::InvalidLockDuration);
-    
+    require!(duration >= 0, WUSDError::InvalidLockDuration);
+
+    let now = Clock::get()?.unix_timestamp;
     let state = &mut ctx.accounts.state;
-    if let Some(pool) = state.staking_pools.iter_mut().find(|p| p.id == pool_id) {
-        pool.duration = new_duration;
-        Ok(())
-    } else {
-        err!(WUSDError::InvalidPoolId)
-    }
+    let id = state.staking_config.total_staking_plans;
+    require!((id as usize) < MAX_STAKING_POOLS, WUSDError::InvalidPoolId);
+
+    state.staking_pools[id as usize] = StakingPool {
+        id,
+        apy,
+        duration,
+        min_stake_amount,
+        status: PoolStatus::Active,
+        last_update_time: now,
+        ..Default::default()
+    };
+    state.staking_config.total_staking_plans = id + 1;
+
+    emit!(StakingPoolCreated {
+        staking_pool_id: id,
+        apy,
+        duration,
+        min_stake_amount,
+    });
+
+    Ok(())
+}
+
+/// 更新质押池参数，修改前先按旧 APY 结算累计奖励，已有质押的锁定期不受影响
+/// * `staking_pool_id` - 质押池ID
+/// * `apy` - 年化收益率
+/// * `duration` - 锁定期（秒）
+/// * `min_stake_amount` - 最小质押金额
+pub fn update_staking_pool(
+    ctx: Context<StakingPoolAccounts>,
+    staking_pool_id: u64,
+    apy: u64,
+    duration: i64,
+    min_stake_amount: u64,
+) -> Result<()> {
+    require!(duration >= 0, WUSDError::InvalidLockDuration);
+
+    let now = Clock::get()?.unix_timestamp;
+    let pool = ctx.accounts.state.get_staking_pool_mut(staking_pool_id)?;
+    require!(pool.status != PoolStatus::Closed, WUSDError::InvalidPoolStatus);
+    pool.update_rewards(now)?;
+    pool.apy = apy;
+    pool.duration = duration;
+    pool.min_stake_amount = min_stake_amount;
+
+    emit!(StakingPoolUpdated {
+        staking_pool_id,
+        apy,
+        duration,
+        min_stake_amount,
+    });
+
+    Ok(())
+}
+
+/// 设置质押池状态，暂停后不能新增质押，关闭后停止累计奖励并解除锁定，且不能重新开启
+/// * `staking_pool_id` - 质押池ID
+/// * `status` - 新状态
+pub fn set_pool_status(
+    ctx: Context<StakingPoolAccounts>,
+    staking_pool_id: u64,
+    status: PoolStatus,
+) -> Result<()> {
+    let now = Clock::get()?.unix_timestamp;
+    let pool = ctx.accounts.state.get_staking_pool_mut(staking_pool_id)?;
+    require!(pool.status != PoolStatus::Closed, WUSDError::InvalidPoolStatus);
+    pool.update_rewards(now)?;
+    let old_status = pool.status;
+    pool.status = status;
+
+    emit!(StakingPoolStatusChanged {
+        staking_pool_id,
+        old_status,
+        new_status: status,
+    });
+
+    Ok(())
+}
+
+/// 质押指令的账户参数
+#[derive(Accounts)]
+#[instruction(amount: u64, staking_pool_id: u64)]
+pub struct Stake<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"user_stake", user.key().as_ref()],
+        bump
+    )]
+    pub user_stake: Box<Account<'info, UserStakeState>>,
+
+    /// 新的质押仓位，按用户仓位计数器编号
+    #[account(
+        init,
+        payer = user,
+        space = 8 + StakeAccount::LEN,
+        seeds = [
+            b"stake_account",
+            user.key().as_ref(),
+            user_stake.position_count.to_le_bytes().as_ref()
+        ],
+        bump
+    )]
+    pub stake_account: Box<Account<'info, StakeAccount>>,
+
+    #[account(
+        mut,
+        seeds = [b"state"],
+        bump,
+        constraint = !state.paused @ WUSDError::ContractPaused
+    )]
+    pub state: Box<Account<'info, StateAccount>>,
+
+    #[account(
+        constraint = wusd_mint.key() == state.token_config.wusd_mint @ WUSDError::InvalidAddress
+    )]
+    pub wusd_mint: Box<Account<'info, Mint>>,
+
+    #[account(
+        mut,
+        constraint = user_wusd.owner == user.key() @ WUSDError::InvalidOwner,
+        constraint = user_wusd.mint == wusd_mint.key() @ WUSDError::InvalidAddress
+    )]
+    pub user_wusd: Box<Account<'info, TokenAccount>>,
+
+    /// 质押金库，由状态账户 PDA 控制
+    #[account(
+        init_if_needed,
+        payer = user,
+        seeds = [b"stake_vault", wusd_mint.key().as_ref()],
+        bump,
+        token::mint = wusd_mint,
+        token::authority = state
+    )]
+    pub stake_vault: Box<Account<'info, TokenAccount>>,
+
+    /// 质押池奖励金库，由状态账户 PDA 控制
+    #[account(
+        init_if_needed,
+        payer = user,
+        seeds = [b"reward_vault", staking_pool_id.to_le_bytes().as_ref()],
+        bump,
+        token::mint = wusd_mint,
+        token::authority = state
+    )]
+    pub reward_vault: Box<Account<'info, TokenAccount>>,
+
+    /// CHECK: 质押池 APY 档位表，未配置档位时为空账户
+    #[account(
+        seeds = [b"pool_tiers", staking_pool_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub pool_tiers: UncheckedAccount<'info>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+/// 质押池管理指令的账户参数
+#[derive(Accounts, Clone)]
+pub struct StakingPoolAccounts<'info> {
+    pub authority: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"state"],
+        bump,
+        constraint = state.authority == authority.key() @ WUSDError::Unauthorized
+    )]
+    pub state: Box<Account<'info, StateAccount>>,
+}
+
+/// 设置 APY 档位表的账户参数
+#[derive(Accounts)]
+#[instruction(staking_pool_id: u64)]
+pub struct SetPoolTiers<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        seeds = [b"state"],
+        bump,
+        constraint = state.authority == authority.key() @ WUSDError::Unauthorized
+    )]
+    pub state: Box<Account<'info, StateAccount>>,
+
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = 8 + PoolTierTable::LEN,
+        seeds = [b"pool_tiers", staking_pool_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub pool_tiers: Box<Account<'info, PoolTierTable>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// 延长锁定期的账户参数
+#[derive(Accounts)]
+pub struct ExtendLock<'info> {
+    pub user: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"stake_account",
+            user.key().as_ref(),
+            stake_account.position_id.to_le_bytes().as_ref()
+        ],
+        bump,
+        constraint = stake_account.owner == user.key()
+    )]
+    pub stake_account: Box<Account<'info, StakeAccount>>,
+
+    #[account(
+        mut,
+        seeds = [b"state"],
+        bump,
+        constraint = !state.paused @ WUSDError::ContractPaused
+    )]
+    pub state: Box<Account<'info, StateAccount>>,
+
+    /// CHECK: 质押池 APY 档位表，未配置档位时为空账户
+    #[account(
+        seeds = [b"pool_tiers", stake_account.staking_pool_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub pool_tiers: UncheckedAccount<'info>,
+}
+
+/// 注入奖励的账户参数
+#[derive(Accounts)]
+#[instruction(staking_pool_id: u64)]
+pub struct FundRewards<'info> {
+    #[account(mut)]
+    pub funder: Signer<'info>,
+
+    #[account(
+        seeds = [b"state"],
+        bump
+    )]
+    pub state: Box<Account<'info, StateAccount>>,
+
+    #[account(
+        constraint = wusd_mint.key() == state.token_config.wusd_mint @ WUSDError::InvalidAddress
+    )]
+    pub wusd_mint: Box<Account<'info, Mint>>,
+
+    /// 资金来源的金库账户，由其所有者签名转出
+    #[account(
+        mut,
+        constraint = treasury.key() == state.token_config.treasury @ WUSDError::InvalidAddress,
+        constraint = treasury.mint == wusd_mint.key() @ WUSDError::InvalidAddress
+    )]
+    pub treasury: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        init_if_needed,
+        payer = funder,
+        seeds = [b"reward_vault", staking_pool_id.to_le_bytes().as_ref()],
+        bump,
+        token::mint = wusd_mint,
+        token::authority = state
+    )]
+    pub reward_vault: Box<Account<'info, TokenAccount>>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+/// 查询奖励剩余时长的账户参数
+#[derive(Accounts)]
+#[instruction(staking_pool_id: u64)]
+pub struct RewardRunway<'info> {
+    #[account(
+        seeds = [b"state"],
+        bump
+    )]
+    pub state: Box<Account<'info, StateAccount>>,
+
+    #[account(
+        seeds = [b"reward_vault", staking_pool_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub reward_vault: Box<Account<'info, TokenAccount>>,
 }
 
-/// 质押指令的账户参数
+/// 申请解除质押的账户参数
 #[derive(Accounts)]
-pub struct Stake<'info> {
-    #[account(mut)]
+pub struct RequestUnstake<'info> {
     pub user: Signer<'info>,
 
     #[account(
-        init_if_needed,
-        payer = user,
-        space = 8 + StakeAccount::LEN,
-        seeds = [b"stake_account", user.key().as_ref()],
-        bump
+        mut,
+        seeds = [
+            b"stake_account",
+            user.key().as_ref(),
+            stake_account.position_id.to_le_bytes().as_ref()
+        ],
+        bump,
+        constraint = stake_account.owner == user.key()
     )]
     pub stake_account: Box<Account<'info, StakeAccount>>,
 
@@ -325,16 +1125,42 @@ pub struct Stake<'info> {
         constraint = !state.paused @ WUSDError::ContractPaused
     )]
     pub state: Box<Account<'info, StateAccount>>,
+}
 
-    pub system_program: Program<'info, System>,
+/// 设置自动复投的账户参数
+#[derive(Accounts)]
+pub struct SetAutoCompound<'info> {
+    pub user: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"stake_account",
+            user.key().as_ref(),
+            stake_account.position_id.to_le_bytes().as_ref()
+        ],
+        bump,
+        constraint = stake_account.owner == user.key()
+    )]
+    pub stake_account: Box<Account<'info, StakeAccount>>,
 }
 
-/// 质押池管理指令的账户参数
-#[derive(Accounts, Clone)]
-pub struct StakingPoolAccounts<'info> {
-    #[account(mut)]
-    pub authority: Signer<'info>,
-    
+/// 复投指令的账户参数
+#[derive(Accounts)]
+pub struct Compound<'info> {
+    pub cranker: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"stake_account",
+            stake_account.owner.as_ref(),
+            stake_account.position_id.to_le_bytes().as_ref()
+        ],
+        bump
+    )]
+    pub stake_account: Box<Account<'info, StakeAccount>>,
+
     #[account(
         mut,
         seeds = [b"state"],
@@ -342,11 +1168,33 @@ pub struct StakingPoolAccounts<'info> {
         constraint = !state.paused @ WUSDError::ContractPaused
     )]
     pub state: Box<Account<'info, StateAccount>>,
-    
-    #[account(mut)]
+
+    #[account(
+        constraint = wusd_mint.key() == state.token_config.wusd_mint @ WUSDError::InvalidAddress
+    )]
+    pub wusd_mint: Box<Account<'info, Mint>>,
+
+    #[account(
+        mut,
+        seeds = [b"stake_vault", wusd_mint.key().as_ref()],
+        bump
+    )]
     pub stake_vault: Box<Account<'info, TokenAccount>>,
-    
-    pub system_program: Program<'info, System>,
+
+    #[account(
+        mut,
+        seeds = [b"reward_vault", stake_account.staking_pool_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub reward_vault: Box<Account<'info, TokenAccount>>,
+
+    /// 调用者接收小费的代币账户
+    #[account(
+        mut,
+        constraint = cranker_wusd.mint == wusd_mint.key() @ WUSDError::InvalidAddress
+    )]
+    pub cranker_wusd: Box<Account<'info, TokenAccount>>,
+
     pub token_program: Program<'info, Token>,
 }
 
@@ -357,18 +1205,16 @@ pub struct Claim<'info> {
 
     #[account(
         mut,
-        seeds = [b"stake_account", user.key().as_ref()],
+        seeds = [
+            b"stake_account",
+            user.key().as_ref(),
+            stake_account.position_id.to_le_bytes().as_ref()
+        ],
         bump,
         constraint = stake_account.owner == user.key()
     )]
     pub stake_account: Box<Account<'info, StakeAccount>>,
     
-    #[account(mut)]
-    pub user_wusd: Box<Account<'info, TokenAccount>>,
-    
-    #[account(mut)]
-    pub wusd_mint: Box<Account<'info, Mint>>,
-    
     #[account(
         mut,
         seeds = [b"state"],
@@ -376,7 +1222,27 @@ pub struct Claim<'info> {
         constraint = !state.paused @ WUSDError::ContractPaused
     )]
     pub state: Box<Account<'info, StateAccount>>,
-    
+
+    #[account(
+        constraint = wusd_mint.key() == state.token_config.wusd_mint @ WUSDError::InvalidAddress
+    )]
+    pub wusd_mint: Box<Account<'info, Mint>>,
+
+    #[account(
+        mut,
+        constraint = user_wusd.owner == user.key() @ WUSDError::InvalidOwner,
+        constraint = user_wusd.mint == wusd_mint.key() @ WUSDError::InvalidAddress
+    )]
+    pub user_wusd: Box<Account<'info, TokenAccount>>,
+
+    /// 质押池奖励金库
+    #[account(
+        mut,
+        seeds = [b"reward_vault", stake_account.staking_pool_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub reward_vault: Box<Account<'info, TokenAccount>>,
+
     pub token_program: Program<'info, Token>,
 }
 
@@ -388,18 +1254,78 @@ pub struct Withdraw<'info> {
 
     #[account(
         mut,
-        seeds = [b"stake_account", user.key().as_ref()],
+        seeds = [b"user_stake", user.key().as_ref()],
+        bump
+    )]
+    pub user_stake: Box<Account<'info, UserStakeState>>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"stake_account",
+            user.key().as_ref(),
+            stake_account.position_id.to_le_bytes().as_ref()
+        ],
         bump,
         constraint = stake_account.owner == user.key()
     )]
     pub stake_account: Box<Account<'info, StakeAccount>>,
     
-    #[account(mut)]
+    #[account(
+        mut,
+        seeds = [b"state"],
+        bump,
+        constraint = !state.paused @ WUSDError::ContractPaused
+    )]
+    pub state: Box<Account<'info, StateAccount>>,
+
+    #[account(
+        constraint = wusd_mint.key() == state.token_config.wusd_mint @ WUSDError::InvalidAddress
+    )]
+    pub wusd_mint: Box<Account<'info, Mint>>,
+
+    #[account(
+        mut,
+        constraint = user_wusd.owner == user.key() @ WUSDError::InvalidOwner,
+        constraint = user_wusd.mint == wusd_mint.key() @ WUSDError::InvalidAddress
+    )]
     pub user_wusd: Box<Account<'info, TokenAccount>>,
-    
-    #[account(mut)]
+
+    #[account(
+        mut,
+        seeds = [b"stake_vault", wusd_mint.key().as_ref()],
+        bump
+    )]
     pub stake_vault: Box<Account<'info, TokenAccount>>,
-    
+
+    /// 紧急提取罚金的接收账户
+    #[account(
+        mut,
+        constraint = treasury.key() == state.token_config.treasury @ WUSDError::InvalidAddress,
+        constraint = treasury.mint == wusd_mint.key() @ WUSDError::InvalidAddress
+    )]
+    pub treasury: Box<Account<'info, TokenAccount>>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+/// 提前退出的账户参数
+#[derive(Accounts)]
+pub struct EarlyExit<'info> {
+    pub user: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"stake_account",
+            user.key().as_ref(),
+            stake_account.position_id.to_le_bytes().as_ref()
+        ],
+        bump,
+        constraint = stake_account.owner == user.key()
+    )]
+    pub stake_account: Box<Account<'info, StakeAccount>>,
+
     #[account(
         mut,
         seeds = [b"state"],
@@ -407,7 +1333,34 @@ pub struct Withdraw<'info> {
         constraint = !state.paused @ WUSDError::ContractPaused
     )]
     pub state: Box<Account<'info, StateAccount>>,
-    
+
+    #[account(
+        constraint = wusd_mint.key() == state.token_config.wusd_mint @ WUSDError::InvalidAddress
+    )]
+    pub wusd_mint: Box<Account<'info, Mint>>,
+
+    #[account(
+        mut,
+        constraint = user_wusd.owner == user.key() @ WUSDError::InvalidOwner,
+        constraint = user_wusd.mint == wusd_mint.key() @ WUSDError::InvalidAddress
+    )]
+    pub user_wusd: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        seeds = [b"stake_vault", wusd_mint.key().as_ref()],
+        bump
+    )]
+    pub stake_vault: Box<Account<'info, TokenAccount>>,
+
+    /// 质押池奖励金库，接收没收和扣除的金额
+    #[account(
+        mut,
+        seeds = [b"reward_vault", stake_account.staking_pool_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub reward_vault: Box<Account<'info, TokenAccount>>,
+
     pub token_program: Program<'info, Token>,
 }
 
@@ -415,9 +1368,13 @@ pub struct Withdraw<'info> {
 #[event]
 pub struct WithdrawEvent {
     pub user: Pubkey,
+    pub position_id: u64,
     pub staking_pool_id: u64,
     pub amount: u64,
+    /// 提取后仍可领取的奖励，紧急提取时为 0
     pub rewards: u64,
+    /// 紧急提取扣除的罚金
+    pub penalty: u64,
     pub withdraw_time: i64,
     pub is_emergency: bool,
 }
@@ -439,6 +1396,7 @@ pub enum ClaimType {
     Unclaimed = 0,
     Claimed = 1,
     Emergency = 2,
+    EarlyExit = 3,
 }
 
 #[derive(AnchorSerialize, AnchorDeserialize, Clone)]
@@ -446,7 +1404,13 @@ pub struct StakeInfo {
     pub amount: u64,
     pub apy: u64,
     pub rewards_earned: u64,
+    /// 已领取或已复投的奖励总额
+    pub rewards_paid: u64,
     pub apy_tier: u8,
+    /// 档位对应的 APY 倍数，按 `TIER_MULTIPLIER_SCALE` 表示
+    pub apy_multiplier: u64,
+    /// 已结算至 `rewards_earned` 的累计奖励基线
+    pub reward_debt: u128,
 }
 
 #[derive(AnchorSerialize, AnchorDeserialize, Clone)]
@@ -457,73 +1421,75 @@ pub struct TimeInfo {
     pub last_update_time: i64,
 }
 
-/// 质押账户结构体，存储用户的质押信息
+/// 用户质押账户，记录仓位计数器和紧急提取冷却期
+#[account]
+pub struct UserStakeState {
+    pub owner: Pubkey,
+    /// 已创建的仓位数量，新仓位以此为编号
+    pub position_count: u64,
+    pub emergency_cooldown: i64,
+}
+
+impl UserStakeState {
+    pub const LEN: usize = 32 + 8 + 8;
+}
+
+/// 质押仓位结构体，存储用户单笔质押的信息
 #[account]
 pub struct StakeAccount {
     pub owner: Pubkey,
+    /// 仓位编号
+    pub position_id: u64,
     pub staking_pool_id: u64,
     pub stake_info: Box<StakeInfo>,
     pub time_info: Box<TimeInfo>,
     pub status: StakingStatus,
     pub claim_type: ClaimType,
     pub emergency_cooldown: i64,
+    /// 是否允许任何人将奖励复投为本金
+    pub auto_compound: bool,
 }
 
 impl StakeAccount {
-    pub const SIZE: usize = 32 + 8 + 32 + 32 + 1 + 1 + 8;
-} 
-
-/// 设置已终止质押计划的冷却期的账户参数
-#[derive(Accounts)]
-pub struct TerminatedPlanCooldown<'info> {
-    #[account(mut)]
-    pub authority: Signer<'info>,
-    
-    #[account(
-        mut,
-        seeds = [b"state"],
-        bump,
-        constraint = !state.paused @ WUSDError::ContractPaused,
-        constraint = state.authority == authority.key() @ WUSDError::Unauthorized
-    )]
-    pub state: Box<Account<'info, StateAccount>>,
-}
-
-/// 质押池详情结构体
-#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
-pub struct StakingPoolDetail {
-    pub staking_pool_id: u64,
-    pub staking_pool: StakingPool,
-}
-
-/// 质押计划详情结构体
-#[derive(AnchorSerialize, AnchorDeserialize, Clone)] 
-pub struct StakingPlan {
-    pub min_stake_amount: u64,
-    pub apy: u64,
-    pub duration: i64,
-    pub status: PoolStatus,
-}
-
-#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
-pub struct StakingPlanDetail {
-    pub staking_plan_id: u64,
-    pub staking_plan: StakingPlan,
+    /// 按质押池当前累计值将新增奖励按档位倍数计入 `rewards_earned`，调用前须先更新质押池
+    /// 非活跃的仓位不再累计奖励
+    pub fn settle_rewards(&mut self, pool: &StakingPool) -> Result<()> {
+        if self.status != StakingStatus::Active {
+            return Ok(());
+        }
+        let accumulated = pool.accumulated_rewards(self.stake_info.amount)?;
+        let pending = apply_tier_multiplier(
+            accumulated.saturating_sub(self.stake_info.reward_debt),
+            self.stake_info.apy_multiplier,
+        );
+        self.stake_info.rewards_earned = u64::try_from(pending)
+            .ok()
+            .and_then(|pending| self.stake_info.rewards_earned.checked_add(pending))
+            .ok_or(WUSDError::MathOverflow)?;
+        self.stake_info.reward_debt = accumulated;
+        Ok(())
+    }
 }
 
 impl StakeAccount {
     pub const LEN: usize = 32 + // owner
+        8 + // position_id
         8 + // amount
         8 + // staking_pool_id
         8 + // apy
         8 + // start_time
         8 + // end_time
         8 + // claimable_timestamp
+        8 + // last_update_time
         8 + // rewards_earned
+        8 + // rewards_paid
+        16 + // reward_debt
         1 + // status
         1 + // claim_type
         1 + // apy_tier
-        8; // emergency_cooldown
+        8 + // apy_multiplier
+        8 + // emergency_cooldown
+        1; // auto_compound
 }
 
 /// 质押事件，记录质押操作的详细信息
@@ -539,9 +1505,146 @@ pub struct StakeEvent {
     pub staked_at: i64,
 }
 
+/// 质押池创建事件
+#[event]
+pub struct StakingPoolCreated {
+    pub staking_pool_id: u64,
+    pub apy: u64,
+    pub duration: i64,
+    pub min_stake_amount: u64,
+}
+
+/// 质押池参数更新事件
+#[event]
+pub struct StakingPoolUpdated {
+    pub staking_pool_id: u64,
+    pub apy: u64,
+    pub duration: i64,
+    pub min_stake_amount: u64,
+}
+
+/// 质押池状态变更事件
+#[event]
+pub struct StakingPoolStatusChanged {
+    pub staking_pool_id: u64,
+    pub old_status: PoolStatus,
+    pub new_status: PoolStatus,
+}
+
+/// APY 档位表更新事件
+#[event]
+pub struct PoolTiersUpdated {
+    pub staking_pool_id: u64,
+    pub tiers: Vec<ApyTier>,
+}
+
+/// 延长锁定期事件
+#[event]
+pub struct LockExtended {
+    pub user: Pubkey,
+    pub position_id: u64,
+    pub old_end_time: i64,
+    pub new_end_time: i64,
+    pub apy_tier: u8,
+    pub apy_multiplier: u64,
+}
+
+/// 提前退出事件，记录本金和奖励的完整结算明细
+#[event]
+pub struct EarlyExitEvent {
+    pub user: Pubkey,
+    pub position_id: u64,
+    pub staking_pool_id: u64,
+    /// 退出前的本金
+    pub principal: u64,
+    /// 按剩余锁定比例扣除的本金
+    pub principal_haircut: u64,
+    /// 已领取奖励超出应得部分，从本金扣回
+    pub rewards_clawback: u64,
+    /// 实际退还的本金
+    pub principal_returned: u64,
+    /// 仓位累计获得的全部奖励，含已领取部分
+    pub rewards_earned: u64,
+    /// 没收的未领取奖励
+    pub rewards_forfeited: u64,
+    /// 本次支付的奖励
+    pub rewards_paid: u64,
+    pub time_served: i64,
+    pub lock_duration: i64,
+    pub exit_time: i64,
+}
+
+/// 提前退出本金扣除比例设置事件
+#[event]
+pub struct EarlyExitHaircutSet {
+    pub staking_pool_id: u64,
+    pub early_exit_haircut: u64,
+}
+
+/// 解除质押申请事件
+#[event]
+pub struct UnstakeRequested {
+    pub user: Pubkey,
+    pub position_id: u64,
+    pub amount: u64,
+    pub claimable_timestamp: i64,
+}
+
+/// 解除质押等待期设置事件
+#[event]
+pub struct UnbondingPeriodSet {
+    pub staking_pool_id: u64,
+    pub unbonding_period: i64,
+}
+
+/// 奖励注入事件
+#[event]
+pub struct RewardsFunded {
+    pub staking_pool_id: u64,
+    pub funder: Pubkey,
+    pub amount: u64,
+    pub vault_balance: u64,
+}
+
+/// 奖励发放计划设置事件
+#[event]
+pub struct EmissionScheduleSet {
+    pub staking_pool_id: u64,
+    pub emission_rate: u64,
+    pub emission_start: i64,
+    pub emission_end: i64,
+}
+
+/// 自动复投设置事件
+#[event]
+pub struct AutoCompoundSet {
+    pub user: Pubkey,
+    pub position_id: u64,
+    pub enabled: bool,
+}
+
+/// 复投小费比例设置事件
+#[event]
+pub struct CompoundTipSet {
+    pub tip_bps: u64,
+}
+
+/// 复投事件，记录转入本金的奖励和调用者小费
+#[event]
+pub struct CompoundEvent {
+    pub user: Pubkey,
+    pub position_id: u64,
+    pub cranker: Pubkey,
+    pub compounded: u64,
+    pub tip: u64,
+    pub new_principal: u64,
+    pub compounded_at: i64,
+}
+
 /// 领取奖励事件，记录领取操作的详细信息
 #[event]
 pub struct ClaimEvent {
     pub user: Pubkey,
+    pub position_id: u64,
     pub amount: u64,
 }
\ No newline at end of file
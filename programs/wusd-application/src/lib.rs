@@ -12,6 +12,7 @@ use error::WUSDError;
 use instructions::swap::*; 
 use instructions::stake::*; 
 use instructions::softstake::*; 
+use instructions::migrate::*;
 
 declare_id!("CtvwimRuMvuURzzRbEiXCB6KhXfvkR97R5XqwvwAmH4v");
 
@@ -19,23 +20,10 @@ declare_id!("CtvwimRuMvuURzzRbEiXCB6KhXfvkR97R5XqwvwAmH4v");
 pub mod wusd_application {
     use super::*; 
     pub fn initialize_stake_account(ctx: Context<InitializeStakeAccount>) -> Result<()> {
-        let stake_account = &mut ctx.accounts.stake_account;
-        stake_account.owner = ctx.accounts.user.key();
-        stake_account.staking_pool_id = 0;
-        stake_account.stake_info = Box::new(StakeInfo {
-            amount: 0,
-            apy: 0,
-            rewards_earned: 0,
-            apy_tier: 0,
-        });
-        stake_account.time_info = Box::new(TimeInfo {
-            start_time: Clock::get()?.unix_timestamp,
-            end_time: 0,
-            claimable_timestamp: 0,
-            last_update_time: Clock::get()?.unix_timestamp,
-        });
-        stake_account.status = StakingStatus::Active;
-        stake_account.claim_type = ClaimType::Unclaimed;
+        let user_stake = &mut ctx.accounts.user_stake;
+        user_stake.owner = ctx.accounts.user.key();
+        user_stake.position_count = 0;
+        user_stake.emergency_cooldown = 0;
         Ok(())
     }
 
@@ -58,6 +46,7 @@ pub mod wusd_application {
             emergency_withdraw_penalty: 500_000,
             emergency_cooldown_duration: 24 * 60 * 60,
             total_staking_plans: 0,
+            compound_tip_bps: 0,
         });
         state.paused = false;
         
@@ -66,15 +55,88 @@ pub mod wusd_application {
         state.token_whitelist[2] = (Pubkey::default(), false);
 
         state.exchange_rates[0] = (ctx.accounts.wusd_mint.key(), ctx.accounts.collateral_mint.key(), Rate { input: 1_000_000, output: 1_000_000 });
+        state.version = STATE_VERSION;
         Ok(())
     }
 
+    pub fn migrate_state(ctx: Context<MigrateState>) -> Result<()> {
+        instructions::migrate::migrate_state(ctx)
+    }
+
     pub fn stake(ctx: Context<Stake>, amount: u64, staking_pool_id: u64) -> Result<()> {
         instructions::stake::stake(ctx, amount, staking_pool_id)
     }
 
-    pub fn claim(ctx: Context<Claim>) -> Result<()> {
-        instructions::stake::claim(ctx)
+    pub fn claim(ctx: Context<Claim>, amount: u64) -> Result<()> {
+        instructions::stake::claim(ctx, amount)
+    }
+
+    pub fn withdraw(ctx: Context<Withdraw>, amount: u64) -> Result<()> {
+        instructions::stake::withdraw(ctx, amount, false)
+    }
+
+    pub fn early_exit(ctx: Context<EarlyExit>) -> Result<()> {
+        instructions::stake::early_exit(ctx)
+    }
+
+    pub fn set_early_exit_haircut(ctx: Context<StakingPoolAccounts>, staking_pool_id: u64, early_exit_haircut: u64) -> Result<()> {
+        instructions::stake::set_early_exit_haircut(ctx, staking_pool_id, early_exit_haircut)
+    }
+
+    pub fn request_unstake(ctx: Context<RequestUnstake>) -> Result<()> {
+        instructions::stake::request_unstake(ctx)
+    }
+
+    pub fn set_unbonding_period(ctx: Context<StakingPoolAccounts>, staking_pool_id: u64, unbonding_period: i64) -> Result<()> {
+        instructions::stake::set_unbonding_period(ctx, staking_pool_id, unbonding_period)
+    }
+
+    pub fn emergency_withdraw(ctx: Context<Withdraw>, amount: u64) -> Result<()> {
+        instructions::stake::withdraw(ctx, amount, true)
+    }
+
+    pub fn create_staking_pool(ctx: Context<StakingPoolAccounts>, apy: u64, duration: i64, min_stake_amount: u64) -> Result<()> {
+        instructions::stake::create_staking_pool(ctx, apy, duration, min_stake_amount)
+    }
+
+    pub fn update_staking_pool(ctx: Context<StakingPoolAccounts>, staking_pool_id: u64, apy: u64, duration: i64, min_stake_amount: u64) -> Result<()> {
+        instructions::stake::update_staking_pool(ctx, staking_pool_id, apy, duration, min_stake_amount)
+    }
+
+    pub fn set_pool_status(ctx: Context<StakingPoolAccounts>, staking_pool_id: u64, status: PoolStatus) -> Result<()> {
+        instructions::stake::set_pool_status(ctx, staking_pool_id, status)
+    }
+
+    pub fn set_pool_tiers(ctx: Context<SetPoolTiers>, staking_pool_id: u64, tiers: Vec<ApyTier>) -> Result<()> {
+        instructions::stake::set_pool_tiers(ctx, staking_pool_id, tiers)
+    }
+
+    pub fn extend_lock(ctx: Context<ExtendLock>, new_end_time: i64) -> Result<()> {
+        instructions::stake::extend_lock(ctx, new_end_time)
+    }
+
+    pub fn fund_rewards(ctx: Context<FundRewards>, staking_pool_id: u64, amount: u64) -> Result<()> {
+        instructions::stake::fund_rewards(ctx, staking_pool_id, amount)
+    }
+
+    pub fn set_emission_schedule(ctx: Context<StakingPoolAccounts>, staking_pool_id: u64, emission_rate: u64, emission_start: i64, emission_end: i64) -> Result<()> {
+        instructions::stake::set_emission_schedule(ctx, staking_pool_id, emission_rate, emission_start, emission_end)
+    }
+
+    pub fn get_reward_runway(ctx: Context<RewardRunway>, staking_pool_id: u64) -> Result<u64> {
+        instructions::stake::get_reward_runway(ctx, staking_pool_id)
+    }
+
+    pub fn set_auto_compound(ctx: Context<SetAutoCompound>, enabled: bool) -> Result<()> {
+        instructions::stake::set_auto_compound(ctx, enabled)
+    }
+
+    pub fn set_compound_tip(ctx: Context<StakingPoolAccounts>, tip_bps: u64) -> Result<()> {
+        instructions::stake::set_compound_tip(ctx, tip_bps)
+    }
+
+    pub fn compound(ctx: Context<Compound>) -> Result<()> {
+        instructions::stake::compound(ctx)
     }
 
     pub fn set_pool_address(ctx: Context<PoolAddress>, new_pool_address: Pubkey) -> Result<()> {
@@ -117,8 +179,12 @@ pub mod wusd_application {
         instructions::softstake::soft_stake_handler(ctx, amount, staking_pool_id, access_key)
     }
 
-    pub fn soft_claim(ctx: Context<SoftClaim>) -> Result<()> {
-        instructions::softstake::soft_claim_handler(ctx)
+    pub fn soft_claim(ctx: Context<SoftClaim>, amount: u64) -> Result<()> {
+        instructions::softstake::soft_claim_handler(ctx, amount)
+    }
+
+    pub fn soft_unstake(ctx: Context<SoftUnstake>, amount: u64) -> Result<()> {
+        instructions::softstake::soft_unstake_handler(ctx, amount)
     }
 
     pub fn set_whitelist_token(ctx: Context<SetWhitelistToken>, token_mint: Pubkey, status: bool) -> Result<()> {
@@ -3,23 +3,229 @@ use crate::error::WUSDError;
 use crate::instructions::swap::Rate;
 use crate::instructions::softstake::SoftStakeAccount;
 
+/// 全局状态账户的布局版本，布局变更时递增并在 `migrate_state` 中处理旧版本
+/// 版本 0 为引入奖励累计、发放计划等质押池字段及质押数据奖励累计基数之前的布局
+pub const STATE_VERSION: u8 = 1;
+
+/// 年化收益率精度，1_000_000 表示 100%
+pub const APY_SCALE: u128 = 1_000_000;
+/// 每单位质押累计奖励的定点精度
+pub const REWARD_PRECISION: u128 = 1_000_000_000_000;
+/// 一年的秒数
+pub const SECONDS_PER_YEAR: u128 = 365 * 24 * 60 * 60;
+/// 质押池数量上限
+pub const MAX_STAKING_POOLS: usize = 16;
+/// 每个质押池的 APY 档位数量上限
+pub const MAX_APY_TIERS: usize = 8;
+/// APY 档位倍数精度，10_000 表示 1 倍
+pub const TIER_MULTIPLIER_SCALE: u64 = 10_000;
+/// 复投小费比例上限（基点）
+pub const MAX_COMPOUND_TIP_BPS: u64 = 100;
+/// 奖励金库至少须覆盖的发放时长（秒），适用于无锁定期或锁定期较短的质押池
+pub const MIN_REWARD_HORIZON: i64 = 24 * 60 * 60;
+
 /// 质押池状态枚举
-#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Default)]
 #[repr(u8)]
 pub enum PoolStatus {
+    #[default]
     Active = 0,
     Paused = 1,
     Closed = 2
 }
 
 /// 质押池配置结构体
-#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy)]
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default)]
 pub struct StakingPool {
     pub id: u64,
     pub apy: u64,
     pub duration: i64,
     pub min_stake_amount: u64,
     pub status: PoolStatus,
+    /// 池内仍在累计奖励的质押总额，不含解除质押等待中的部分
+    pub total_staked: u64,
+    /// 每单位质押累计奖励，按 `REWARD_PRECISION` 定点表示
+    pub reward_per_token_stored: u128,
+    /// 累计奖励的最后更新时间
+    pub last_update_time: i64,
+    /// 按 APY 档位倍数加权的质押总额
+    pub total_weighted_staked: u128,
+    /// 已累计但尚未支付的奖励
+    pub rewards_owed: u64,
+    /// 整个质押池每秒最多发放的奖励，0 表示只按 APY 发放
+    pub emission_rate: u64,
+    /// 奖励发放开始时间
+    pub emission_start: i64,
+    /// 奖励发放结束时间，0 表示未设置发放计划
+    pub emission_end: i64,
+    /// 申请解除质押后须等待的秒数
+    pub unbonding_period: i64,
+    /// 提前退出时按剩余锁定比例扣除的本金比例，按 `APY_SCALE` 表示，0 表示不扣除
+    pub early_exit_haircut: u64,
+    /// 池内仓位最晚的锁定结束时间，只增不减，用于估算奖励金库须覆盖的期限
+    pub max_lock_end: i64,
+}
+
+impl StakingPool {
+    /// 按当前 APY 和发放计划将每单位质押累计奖励推进到 `now`，已关闭的质押池不再累计
+    /// 修改 APY、发放计划或状态前须先调用，保证之前的时间段按旧配置结算
+    pub fn update_rewards(&mut self, now: i64) -> Result<()> {
+        let from = self.last_update_time.max(self.emission_start);
+        let to = if self.emission_end > 0 { now.min(self.emission_end) } else { now };
+        if to > from && self.status != PoolStatus::Closed {
+            let elapsed = (to - from) as u128;
+            let mut delta = (self.apy as u128)
+                .checked_mul(elapsed)
+                .and_then(|v| v.checked_mul(REWARD_PRECISION))
+                .ok_or(WUSDError::MathOverflow)?
+                / (SECONDS_PER_YEAR * APY_SCALE);
+            // 按 APY 计算的发放量超过发放速率时，按发放速率均摊到加权质押上
+            if self.emission_rate > 0 && self.total_weighted_staked > 0 {
+                let cap = (self.emission_rate as u128)
+                    .checked_mul(elapsed)
+                    .and_then(|v| v.checked_mul(REWARD_PRECISION))
+                    .ok_or(WUSDError::MathOverflow)?
+                    / self.total_weighted_staked;
+                delta = delta.min(cap);
+            }
+            self.reward_per_token_stored = self.reward_per_token_stored
+                .checked_add(delta)
+                .ok_or(WUSDError::MathOverflow)?;
+            let accrued = self.total_weighted_staked
+                .checked_mul(delta)
+                .ok_or(WUSDError::MathOverflow)?
+                / REWARD_PRECISION;
+            self.rewards_owed = u64::try_from(accrued)
+                .ok()
+                .and_then(|accrued| self.rewards_owed.checked_add(accrued))
+                .ok_or(WUSDError::MathOverflow)?;
+        }
+        self.last_update_time = now;
+        Ok(())
+    }
+
+    /// 计入一笔质押及其档位倍数
+    pub fn stake_in(&mut self, amount: u64, multiplier: u64) -> Result<()> {
+        self.total_staked = self.total_staked
+            .checked_add(amount)
+            .ok_or(WUSDError::MathOverflow)?;
+        self.total_weighted_staked = self.total_weighted_staked
+            .checked_add(weighted_amount(amount, multiplier))
+            .ok_or(WUSDError::MathOverflow)?;
+        Ok(())
+    }
+
+    /// 移出一笔质押及其档位倍数
+    pub fn stake_out(&mut self, amount: u64, multiplier: u64) -> Result<()> {
+        self.total_staked = self.total_staked
+            .checked_sub(amount)
+            .ok_or(WUSDError::MathOverflow)?;
+        self.total_weighted_staked = self.total_weighted_staked
+            .saturating_sub(weighted_amount(amount, multiplier));
+        Ok(())
+    }
+
+    /// 奖励已支付或被放弃，从待付奖励中扣除
+    pub fn release_rewards(&mut self, amount: u64) {
+        self.rewards_owed = self.rewards_owed.saturating_sub(amount);
+    }
+
+    /// 当前加权质押下整个质押池每秒发放的奖励
+    pub fn emission_per_second(&self) -> u128 {
+        let by_apy = self.total_weighted_staked * self.apy as u128 / (SECONDS_PER_YEAR * APY_SCALE);
+        if self.emission_rate > 0 {
+            by_apy.min(self.emission_rate as u128)
+        } else {
+            by_apy
+        }
+    }
+
+    /// 记录仓位的锁定结束时间，延长奖励金库须覆盖的期限
+    pub fn record_lock_end(&mut self, end_time: i64) {
+        self.max_lock_end = self.max_lock_end.max(end_time);
+    }
+
+    /// 预计须支付的奖励总额，包括已累计未支付的部分
+    /// 设置了发放计划时计算到计划结束，否则计算到池内最晚的锁定结束时间，且不少于 `MIN_REWARD_HORIZON`
+    pub fn projected_obligations(&self, now: i64) -> Result<u128> {
+        let start = now.max(self.emission_start);
+        let end = if self.emission_end > 0 {
+            self.emission_end
+        } else {
+            self.max_lock_end.max(now.saturating_add(MIN_REWARD_HORIZON))
+        };
+        let remaining = (end - start).max(0) as u128;
+        self.emission_per_second()
+            .checked_mul(remaining)
+            .and_then(|v| v.checked_add(self.rewards_owed as u128))
+            .ok_or_else(|| WUSDError::MathOverflow.into())
+    }
+
+    /// 奖励金库在当前发放速率下还能支撑的秒数，不超过发放计划的结束时间
+    /// 无发放时返回 `u64::MAX`
+    pub fn runway(&self, now: i64, vault_balance: u64) -> u64 {
+        let available = vault_balance.saturating_sub(self.rewards_owed) as u128;
+        let rate = self.emission_per_second();
+        let mut runway = available.checked_div(rate).unwrap_or(u64::MAX as u128);
+        if self.emission_end > 0 {
+            runway = runway.min((self.emission_end - now.max(self.emission_start)).max(0) as u128);
+        }
+        runway.min(u64::MAX as u128) as u64
+    }
+
+    /// 按当前累计值计算某一质押金额对应的累计奖励
+    pub fn accumulated_rewards(&self, amount: u64) -> Result<u128> {
+        Ok((amount as u128)
+            .checked_mul(self.reward_per_token_stored)
+            .ok_or(WUSDError::MathOverflow)?
+            / REWARD_PRECISION)
+    }
+}
+
+/// APY 档位，锁定期和质押金额同时达到门槛时适用
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default)]
+pub struct ApyTier {
+    /// 最短锁定期（秒）
+    pub min_lock_duration: i64,
+    /// 最小质押金额
+    pub min_amount: u64,
+    /// APY 倍数，按 `TIER_MULTIPLIER_SCALE` 表示
+    pub multiplier: u64,
+}
+
+/// 质押池 APY 档位表
+#[account]
+pub struct PoolTierTable {
+    pub staking_pool_id: u64,
+    pub tier_count: u8,
+    pub tiers: [ApyTier; MAX_APY_TIERS],
+}
+
+/// 按档位倍数加权的质押金额
+pub fn weighted_amount(amount: u64, multiplier: u64) -> u128 {
+    (amount as u128) * (multiplier as u128) / TIER_MULTIPLIER_SCALE as u128
+}
+
+impl PoolTierTable {
+    pub const LEN: usize = 8 + 1 + (8 + 8 + 8) * MAX_APY_TIERS;
+
+    /// 选出满足条件的倍数最高的档位，返回档位编号（从 1 开始，0 表示无加成）和倍数
+    pub fn select(&self, amount: u64, lock_duration: i64) -> (u8, u64) {
+        self.tiers[..self.tier_count as usize].iter()
+            .enumerate()
+            .filter(|(_, tier)| lock_duration >= tier.min_lock_duration && amount >= tier.min_amount)
+            .map(|(i, tier)| (i as u8 + 1, tier.multiplier))
+            .fold((0, TIER_MULTIPLIER_SCALE), |best, tier| if tier.1 > best.1 { tier } else { best })
+    }
+
+    /// 从可能未创建的档位表账户中选出档位，未配置档位表时无加成
+    pub fn select_from(info: &AccountInfo, amount: u64, lock_duration: i64) -> Result<(u8, u64)> {
+        if info.data_is_empty() {
+            return Ok((0, TIER_MULTIPLIER_SCALE));
+        }
+        let table = Account::<PoolTierTable>::try_from(info)?;
+        Ok(table.select(amount, lock_duration))
+    }
 }
 
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy)]
@@ -47,6 +253,8 @@ pub struct StakingConfig {
     pub emergency_withdraw_penalty: u64,
     pub emergency_cooldown_duration: i64,
     pub total_staking_plans: u64,
+    /// 复投时从奖励中支付给调用者的小费比例（基点）
+    pub compound_tip_bps: u64,
 }
 
 #[account]
@@ -57,11 +265,13 @@ pub struct StateAccount {
     pub paused: bool,
     pub token_whitelist: Box<[(Pubkey, bool); 3]>,
     pub exchange_rates: Box<[(Pubkey, Pubkey, Rate); 3]>,
-    pub staking_pools: Box<[StakingPool; 16]>,
+    pub staking_pools: Box<[StakingPool; MAX_STAKING_POOLS]>,
     pub claims: Box<[(Pubkey, SoftStakeAccount); 16]>,
     pub claims_count: u32,
     pub owners: Box<[Pubkey; 5]>,
-    pub pool_address: Pubkey
+    pub pool_address: Pubkey,
+    /// 账户布局版本，见 `STATE_VERSION`
+    pub version: u8,
 }
 
 impl StateAccount {
@@ -77,6 +287,7 @@ impl StateAccount {
         8 + // last_update_time
         8 + // emergency_withdraw_penalty
         8 + // emergency_cooldown_duration
+        8 + // compound_tip_bps
         1 + // collateral_decimals
         1 + // wusd_decimals
         (32 + 1) * 3 + // token_whitelist
@@ -84,9 +295,10 @@ impl StateAccount {
         8 + // total_staking_plans
         32 * 5 + // owners
         32 + // pool_address
-        (32 + 128) * 16 + // claims
+        (32 + 145) * 16 + // claims
         4 + // claims_count
-        (8 + 8 + 8 + 8 + 1) * 16; // staking_pools
+        (8 + 8 + 8 + 8 + 1 + 8 + 16 + 8 + 16 + 8 + 8 + 8 + 8 + 8 + 8 + 8) * 16 + // staking_pools
+        1; // version
 
     pub fn has_role(_role: &[u8], authority: &Pubkey, state_authority: &Pubkey) -> bool {
         authority == state_authority
@@ -97,14 +309,16 @@ impl StateAccount {
         Ok(())
     }
 
-    pub fn get_staking_pool(pool_id: u64) -> Result<StakingPool> {
-        Ok(StakingPool {
-            id: pool_id,
-            apy: 500_000_000, // 5% APY
-            duration: 365 * 24 * 60 * 60, // 1年
-            min_stake_amount: 100_000_000, // 最小质押金额
-            status: PoolStatus::Active,
-        })
+    /// 获取已创建的质押池，质押池按创建顺序编号，`total_staking_plans` 为已创建数量
+    pub fn get_staking_pool(&self, pool_id: u64) -> Result<StakingPool> {
+        require!(pool_id < self.staking_config.total_staking_plans, WUSDError::InvalidPoolId);
+        Ok(self.staking_pools[pool_id as usize])
+    }
+
+    /// 获取已创建质押池的可变引用
+    pub fn get_staking_pool_mut(&mut self, pool_id: u64) -> Result<&mut StakingPool> {
+        require!(pool_id < self.staking_config.total_staking_plans, WUSDError::InvalidPoolId);
+        Ok(&mut self.staking_pools[pool_id as usize])
     }
 
     pub fn is_token_whitelisted(mint: Pubkey, token_whitelist: &Box<[(Pubkey, bool); 3]>) -> bool {
@@ -42,4 +42,68 @@ pub enum WusdError {
     InvalidTransferFrom,
     #[msg("Invalid mint address")]
     InvalidMint, 
-}
\ No newline at end of file
+    #[msg("Invalid account data")]
+    InvalidAccountData,
+    #[msg("Account is already migrated")]
+    AlreadyMigrated,
+    #[msg("No checkpoint at or before the requested slot")]
+    CheckpointNotFound,
+    #[msg("Checkpoint slot is out of order")]
+    InvalidCheckpoint,
+    #[msg("Checkpoint account is full, extend it first")]
+    CheckpointsFull,
+    #[msg("Invalid velocity tier")]
+    InvalidVelocityTier,
+    #[msg("Velocity config not found")]
+    VelocityConfigNotFound,
+    #[msg("Velocity limit exceeded")]
+    VelocityLimitExceeded,
+    #[msg("Recipient is not allowlisted")]
+    RecipientNotAllowlisted,
+    #[msg("Invalid mint authority")]
+    InvalidMintAuthority,
+    #[msg("Timelock has not expired")]
+    TimelockNotExpired,
+    #[msg("Confirmation does not match the pending transfer")]
+    ConfirmationMismatch,
+    #[msg("Redemption is not pending")]
+    RedemptionNotPending,
+    #[msg("Invalid redemption status")]
+    InvalidRedemptionStatus,
+    #[msg("Invalid guardian set")]
+    InvalidGuardianSet,
+    #[msg("Bridge chain is disabled")]
+    BridgeChainDisabled,
+    #[msg("Bridge rate limit exceeded")]
+    BridgeRateLimitExceeded,
+    #[msg("Not enough valid guardian signatures")]
+    InsufficientAttestations,
+    #[msg("Invalid or missing signature")]
+    InvalidSignature,
+    #[msg("Relayer fee exceeds the signed maximum")]
+    RelayerFeeTooHigh,
+    #[msg("Payment hold has expired")]
+    HoldExpired,
+    #[msg("Payment hold has not expired")]
+    HoldNotExpired,
+    #[msg("Invalid stream schedule")]
+    InvalidStreamSchedule,
+    #[msg("Nothing to withdraw from the stream")]
+    NothingToWithdraw,
+    #[msg("Invalid subscription terms")]
+    InvalidSubscription,
+    #[msg("Subscription payment is not due yet")]
+    SubscriptionNotDue,
+    #[msg("Subscription has no remaining cycles")]
+    SubscriptionCompleted,
+    #[msg("Owner has no valid sanctions clearance")]
+    SanctionsNotCleared,
+    #[msg("Invalid sanctions non-membership proof")]
+    InvalidSanctionsProof,
+    #[msg("Travel rule record is required for this amount")]
+    TravelRuleRequired,
+    #[msg("Travel rule record does not match the transfer")]
+    TravelRuleMismatch,
+    #[msg("Invalid role account")]
+    InvalidRoleAccount,
+}
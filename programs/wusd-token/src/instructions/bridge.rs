@@ -0,0 +1,531 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::sysvar::instructions as ix_sysvar;
+use anchor_spl::token::{self, Token, TokenAccount, Mint};
+use crate::{AccessLevel, error::WusdError};
+use crate::state::{
+    AuthorityState, AccessRegistryState, PauseState, FreezeState, VelocityTier,
+    BridgeConfig, BridgeChain, BridgeReceipt, BridgeMessage, MAX_GUARDIANS,
+};
+use crate::utils::{record_checkpoint, require_credit_access, require_not_sanctioned, ed25519_signers};
+
+/// 设置跨链桥守护者集合
+/// * `guardians` - 守护者 Ed25519 公钥
+/// * `quorum` - 入站消息所需的最少签名数
+pub fn configure_bridge(ctx: Context<ConfigureBridge>, guardians: Vec<Pubkey>, quorum: u8) -> Result<()> {
+    let bridge_config = &mut ctx.accounts.bridge_config;
+    bridge_config.mint = ctx.accounts.token_mint.key();
+    bridge_config.set_guardians(&guardians, quorum)?;
+
+    emit!(BridgeConfigured {
+        version: crate::EVENT_VERSION,
+        actor: ctx.accounts.authority.key(),
+        mint: ctx.accounts.token_mint.key(),
+        guardians,
+        quorum,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+/// 设置对端链的开关和双向速率限制
+/// * `chain_id` - 对端链编号
+/// * `enabled` - 是否开放跨链
+/// * `outbound_tier` - 出站限额档位
+/// * `inbound_tier` - 入站限额档位
+pub fn set_bridge_chain(
+    ctx: Context<SetBridgeChain>,
+    chain_id: u16,
+    enabled: bool,
+    outbound_tier: VelocityTier,
+    inbound_tier: VelocityTier,
+) -> Result<()> {
+    outbound_tier.validate()?;
+    inbound_tier.validate()?;
+
+    let bridge_chain = &mut ctx.accounts.bridge_chain;
+    bridge_chain.mint = ctx.accounts.token_mint.key();
+    bridge_chain.chain_id = chain_id;
+    bridge_chain.enabled = enabled;
+    bridge_chain.outbound.tier = outbound_tier;
+    bridge_chain.inbound.tier = inbound_tier;
+
+    emit!(BridgeChainSet {
+        version: crate::EVENT_VERSION,
+        actor: ctx.accounts.authority.key(),
+        mint: ctx.accounts.token_mint.key(),
+        chain_id,
+        enabled,
+        outbound_tier,
+        inbound_tier,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+/// 出站跨链：销毁代币并发出跨链消息，由守护者在目标链上铸造
+/// * `destination_chain` - 目标链编号
+/// * `recipient` - 目标链上的收款地址
+/// * `amount` - 跨链数量
+pub fn bridge_out(
+    ctx: Context<BridgeOut>,
+    destination_chain: u16,
+    recipient: [u8; 32],
+    amount: u64,
+) -> Result<()> {
+    ctx.accounts.pause_state.validate_not_paused()?;
+    require!(amount > 0, WusdError::InvalidAmount);
+    require!(
+        ctx.accounts.access_registry.has_access(
+            ctx.accounts.sender.key(),
+            AccessLevel::Debit
+        ),
+        WusdError::AccessDenied
+    );
+    ctx.accounts.from_freeze_state.check_frozen()?;
+    require_not_sanctioned(
+        &ctx.accounts.sanctions_config,
+        &ctx.accounts.sender_clearance,
+        ctx.accounts.sender.key(),
+    )?;
+    require!(
+        ctx.accounts.token_account.amount >= amount,
+        WusdError::InsufficientBalance
+    );
+
+    let now = Clock::get()?.unix_timestamp;
+    let bridge_chain = &mut ctx.accounts.bridge_chain;
+    require!(bridge_chain.enabled, WusdError::BridgeChainDisabled);
+    bridge_chain.outbound.consume(amount, now)?;
+
+    token::burn(
+        CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            token::Burn {
+                mint: ctx.accounts.token_mint.to_account_info(),
+                from: ctx.accounts.token_account.to_account_info(),
+                authority: ctx.accounts.sender.to_account_info(),
+            },
+        ),
+        amount,
+    )?;
+
+    record_checkpoint(
+        &ctx.accounts.checkpoints,
+        &mut ctx.accounts.token_account,
+    )?;
+
+    let bridge_config = &mut ctx.accounts.bridge_config;
+    let sequence = bridge_config.outbound_sequence;
+    bridge_config.outbound_sequence = sequence
+        .checked_add(1)
+        .ok_or(WusdError::InvalidAmount)?;
+
+    emit!(BridgeOutEvent {
+        version: crate::EVENT_VERSION,
+        sender: ctx.accounts.sender.key(),
+        mint: ctx.accounts.token_mint.key(),
+        destination_chain,
+        recipient,
+        amount,
+        sequence,
+        timestamp: now,
+    });
+
+    Ok(())
+}
+
+/// 入站跨链：校验守护者签名后铸造代币
+/// 同一交易中须先包含 Ed25519 程序的验签指令，签名内容为 `BridgeMessage` 的序列化结果
+/// * `source_chain` - 源链编号
+/// * `sequence` - 源链上的消息序号
+/// * `amount` - 铸造数量
+pub fn bridge_in(
+    ctx: Context<BridgeIn>,
+    source_chain: u16,
+    sequence: u64,
+    amount: u64,
+) -> Result<()> {
+    ctx.accounts.pause_state.validate_not_paused()?;
+    require!(amount > 0, WusdError::InvalidAmount);
+
+    let message = BridgeMessage {
+        mint: ctx.accounts.token_mint.key(),
+        source_chain,
+        sequence,
+        recipient: ctx.accounts.token_account.key(),
+        amount,
+    };
+    verify_guardian_attestations(
+        &ctx.accounts.instructions,
+        &ctx.accounts.bridge_config,
+        &message.try_to_vec()?,
+    )?;
+
+    // 接收方与普通转账的收款方同样检查
+    ctx.accounts.to_freeze_state.check_frozen()?;
+    require_credit_access(
+        &ctx.accounts.access_registry,
+        ctx.accounts.token_account.owner,
+        &ctx.accounts.recipient_allowlist,
+    )?;
+    require_not_sanctioned(
+        &ctx.accounts.sanctions_config,
+        &ctx.accounts.recipient_clearance,
+        ctx.accounts.token_account.owner,
+    )?;
+
+    let now = Clock::get()?.unix_timestamp;
+    let bridge_chain = &mut ctx.accounts.bridge_chain;
+    require!(bridge_chain.enabled, WusdError::BridgeChainDisabled);
+    bridge_chain.inbound.consume(amount, now)?;
+
+    let mint_key = ctx.accounts.token_mint.key();
+    let bump = *ctx.bumps.get("authority_state").unwrap();
+    let seeds = &[b"authority", mint_key.as_ref(), &[bump]];
+    token::mint_to(
+        CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            token::MintTo {
+                mint: ctx.accounts.token_mint.to_account_info(),
+                to: ctx.accounts.token_account.to_account_info(),
+                authority: ctx.accounts.authority_state.to_account_info(),
+            },
+            &[&seeds[..]],
+        ),
+        amount,
+    )?;
+
+    record_checkpoint(
+        &ctx.accounts.checkpoints,
+        &mut ctx.accounts.token_account,
+    )?;
+
+    // 回执账户只能创建一次，用于防止重放
+    let receipt = &mut ctx.accounts.receipt;
+    receipt.source_chain = source_chain;
+    receipt.sequence = sequence;
+    receipt.recipient = ctx.accounts.token_account.key();
+    receipt.amount = amount;
+    receipt.processed_at = now;
+
+    emit!(BridgeInEvent {
+        version: crate::EVENT_VERSION,
+        relayer: ctx.accounts.relayer.key(),
+        mint: mint_key,
+        source_chain,
+        sequence,
+        recipient: ctx.accounts.token_account.key(),
+        amount,
+        timestamp: now,
+    });
+
+    Ok(())
+}
+
+/// 统计对消息签名的不同守护者数量，未达到法定数量时拒绝
+fn verify_guardian_attestations(
+    instructions: &AccountInfo,
+    bridge_config: &BridgeConfig,
+    message: &[u8],
+) -> Result<()> {
+    let mut signed = [false; MAX_GUARDIANS];
+    for signer in ed25519_signers(instructions, message)? {
+        if let Some(guardian) = bridge_config.guardian_index(&signer) {
+            signed[guardian] = true;
+        }
+    }
+
+    let signatures = signed.iter().filter(|signed| **signed).count();
+    require!(
+        bridge_config.quorum > 0 && signatures >= bridge_config.quorum as usize,
+        WusdError::InsufficientAttestations
+    );
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct ConfigureBridge<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub token_mint: Account<'info, Mint>,
+
+    #[account(
+        seeds = [b"authority", token_mint.key().as_ref()],
+        bump,
+        constraint = authority_state.is_admin(authority.key()) @ WusdError::Unauthorized
+    )]
+    pub authority_state: Account<'info, AuthorityState>,
+
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = BridgeConfig::SIZE,
+        seeds = [b"bridge_config", token_mint.key().as_ref()],
+        bump
+    )]
+    pub bridge_config: Box<Account<'info, BridgeConfig>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(chain_id: u16)]
+pub struct SetBridgeChain<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub token_mint: Account<'info, Mint>,
+
+    #[account(
+        seeds = [b"authority", token_mint.key().as_ref()],
+        bump,
+        constraint = authority_state.is_admin(authority.key()) @ WusdError::Unauthorized
+    )]
+    pub authority_state: Account<'info, AuthorityState>,
+
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = BridgeChain::SIZE,
+        seeds = [b"bridge_chain", token_mint.key().as_ref(), &chain_id.to_le_bytes()],
+        bump
+    )]
+    pub bridge_chain: Account<'info, BridgeChain>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(destination_chain: u16)]
+pub struct BridgeOut<'info> {
+    pub sender: Signer<'info>,
+
+    #[account(mut)]
+    pub token_mint: Account<'info, Mint>,
+
+    #[account(
+        mut,
+        constraint = token_account.owner == sender.key() @ WusdError::InvalidOwner,
+        constraint = token_account.mint == token_mint.key() @ WusdError::InvalidMint
+    )]
+    pub token_account: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"bridge_config", token_mint.key().as_ref()],
+        bump
+    )]
+    pub bridge_config: Box<Account<'info, BridgeConfig>>,
+
+    #[account(
+        mut,
+        seeds = [b"bridge_chain", token_mint.key().as_ref(), &destination_chain.to_le_bytes()],
+        bump
+    )]
+    pub bridge_chain: Account<'info, BridgeChain>,
+
+    #[account(
+        seeds = [b"pause_state", token_mint.key().as_ref()],
+        bump
+    )]
+    pub pause_state: Account<'info, PauseState>,
+
+    #[account(
+        seeds = [b"access_registry"],
+        bump
+    )]
+    pub access_registry: Box<Account<'info, AccessRegistryState>>,
+
+    #[account(
+        seeds = [b"freeze", token_account.key().as_ref()],
+        bump
+    )]
+    pub from_freeze_state: Account<'info, FreezeState>,
+
+    /// CHECK: 制裁名单配置，未配置时为空账户
+    #[account(
+        seeds = [b"sanctions", token_mint.key().as_ref()],
+        bump
+    )]
+    pub sanctions_config: UncheckedAccount<'info>,
+
+    /// CHECK: 转出方制裁名单检查缓存，未通过检查时为空账户
+    #[account(
+        seeds = [b"clearance", token_mint.key().as_ref(), sender.key().as_ref()],
+        bump
+    )]
+    pub sender_clearance: UncheckedAccount<'info>,
+
+    /// CHECK: 余额快照账户，未开启快照时为空账户
+    #[account(
+        mut,
+        seeds = [b"checkpoint", token_account.key().as_ref()],
+        bump
+    )]
+    pub checkpoints: UncheckedAccount<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+#[instruction(source_chain: u16, sequence: u64)]
+pub struct BridgeIn<'info> {
+    /// 提交守护者签名的中继者，支付回执账户租金
+    #[account(mut)]
+    pub relayer: Signer<'info>,
+
+    #[account(mut)]
+    pub token_mint: Box<Account<'info, Mint>>,
+
+    #[account(
+        seeds = [b"authority", token_mint.key().as_ref()],
+        bump
+    )]
+    pub authority_state: Box<Account<'info, AuthorityState>>,
+
+    #[account(
+        mut,
+        constraint = token_account.mint == token_mint.key() @ WusdError::InvalidMint
+    )]
+    pub token_account: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        seeds = [b"bridge_config", token_mint.key().as_ref()],
+        bump
+    )]
+    pub bridge_config: Box<Account<'info, BridgeConfig>>,
+
+    #[account(
+        mut,
+        seeds = [b"bridge_chain", token_mint.key().as_ref(), &source_chain.to_le_bytes()],
+        bump
+    )]
+    pub bridge_chain: Box<Account<'info, BridgeChain>>,
+
+    #[account(
+        init,
+        payer = relayer,
+        space = BridgeReceipt::SIZE,
+        seeds = [
+            b"bridge_receipt",
+            token_mint.key().as_ref(),
+            &source_chain.to_le_bytes(),
+            &sequence.to_le_bytes()
+        ],
+        bump
+    )]
+    pub receipt: Box<Account<'info, BridgeReceipt>>,
+
+    #[account(
+        seeds = [b"pause_state", token_mint.key().as_ref()],
+        bump
+    )]
+    pub pause_state: Box<Account<'info, PauseState>>,
+
+    #[account(
+        seeds = [b"access_registry"],
+        bump
+    )]
+    pub access_registry: Box<Account<'info, AccessRegistryState>>,
+
+    #[account(
+        seeds = [b"freeze", token_account.key().as_ref()],
+        bump
+    )]
+    pub to_freeze_state: Box<Account<'info, FreezeState>>,
+
+    /// CHECK: 接收方的入账白名单登记，未登记时为空账户
+    #[account(
+        seeds = [b"allowlist", token_account.owner.as_ref()],
+        bump
+    )]
+    pub recipient_allowlist: UncheckedAccount<'info>,
+
+    /// CHECK: 制裁名单配置，未配置时为空账户
+    #[account(
+        seeds = [b"sanctions", token_mint.key().as_ref()],
+        bump
+    )]
+    pub sanctions_config: UncheckedAccount<'info>,
+
+    /// CHECK: 接收方的制裁名单检查缓存，未通过检查时为空账户
+    #[account(
+        seeds = [b"clearance", token_mint.key().as_ref(), token_account.owner.as_ref()],
+        bump
+    )]
+    pub recipient_clearance: UncheckedAccount<'info>,
+
+    /// CHECK: 余额快照账户，未开启快照时为空账户
+    #[account(
+        mut,
+        seeds = [b"checkpoint", token_account.key().as_ref()],
+        bump
+    )]
+    pub checkpoints: UncheckedAccount<'info>,
+
+    /// CHECK: 指令 sysvar，用于读取同一交易中的 Ed25519 验签指令
+    #[account(address = ix_sysvar::ID)]
+    pub instructions: UncheckedAccount<'info>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+/// 跨链桥守护者集合更新事件
+#[event]
+pub struct BridgeConfigured {
+    /// 事件结构版本
+    pub version: u8,
+    pub actor: Pubkey,
+    pub mint: Pubkey,
+    pub guardians: Vec<Pubkey>,
+    pub quorum: u8,
+    pub timestamp: i64,
+}
+
+/// 对端链配置更新事件
+#[event]
+pub struct BridgeChainSet {
+    /// 事件结构版本
+    pub version: u8,
+    pub actor: Pubkey,
+    pub mint: Pubkey,
+    pub chain_id: u16,
+    pub enabled: bool,
+    pub outbound_tier: VelocityTier,
+    pub inbound_tier: VelocityTier,
+    pub timestamp: i64,
+}
+
+/// 出站跨链消息，守护者监听该事件并在目标链上签名铸造
+#[event]
+pub struct BridgeOutEvent {
+    /// 事件结构版本
+    pub version: u8,
+    pub sender: Pubkey,
+    pub mint: Pubkey,
+    pub destination_chain: u16,
+    /// 目标链上的收款地址
+    pub recipient: [u8; 32],
+    pub amount: u64,
+    /// 出站消息序号
+    pub sequence: u64,
+    pub timestamp: i64,
+}
+
+/// 入站跨链铸造事件
+#[event]
+pub struct BridgeInEvent {
+    /// 事件结构版本
+    pub version: u8,
+    pub relayer: Pubkey,
+    pub mint: Pubkey,
+    pub source_chain: u16,
+    pub sequence: u64,
+    pub recipient: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
@@ -2,6 +2,7 @@ use anchor_lang::prelude::*;
 use anchor_spl::token::{self, Token, TokenAccount, Mint}; 
 use crate::{AccessLevel, error::WusdError};
 use crate::state::{AuthorityState, MintState, AccessRegistryState, PauseState};
+use crate::utils::record_checkpoint;
 
 /// 销毁WUSD代币
 /// * `ctx` - 销毁上下文
@@ -50,9 +51,18 @@ pub fn burn(ctx: Context<Burn>, amount: u64) -> Result<()> {
         amount,
     )?;
 
+    // 更新余额快照
+    record_checkpoint(
+        &ctx.accounts.checkpoints,
+        &mut ctx.accounts.token_account,
+    )?;
+
     emit!(BurnEvent {
-        burner: ctx.accounts.authority.key(),
-        amount
+        version: crate::EVENT_VERSION,
+        actor: ctx.accounts.authority.key(),
+        mint: ctx.accounts.mint.key(),
+        amount,
+        timestamp: Clock::get()?.unix_timestamp,
     });
 
     Ok(())
@@ -74,13 +84,25 @@ pub struct Burn<'info> {
     pub mint_state: Account<'info, MintState>,
     pub pause_state: Account<'info, PauseState>,
     pub access_registry: Account<'info, AccessRegistryState>, 
+    /// CHECK: 余额快照账户，未开启快照时为空账户
+    #[account(
+        mut,
+        seeds = [b"checkpoint", token_account.key().as_ref()],
+        bump
+    )]
+    pub checkpoints: UncheckedAccount<'info>,
 } 
 
 /// 销毁事件，记录代币销毁的详细信息
 #[event]
 pub struct BurnEvent {
+    /// 事件结构版本
+    pub version: u8,
     /// 销毁者地址，执行销毁操作的账户
-    pub burner: Pubkey,
+    pub actor: Pubkey,
+    /// 代币铸币账户地址
+    pub mint: Pubkey,
     /// 销毁数量，被销毁的代币数量
     pub amount: u64,
+    pub timestamp: i64,
 }
\ No newline at end of file
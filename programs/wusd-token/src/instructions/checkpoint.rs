@@ -0,0 +1,147 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::TokenAccount;
+use crate::error::WusdError;
+use crate::state::{BalanceCheckpoints, INITIAL_CHECKPOINTS, MAX_CHECKPOINT_GROWTH};
+
+/// 为代币账户开启余额快照
+/// 开启后 mint、burn、transfer、transfer_from 在变动该账户余额时会记录快照
+pub fn enable_checkpoints(ctx: Context<EnableCheckpoints>) -> Result<()> {
+    let slot = Clock::get()?.slot;
+    let balance = ctx.accounts.token_account.amount;
+
+    let checkpoints = &mut ctx.accounts.checkpoints;
+    checkpoints.token_account = ctx.accounts.token_account.key();
+    checkpoints.owner = ctx.accounts.owner.key();
+    checkpoints.capacity = INITIAL_CHECKPOINTS;
+    checkpoints.checkpoints = Vec::new();
+    // 以开启时的余额作为第一条快照
+    checkpoints.record(slot, balance)?;
+
+    emit!(CheckpointsEnabled {
+        version: crate::EVENT_VERSION,
+        actor: ctx.accounts.owner.key(),
+        mint: ctx.accounts.token_account.mint,
+        token_account: ctx.accounts.token_account.key(),
+        slot,
+        balance,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+/// 扩大快照账户容量，快照写满后余额变动会被拒绝，需先扩容
+/// * `additional` - 增加的快照数量
+pub fn extend_checkpoints(ctx: Context<ExtendCheckpoints>, additional: u16) -> Result<()> {
+    require!(
+        additional > 0 && additional <= MAX_CHECKPOINT_GROWTH,
+        WusdError::InvalidAmount
+    );
+
+    let checkpoints = &mut ctx.accounts.checkpoints;
+    checkpoints.capacity = checkpoints.capacity
+        .checked_add(additional)
+        .ok_or(WusdError::InvalidAmount)?;
+
+    emit!(CheckpointsExtended {
+        version: crate::EVENT_VERSION,
+        actor: ctx.accounts.owner.key(),
+        mint: ctx.accounts.token_account.mint,
+        token_account: ctx.accounts.token_account.key(),
+        capacity: checkpoints.capacity,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+/// 查询代币账户在指定 slot 结束时的余额，结果通过 return data 返回
+pub fn get_balance_at(ctx: Context<BalanceAt>, slot: u64) -> Result<u64> {
+    ctx.accounts.checkpoints.balance_at(slot)
+}
+
+#[derive(Accounts)]
+pub struct EnableCheckpoints<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        constraint = token_account.owner == owner.key() @ WusdError::InvalidOwner
+    )]
+    pub token_account: Account<'info, TokenAccount>,
+
+    #[account(
+        init,
+        payer = owner,
+        space = BalanceCheckpoints::space(INITIAL_CHECKPOINTS),
+        seeds = [b"checkpoint", token_account.key().as_ref()],
+        bump
+    )]
+    pub checkpoints: Box<Account<'info, BalanceCheckpoints>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(additional: u16)]
+pub struct ExtendCheckpoints<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    pub token_account: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"checkpoint", token_account.key().as_ref()],
+        bump,
+        has_one = owner @ WusdError::InvalidOwner,
+        realloc = BalanceCheckpoints::space(checkpoints.capacity.saturating_add(additional)),
+        realloc::payer = owner,
+        realloc::zero = false
+    )]
+    pub checkpoints: Box<Account<'info, BalanceCheckpoints>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct BalanceAt<'info> {
+    /// CHECK: 仅用于推导快照账户地址
+    pub token_account: AccountInfo<'info>,
+
+    #[account(
+        seeds = [b"checkpoint", token_account.key().as_ref()],
+        bump
+    )]
+    pub checkpoints: Box<Account<'info, BalanceCheckpoints>>,
+}
+
+/// 开启余额快照事件
+#[event]
+pub struct CheckpointsEnabled {
+    /// 事件结构版本
+    pub version: u8,
+    /// 开启快照的持有人
+    pub actor: Pubkey,
+    /// 代币铸币账户地址
+    pub mint: Pubkey,
+    pub token_account: Pubkey,
+    pub slot: u64,
+    pub balance: u64,
+    pub timestamp: i64,
+}
+
+/// 快照账户扩容事件
+#[event]
+pub struct CheckpointsExtended {
+    /// 事件结构版本
+    pub version: u8,
+    /// 执行扩容的持有人
+    pub actor: Pubkey,
+    /// 代币铸币账户地址
+    pub mint: Pubkey,
+    pub token_account: Pubkey,
+    /// 扩容后的快照容量
+    pub capacity: u16,
+    pub timestamp: i64,
+}
@@ -1,4 +1,5 @@
 use anchor_lang::prelude::*; 
+use anchor_spl::token::TokenAccount;
 use crate::error::WusdError;   
 use crate::state::{FreezeState, AuthorityState};   
 
@@ -25,7 +26,10 @@ pub fn freeze_account(ctx: Context<FreezeAccount>) -> Result<()> {
 
     // 发出冻结事件
     emit!(FreezeAccountEvent {
-        authority: ctx.accounts.authority.key(),
+        version: crate::EVENT_VERSION,
+        actor: ctx.accounts.authority.key(),
+        mint: ctx.accounts.account.mint,
+        token_account: ctx.accounts.account.key(),
         freeze_state: ctx.accounts.freeze_state.key(),
         timestamp: Clock::get()?.unix_timestamp,
     });
@@ -52,7 +56,10 @@ pub fn unfreeze_account(ctx: Context<UnfreezeAccount>) -> Result<()> {
 
     // 发出解冻事件
     emit!(UnfreezeAccountEvent {
-        authority: ctx.accounts.authority.key(),
+        version: crate::EVENT_VERSION,
+        actor: ctx.accounts.authority.key(),
+        mint: ctx.accounts.account.mint,
+        token_account: ctx.accounts.account.key(),
         freeze_state: ctx.accounts.freeze_state.key(),
         timestamp: Clock::get()?.unix_timestamp,
     });
@@ -93,10 +100,13 @@ pub struct FreezeAccount<'info> {
     )]
     pub freeze_state: Account<'info, FreezeState>,
 
-    /// 要冻结的账户
-    /// CHECK: 这个账户仅用于生成PDA种子
-    pub account: AccountInfo<'info>,
+    /// 要冻结的代币账户
+    pub account: Account<'info, TokenAccount>,
 
+    #[account(
+        seeds = [b"authority", account.mint.as_ref()],
+        bump
+    )]
     pub authority_state: Account<'info, AuthorityState>,
 
     pub system_program: Program<'info, System>,
@@ -116,23 +126,42 @@ pub struct UnfreezeAccount<'info> {
     /// CHECK: 这个账户的安全性由FreezeState结构和程序逻辑保证
     pub freeze_state: Account<'info, FreezeState>,
 
-    /// 要解冻的账户
-    /// CHECK: 这个账户仅用于生成PDA种子
-    pub account: AccountInfo<'info>,
+    /// 要解冻的代币账户
+    pub account: Account<'info, TokenAccount>,
 
+    #[account(
+        seeds = [b"authority", account.mint.as_ref()],
+        bump
+    )]
     pub authority_state: Account<'info, AuthorityState>,
 } 
 
+/// 账户冻结事件
 #[event]
 pub struct FreezeAccountEvent {
-    pub authority: Pubkey,
+    /// 事件结构版本
+    pub version: u8,
+    /// 执行冻结的管理员
+    pub actor: Pubkey,
+    /// 代币铸币账户地址
+    pub mint: Pubkey,
+    /// 被冻结的代币账户
+    pub token_account: Pubkey,
     pub freeze_state: Pubkey,
     pub timestamp: i64,
 }
 
+/// 账户解冻事件
 #[event]
 pub struct UnfreezeAccountEvent {
-    pub authority: Pubkey,
+    /// 事件结构版本
+    pub version: u8,
+    /// 执行解冻的管理员
+    pub actor: Pubkey,
+    /// 代币铸币账户地址
+    pub mint: Pubkey,
+    /// 被解冻的代币账户
+    pub token_account: Pubkey,
     pub freeze_state: Pubkey,
     pub timestamp: i64,
 }
\ No newline at end of file
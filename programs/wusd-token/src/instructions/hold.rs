@@ -0,0 +1,594 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, Token, TokenAccount, Mint};
+use crate::error::WusdError;
+use crate::state::{FreezeState, MintState, AccessRegistryState, PauseState, PaymentHold, TravelRuleLink};
+use crate::utils::{
+    require_has_access, require_credit_access, require_not_sanctioned, record_checkpoint, apply_velocity_limit,
+    consume_travel_rule,
+};
+
+/// 创建商户预授权，将资金转入托管账户
+/// * `merchant` - 收款商户
+/// * `hold_id` - 预授权编号，同一付款人和商户之间不能重复
+/// * `amount` - 冻结金额
+/// * `expiry` - 到期时间
+pub fn create_hold(
+    ctx: Context<CreateHold>,
+    merchant: Pubkey,
+    hold_id: u64,
+    amount: u64,
+    expiry: i64,
+) -> Result<()> {
+    ctx.accounts.pause_state.validate_not_paused()?;
+    require!(amount > 0, WusdError::InvalidAmount);
+    ctx.accounts.payer_freeze_state.check_frozen()?;
+
+    let now = Clock::get()?.unix_timestamp;
+    require!(expiry > now, WusdError::HoldExpired);
+
+    require_has_access(
+        ctx.accounts.payer.key(),
+        true,
+        Some(amount),
+        &ctx.accounts.pause_state,
+        Some(&ctx.accounts.access_registry),
+    )?;
+    require!(
+        ctx.accounts.payer_token.amount >= amount,
+        WusdError::InsufficientBalance
+    );
+    apply_velocity_limit(
+        &ctx.accounts.payer_velocity_limit,
+        &ctx.accounts.velocity_config,
+        ctx.accounts.payer_token.key(),
+        &ctx.accounts.payer.to_account_info(),
+        &ctx.accounts.system_program.to_account_info(),
+        amount,
+    )?;
+
+    // 大额预授权在锁定资金时附带旅行规则记录，转入账户为托管账户，扣款时不再校验
+    let travel_rule = consume_travel_rule(
+        &ctx.accounts.mint_state,
+        &ctx.accounts.travel_rule_record,
+        ctx.accounts.payer_token.key(),
+        ctx.accounts.escrow.key(),
+        amount,
+    )?;
+
+    token::transfer(
+        CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            token::Transfer {
+                from: ctx.accounts.payer_token.to_account_info(),
+                to: ctx.accounts.escrow.to_account_info(),
+                authority: ctx.accounts.payer.to_account_info(),
+            },
+        ),
+        amount,
+    )?;
+
+    record_checkpoint(
+        &ctx.accounts.payer_checkpoints,
+        &mut ctx.accounts.payer_token,
+    )?;
+
+    let hold = &mut ctx.accounts.hold;
+    hold.payer = ctx.accounts.payer.key();
+    hold.merchant = merchant;
+    hold.hold_id = hold_id;
+    hold.mint = ctx.accounts.token_mint.key();
+    hold.payer_token = ctx.accounts.payer_token.key();
+    hold.amount = amount;
+    hold.expiry = expiry;
+    hold.created_at = now;
+    hold.bump = *ctx.bumps.get("hold").unwrap();
+
+    emit!(HoldCreated {
+        version: crate::EVENT_VERSION,
+        payer: hold.payer,
+        merchant,
+        hold_id,
+        mint: hold.mint,
+        amount,
+        expiry,
+        timestamp: now,
+        travel_rule,
+    });
+
+    Ok(())
+}
+
+/// 商户扣款，剩余资金退回付款人并关闭预授权
+/// * `amount` - 扣款金额，不能超过冻结金额
+pub fn capture_hold(ctx: Context<CaptureHold>, amount: u64) -> Result<()> {
+    ctx.accounts.pause_state.validate_not_paused()?;
+    ctx.accounts.payer_freeze_state.check_frozen()?;
+    ctx.accounts.merchant_freeze_state.check_frozen()?;
+
+    let now = Clock::get()?.unix_timestamp;
+    let hold = &ctx.accounts.hold;
+    require!(now < hold.expiry, WusdError::HoldExpired);
+    require!(amount > 0 && amount <= hold.amount, WusdError::InvalidAmount);
+
+    require!(
+        ctx.accounts.access_registry.initialized,
+        WusdError::AccessRegistryNotInitialized
+    );
+    require_credit_access(
+        &ctx.accounts.access_registry,
+        ctx.accounts.merchant_token.owner,
+        &ctx.accounts.merchant_allowlist,
+    )?;
+
+    // 检查制裁名单
+    require_not_sanctioned(
+        &ctx.accounts.sanctions_config,
+        &ctx.accounts.payer_clearance,
+        ctx.accounts.payer_token.owner,
+    )?;
+    require_not_sanctioned(
+        &ctx.accounts.sanctions_config,
+        &ctx.accounts.merchant_clearance,
+        ctx.accounts.merchant_token.owner,
+    )?;
+
+    let remainder = hold.amount - amount;
+    let payer_key = hold.payer;
+    let merchant_key = hold.merchant;
+    let hold_amount = hold.amount;
+    let hold_id = hold.hold_id.to_le_bytes();
+    let seeds = &[b"hold", payer_key.as_ref(), merchant_key.as_ref(), hold_id.as_ref(), &[hold.bump]];
+
+    token::transfer(
+        CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            token::Transfer {
+                from: ctx.accounts.escrow.to_account_info(),
+                to: ctx.accounts.merchant_token.to_account_info(),
+                authority: ctx.accounts.hold.to_account_info(),
+            },
+            &[&seeds[..]],
+        ),
+        amount,
+    )?;
+    if remainder > 0 {
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                token::Transfer {
+                    from: ctx.accounts.escrow.to_account_info(),
+                    to: ctx.accounts.payer_token.to_account_info(),
+                    authority: ctx.accounts.hold.to_account_info(),
+                },
+                &[&seeds[..]],
+            ),
+            remainder,
+        )?;
+    }
+
+    // 关闭托管账户，租金退回付款人
+    token::close_account(CpiContext::new_with_signer(
+        ctx.accounts.token_program.to_account_info(),
+        token::CloseAccount {
+            account: ctx.accounts.escrow.to_account_info(),
+            destination: ctx.accounts.payer.to_account_info(),
+            authority: ctx.accounts.hold.to_account_info(),
+        },
+        &[&seeds[..]],
+    ))?;
+
+    record_checkpoint(
+        &ctx.accounts.merchant_checkpoints,
+        &mut ctx.accounts.merchant_token,
+    )?;
+    record_checkpoint(
+        &ctx.accounts.payer_checkpoints,
+        &mut ctx.accounts.payer_token,
+    )?;
+
+    emit!(HoldCaptured {
+        version: crate::EVENT_VERSION,
+        payer: payer_key,
+        merchant: merchant_key,
+        hold_id: ctx.accounts.hold.hold_id,
+        mint: ctx.accounts.hold.mint,
+        held: hold_amount,
+        captured: amount,
+        refunded: remainder,
+        timestamp: now,
+    });
+
+    Ok(())
+}
+
+/// 释放预授权，全部资金退回付款人
+/// 商户可随时取消，付款人只能在到期后释放
+pub fn release_hold(ctx: Context<ReleaseHold>) -> Result<()> {
+    ctx.accounts.pause_state.validate_not_paused()?;
+    ctx.accounts.payer_freeze_state.check_frozen()?;
+
+    let now = Clock::get()?.unix_timestamp;
+    let hold = &ctx.accounts.hold;
+    let authority = ctx.accounts.authority.key();
+    if authority != hold.merchant {
+        require!(authority == hold.payer, WusdError::Unauthorized);
+        require!(now >= hold.expiry, WusdError::HoldNotExpired);
+    }
+
+    require!(
+        ctx.accounts.access_registry.initialized,
+        WusdError::AccessRegistryNotInitialized
+    );
+    require_credit_access(
+        &ctx.accounts.access_registry,
+        ctx.accounts.payer_token.owner,
+        &ctx.accounts.payer_allowlist,
+    )?;
+
+    let payer_key = hold.payer;
+    let merchant_key = hold.merchant;
+    let amount = hold.amount;
+    let hold_id = hold.hold_id.to_le_bytes();
+    let seeds = &[b"hold", payer_key.as_ref(), merchant_key.as_ref(), hold_id.as_ref(), &[hold.bump]];
+
+    token::transfer(
+        CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            token::Transfer {
+                from: ctx.accounts.escrow.to_account_info(),
+                to: ctx.accounts.payer_token.to_account_info(),
+                authority: ctx.accounts.hold.to_account_info(),
+            },
+            &[&seeds[..]],
+        ),
+        amount,
+    )?;
+
+    token::close_account(CpiContext::new_with_signer(
+        ctx.accounts.token_program.to_account_info(),
+        token::CloseAccount {
+            account: ctx.accounts.escrow.to_account_info(),
+            destination: ctx.accounts.payer.to_account_info(),
+            authority: ctx.accounts.hold.to_account_info(),
+        },
+        &[&seeds[..]],
+    ))?;
+
+    record_checkpoint(
+        &ctx.accounts.payer_checkpoints,
+        &mut ctx.accounts.payer_token,
+    )?;
+
+    emit!(HoldReleased {
+        version: crate::EVENT_VERSION,
+        actor: authority,
+        payer: payer_key,
+        merchant: merchant_key,
+        hold_id: ctx.accounts.hold.hold_id,
+        mint: ctx.accounts.hold.mint,
+        amount,
+        timestamp: now,
+    });
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+#[instruction(merchant: Pubkey, hold_id: u64)]
+pub struct CreateHold<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub token_mint: Box<Account<'info, Mint>>,
+
+    #[account(
+        mut,
+        constraint = payer_token.owner == payer.key() @ WusdError::InvalidOwner,
+        constraint = payer_token.mint == token_mint.key() @ WusdError::InvalidMint
+    )]
+    pub payer_token: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = PaymentHold::SIZE,
+        seeds = [b"hold", payer.key().as_ref(), merchant.as_ref(), &hold_id.to_le_bytes()],
+        bump
+    )]
+    pub hold: Box<Account<'info, PaymentHold>>,
+
+    /// 预授权托管账户，由预授权 PDA 控制
+    #[account(
+        init,
+        payer = payer,
+        seeds = [b"hold_escrow", hold.key().as_ref()],
+        bump,
+        token::mint = token_mint,
+        token::authority = hold
+    )]
+    pub escrow: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        seeds = [b"pause_state", token_mint.key().as_ref()],
+        bump
+    )]
+    pub pause_state: Box<Account<'info, PauseState>>,
+
+    #[account(
+        seeds = [b"access_registry"],
+        bump
+    )]
+    pub access_registry: Box<Account<'info, AccessRegistryState>>,
+
+    #[account(
+        seeds = [b"freeze", payer_token.key().as_ref()],
+        bump
+    )]
+    pub payer_freeze_state: Box<Account<'info, FreezeState>>,
+
+    /// CHECK: 付款方余额快照账户，未开启快照时为空账户
+    #[account(
+        mut,
+        seeds = [b"checkpoint", payer_token.key().as_ref()],
+        bump
+    )]
+    pub payer_checkpoints: UncheckedAccount<'info>,
+
+    /// CHECK: 付款方限额状态账户，尚未创建时为空账户，配置默认档位后首次转出时创建
+    #[account(
+        mut,
+        seeds = [b"velocity", payer_token.key().as_ref()],
+        bump
+    )]
+    pub payer_velocity_limit: UncheckedAccount<'info>,
+
+    /// CHECK: 限额全局配置，未设置默认档位时为空账户
+    #[account(
+        seeds = [b"velocity_config", token_mint.key().as_ref()],
+        bump
+    )]
+    pub velocity_config: UncheckedAccount<'info>,
+
+    #[account(
+        seeds = [b"mint_state", token_mint.key().as_ref()],
+        bump
+    )]
+    pub mint_state: Box<Account<'info, MintState>>,
+
+    /// CHECK: 转出方旅行规则记录，未登记时为空账户
+    #[account(
+        mut,
+        seeds = [b"travel_rule", payer_token.key().as_ref()],
+        bump
+    )]
+    pub travel_rule_record: UncheckedAccount<'info>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct CaptureHold<'info> {
+    pub merchant: Signer<'info>,
+
+    /// CHECK: 付款人，接收预授权和托管账户的租金
+    #[account(mut)]
+    pub payer: AccountInfo<'info>,
+
+    #[account(
+        mut,
+        close = payer,
+        seeds = [b"hold", hold.payer.as_ref(), hold.merchant.as_ref(), &hold.hold_id.to_le_bytes()],
+        bump = hold.bump,
+        has_one = payer,
+        has_one = merchant
+    )]
+    pub hold: Box<Account<'info, PaymentHold>>,
+
+    #[account(
+        mut,
+        seeds = [b"hold_escrow", hold.key().as_ref()],
+        bump
+    )]
+    pub escrow: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        constraint = payer_token.key() == hold.payer_token @ WusdError::InvalidOwner
+    )]
+    pub payer_token: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        constraint = merchant_token.owner == merchant.key() @ WusdError::InvalidOwner,
+        constraint = merchant_token.mint == hold.mint @ WusdError::InvalidMint
+    )]
+    pub merchant_token: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        seeds = [b"pause_state", hold.mint.as_ref()],
+        bump
+    )]
+    pub pause_state: Box<Account<'info, PauseState>>,
+
+    #[account(
+        seeds = [b"access_registry"],
+        bump
+    )]
+    pub access_registry: Box<Account<'info, AccessRegistryState>>,
+
+    #[account(
+        seeds = [b"freeze", payer_token.key().as_ref()],
+        bump
+    )]
+    pub payer_freeze_state: Box<Account<'info, FreezeState>>,
+
+    #[account(
+        seeds = [b"freeze", merchant_token.key().as_ref()],
+        bump
+    )]
+    pub merchant_freeze_state: Box<Account<'info, FreezeState>>,
+
+    /// CHECK: 商户入账白名单登记，未登记时为空账户
+    #[account(
+        seeds = [b"allowlist", merchant_token.owner.as_ref()],
+        bump
+    )]
+    pub merchant_allowlist: UncheckedAccount<'info>,
+
+    /// CHECK: 制裁名单配置，未配置时为空账户
+    #[account(
+        seeds = [b"sanctions", hold.mint.as_ref()],
+        bump
+    )]
+    pub sanctions_config: UncheckedAccount<'info>,
+
+    /// CHECK: 付款方制裁名单检查缓存，未通过检查时为空账户
+    #[account(
+        seeds = [b"clearance", hold.mint.as_ref(), payer_token.owner.as_ref()],
+        bump
+    )]
+    pub payer_clearance: UncheckedAccount<'info>,
+
+    /// CHECK: 商户制裁名单检查缓存，未通过检查时为空账户
+    #[account(
+        seeds = [b"clearance", hold.mint.as_ref(), merchant_token.owner.as_ref()],
+        bump
+    )]
+    pub merchant_clearance: UncheckedAccount<'info>,
+
+    /// CHECK: 付款方余额快照账户，未开启快照时为空账户
+    #[account(
+        mut,
+        seeds = [b"checkpoint", payer_token.key().as_ref()],
+        bump
+    )]
+    pub payer_checkpoints: UncheckedAccount<'info>,
+
+    /// CHECK: 商户余额快照账户，未开启快照时为空账户
+    #[account(
+        mut,
+        seeds = [b"checkpoint", merchant_token.key().as_ref()],
+        bump
+    )]
+    pub merchant_checkpoints: UncheckedAccount<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct ReleaseHold<'info> {
+    /// 商户或到期后的付款人
+    pub authority: Signer<'info>,
+
+    /// CHECK: 付款人，接收预授权和托管账户的租金
+    #[account(mut)]
+    pub payer: AccountInfo<'info>,
+
+    #[account(
+        mut,
+        close = payer,
+        seeds = [b"hold", hold.payer.as_ref(), hold.merchant.as_ref(), &hold.hold_id.to_le_bytes()],
+        bump = hold.bump,
+        has_one = payer
+    )]
+    pub hold: Box<Account<'info, PaymentHold>>,
+
+    #[account(
+        mut,
+        seeds = [b"hold_escrow", hold.key().as_ref()],
+        bump
+    )]
+    pub escrow: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        constraint = payer_token.key() == hold.payer_token @ WusdError::InvalidOwner
+    )]
+    pub payer_token: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        seeds = [b"pause_state", hold.mint.as_ref()],
+        bump
+    )]
+    pub pause_state: Box<Account<'info, PauseState>>,
+
+    #[account(
+        seeds = [b"access_registry"],
+        bump
+    )]
+    pub access_registry: Box<Account<'info, AccessRegistryState>>,
+
+    #[account(
+        seeds = [b"freeze", payer_token.key().as_ref()],
+        bump
+    )]
+    pub payer_freeze_state: Box<Account<'info, FreezeState>>,
+
+    /// CHECK: 付款人入账白名单登记，未登记时为空账户
+    #[account(
+        seeds = [b"allowlist", payer_token.owner.as_ref()],
+        bump
+    )]
+    pub payer_allowlist: UncheckedAccount<'info>,
+
+    /// CHECK: 付款方余额快照账户，未开启快照时为空账户
+    #[account(
+        mut,
+        seeds = [b"checkpoint", payer_token.key().as_ref()],
+        bump
+    )]
+    pub payer_checkpoints: UncheckedAccount<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+/// 预授权创建事件
+#[event]
+pub struct HoldCreated {
+    /// 事件结构版本
+    pub version: u8,
+    pub payer: Pubkey,
+    pub merchant: Pubkey,
+    pub hold_id: u64,
+    pub mint: Pubkey,
+    pub amount: u64,
+    pub expiry: i64,
+    pub timestamp: i64,
+    /// 大额转账关联的旅行规则信息
+    pub travel_rule: Option<TravelRuleLink>,
+}
+
+/// 预授权扣款事件
+#[event]
+pub struct HoldCaptured {
+    /// 事件结构版本
+    pub version: u8,
+    pub payer: Pubkey,
+    pub merchant: Pubkey,
+    pub hold_id: u64,
+    pub mint: Pubkey,
+    /// 冻结金额
+    pub held: u64,
+    /// 商户扣款金额
+    pub captured: u64,
+    /// 退回付款人的金额
+    pub refunded: u64,
+    pub timestamp: i64,
+}
+
+/// 预授权释放事件
+#[event]
+pub struct HoldReleased {
+    /// 事件结构版本
+    pub version: u8,
+    /// 执行释放的商户或付款人
+    pub actor: Pubkey,
+    pub payer: Pubkey,
+    pub merchant: Pubkey,
+    pub hold_id: u64,
+    pub mint: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
@@ -0,0 +1,175 @@
+use anchor_lang::prelude::*;
+use anchor_spl::metadata::{self, Metadata};
+use anchor_spl::token::Mint;
+use mpl_token_metadata::state::DataV2;
+use crate::error::WusdError;
+use crate::state::AuthorityState;
+
+/// 创建代币元数据，由 authority_state PDA 作为铸币权和更新权签名
+/// * `params` - 名称、符号和 URI
+pub fn create_token_metadata(ctx: Context<CreateTokenMetadata>, params: TokenMetadataParams) -> Result<()> {
+    let mint_key = ctx.accounts.token_mint.key();
+    let bump = *ctx.bumps.get("authority_state").unwrap();
+    let seeds = &[b"authority", mint_key.as_ref(), &[bump]];
+
+    metadata::create_metadata_accounts_v3(
+        CpiContext::new_with_signer(
+            ctx.accounts.token_metadata_program.to_account_info(),
+            metadata::CreateMetadataAccountsV3 {
+                metadata: ctx.accounts.metadata.to_account_info(),
+                mint: ctx.accounts.token_mint.to_account_info(),
+                mint_authority: ctx.accounts.authority_state.to_account_info(),
+                payer: ctx.accounts.authority.to_account_info(),
+                update_authority: ctx.accounts.authority_state.to_account_info(),
+                system_program: ctx.accounts.system_program.to_account_info(),
+                rent: ctx.accounts.rent.to_account_info(),
+            },
+            &[&seeds[..]],
+        ),
+        params.to_data(),
+        true,
+        true,
+        None,
+    )?;
+
+    emit!(TokenMetadataUpdated {
+        version: crate::EVENT_VERSION,
+        actor: ctx.accounts.authority.key(),
+        mint: mint_key,
+        name: params.name,
+        symbol: params.symbol,
+        uri: params.uri,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+/// 更新代币元数据
+/// * `params` - 新的名称、符号和 URI
+pub fn update_token_metadata(ctx: Context<UpdateTokenMetadata>, params: TokenMetadataParams) -> Result<()> {
+    let mint_key = ctx.accounts.token_mint.key();
+    let bump = *ctx.bumps.get("authority_state").unwrap();
+    let seeds = &[b"authority", mint_key.as_ref(), &[bump]];
+
+    metadata::update_metadata_accounts_v2(
+        CpiContext::new_with_signer(
+            ctx.accounts.token_metadata_program.to_account_info(),
+            metadata::UpdateMetadataAccountsV2 {
+                metadata: ctx.accounts.metadata.to_account_info(),
+                update_authority: ctx.accounts.authority_state.to_account_info(),
+            },
+            &[&seeds[..]],
+        ),
+        None,
+        Some(params.to_data()),
+        None,
+        None,
+    )?;
+
+    emit!(TokenMetadataUpdated {
+        version: crate::EVENT_VERSION,
+        actor: ctx.accounts.authority.key(),
+        mint: mint_key,
+        name: params.name,
+        symbol: params.symbol,
+        uri: params.uri,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+/// 代币元数据参数
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct TokenMetadataParams {
+    /// 代币名称，如 "WUSD Stablecoin"
+    pub name: String,
+    /// 代币符号，如 "WUSD"
+    pub symbol: String,
+    /// 链下元数据 JSON 的 URI
+    pub uri: String,
+}
+
+impl TokenMetadataParams {
+    /// 转换为 Metaplex 元数据结构，同质化代币不设置创作者、版税和集合
+    fn to_data(&self) -> DataV2 {
+        DataV2 {
+            name: self.name.clone(),
+            symbol: self.symbol.clone(),
+            uri: self.uri.clone(),
+            seller_fee_basis_points: 0,
+            creators: None,
+            collection: None,
+            uses: None,
+        }
+    }
+}
+
+#[derive(Accounts)]
+pub struct CreateTokenMetadata<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub token_mint: Account<'info, Mint>,
+
+    #[account(
+        seeds = [b"authority", token_mint.key().as_ref()],
+        bump,
+        constraint = authority_state.is_admin(authority.key()) @ WusdError::Unauthorized
+    )]
+    pub authority_state: Account<'info, AuthorityState>,
+
+    /// CHECK: 由 Metaplex 程序创建和校验
+    #[account(
+        mut,
+        seeds = [b"metadata", token_metadata_program.key().as_ref(), token_mint.key().as_ref()],
+        bump,
+        seeds::program = token_metadata_program.key()
+    )]
+    pub metadata: UncheckedAccount<'info>,
+
+    pub token_metadata_program: Program<'info, Metadata>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateTokenMetadata<'info> {
+    pub authority: Signer<'info>,
+
+    pub token_mint: Account<'info, Mint>,
+
+    #[account(
+        seeds = [b"authority", token_mint.key().as_ref()],
+        bump,
+        constraint = authority_state.is_admin(authority.key()) @ WusdError::Unauthorized
+    )]
+    pub authority_state: Account<'info, AuthorityState>,
+
+    /// CHECK: 由 Metaplex 程序校验
+    #[account(
+        mut,
+        seeds = [b"metadata", token_metadata_program.key().as_ref(), token_mint.key().as_ref()],
+        bump,
+        seeds::program = token_metadata_program.key()
+    )]
+    pub metadata: UncheckedAccount<'info>,
+
+    pub token_metadata_program: Program<'info, Metadata>,
+}
+
+/// 代币元数据更新事件
+#[event]
+pub struct TokenMetadataUpdated {
+    /// 事件结构版本
+    pub version: u8,
+    /// 执行操作的管理员
+    pub actor: Pubkey,
+    /// 代币铸币账户地址
+    pub mint: Pubkey,
+    pub name: String,
+    pub symbol: String,
+    pub uri: String,
+    pub timestamp: i64,
+}
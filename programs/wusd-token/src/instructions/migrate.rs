@@ -0,0 +1,345 @@
+use anchor_lang::prelude::*;
+use anchor_lang::system_program;
+use anchor_spl::token::Mint;
+use crate::error::WusdError;
+use crate::state::{AuthorityState, MintState, PauseState, AccessRegistryState, Versioned, STATE_VERSION};
+
+/// 迁移权限管理账户到当前布局
+pub fn migrate_authority_state(ctx: Context<MigrateAuthorityState>) -> Result<()> {
+    require_admin(&ctx.accounts.authority_state, ctx.accounts.authority.key())?;
+
+    let from_version = migrate_account::<AuthorityState>(
+        &ctx.accounts.authority_state.to_account_info(),
+        &ctx.accounts.authority.to_account_info(),
+        &ctx.accounts.system_program.to_account_info(),
+    )?;
+
+    emit!(StateMigrated {
+        version: crate::EVENT_VERSION,
+        actor: ctx.accounts.authority.key(),
+        mint: ctx.accounts.token_mint.key(),
+        account: ctx.accounts.authority_state.key(),
+        from_version,
+        to_version: STATE_VERSION,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+/// 迁移铸币状态账户到当前布局
+pub fn migrate_mint_state(ctx: Context<MigrateMintState>) -> Result<()> {
+    require_admin(&ctx.accounts.authority_state, ctx.accounts.authority.key())?;
+
+    let from_version = migrate_account::<MintState>(
+        &ctx.accounts.mint_state.to_account_info(),
+        &ctx.accounts.authority.to_account_info(),
+        &ctx.accounts.system_program.to_account_info(),
+    )?;
+
+    emit!(StateMigrated {
+        version: crate::EVENT_VERSION,
+        actor: ctx.accounts.authority.key(),
+        mint: ctx.accounts.token_mint.key(),
+        account: ctx.accounts.mint_state.key(),
+        from_version,
+        to_version: STATE_VERSION,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+/// 迁移暂停状态账户到当前布局
+pub fn migrate_pause_state(ctx: Context<MigratePauseState>) -> Result<()> {
+    require_admin(&ctx.accounts.authority_state, ctx.accounts.authority.key())?;
+
+    let from_version = migrate_account::<PauseState>(
+        &ctx.accounts.pause_state.to_account_info(),
+        &ctx.accounts.authority.to_account_info(),
+        &ctx.accounts.system_program.to_account_info(),
+    )?;
+
+    emit!(StateMigrated {
+        version: crate::EVENT_VERSION,
+        actor: ctx.accounts.authority.key(),
+        mint: ctx.accounts.token_mint.key(),
+        account: ctx.accounts.pause_state.key(),
+        from_version,
+        to_version: STATE_VERSION,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+/// 迁移访问权限注册表到当前布局
+pub fn migrate_access_registry(ctx: Context<MigrateAccessRegistry>) -> Result<()> {
+    // 注册表不属于某个铸币，由其自身的管理员执行迁移
+    let registry = load_versioned::<AccessRegistryState>(&ctx.accounts.access_registry)?;
+    require!(
+        registry.authority == ctx.accounts.authority.key(),
+        WusdError::Unauthorized
+    );
+
+    let from_version = migrate_account::<AccessRegistryState>(
+        &ctx.accounts.access_registry.to_account_info(),
+        &ctx.accounts.authority.to_account_info(),
+        &ctx.accounts.system_program.to_account_info(),
+    )?;
+
+    emit!(StateMigrated {
+        version: crate::EVENT_VERSION,
+        actor: ctx.accounts.authority.key(),
+        mint: ctx.accounts.token_mint.key(),
+        account: ctx.accounts.access_registry.key(),
+        from_version,
+        to_version: STATE_VERSION,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+/// 查询各状态账户的迁移情况，不修改任何数据
+pub fn get_migration_status(ctx: Context<MigrationStatusView>) -> Result<MigrationStatus> {
+    Ok(MigrationStatus {
+        current_version: STATE_VERSION,
+        authority_state: inspect_account::<AuthorityState>(&ctx.accounts.authority_state)?,
+        mint_state: inspect_account::<MintState>(&ctx.accounts.mint_state)?,
+        pause_state: inspect_account::<PauseState>(&ctx.accounts.pause_state)?,
+        access_registry: inspect_account::<AccessRegistryState>(&ctx.accounts.access_registry)?,
+    })
+}
+
+/// 按当前布局加载状态账户
+/// 旧账户长度不足时在内存中补零，缺失的版本号即为 0
+fn load_versioned<T: AccountDeserialize + Versioned>(info: &AccountInfo) -> Result<T> {
+    require_keys_eq!(*info.owner, crate::ID, WusdError::InvalidAccountData);
+
+    let data = info.try_borrow_data()?;
+    let mut buf = data.to_vec();
+    if buf.len() < T::CURRENT_SIZE {
+        buf.resize(T::CURRENT_SIZE, 0);
+    }
+
+    // try_deserialize 会校验账户判别符
+    T::try_deserialize(&mut buf.as_slice())
+}
+
+/// 验证调用者是权限管理账户中记录的管理员
+fn require_admin(authority_state: &AccountInfo, user: Pubkey) -> Result<()> {
+    let state = load_versioned::<AuthorityState>(authority_state)?;
+    require!(state.is_admin(user), WusdError::Unauthorized);
+    Ok(())
+}
+
+/// 扩容账户、补足租金并写入当前版本号，返回迁移前的版本
+fn migrate_account<'info, T>(
+    target: &AccountInfo<'info>,
+    payer: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+) -> Result<u8>
+where
+    T: AccountSerialize + AccountDeserialize + Versioned,
+{
+    let mut state = load_versioned::<T>(target)?;
+    let from_version = state.version();
+    require!(from_version < STATE_VERSION, WusdError::AlreadyMigrated);
+
+    if target.data_len() < T::CURRENT_SIZE {
+        // 补足新长度所需的租金
+        let rent = Rent::get()?.minimum_balance(T::CURRENT_SIZE);
+        let lamports_needed = rent.saturating_sub(target.lamports());
+        if lamports_needed > 0 {
+            system_program::transfer(
+                CpiContext::new(
+                    system_program.clone(),
+                    system_program::Transfer {
+                        from: payer.clone(),
+                        to: target.clone(),
+                    },
+                ),
+                lamports_needed,
+            )?;
+        }
+        target.realloc(T::CURRENT_SIZE, true)?;
+    }
+
+    state.set_version(STATE_VERSION);
+    let mut data = target.try_borrow_mut_data()?;
+    let mut writer: &mut [u8] = &mut data;
+    state.try_serialize(&mut writer)?;
+
+    Ok(from_version)
+}
+
+/// 读取单个账户的版本信息
+fn inspect_account<T: AccountDeserialize + Versioned>(info: &AccountInfo) -> Result<AccountVersionInfo> {
+    if info.data_is_empty() {
+        return Ok(AccountVersionInfo {
+            address: info.key(),
+            initialized: false,
+            version: 0,
+            needs_migration: false,
+        });
+    }
+
+    let state = load_versioned::<T>(info)?;
+    Ok(AccountVersionInfo {
+        address: info.key(),
+        initialized: true,
+        version: state.version(),
+        needs_migration: state.version() < STATE_VERSION || info.data_len() < T::CURRENT_SIZE,
+    })
+}
+
+#[derive(Accounts)]
+pub struct MigrateAuthorityState<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub token_mint: Account<'info, Mint>,
+
+    /// CHECK: 可能仍是旧布局，在指令中按版本加载并校验
+    #[account(
+        mut,
+        seeds = [b"authority", token_mint.key().as_ref()],
+        bump
+    )]
+    pub authority_state: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateMintState<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub token_mint: Account<'info, Mint>,
+
+    /// CHECK: 可能仍是旧布局，在指令中按版本加载并校验
+    #[account(
+        seeds = [b"authority", token_mint.key().as_ref()],
+        bump
+    )]
+    pub authority_state: UncheckedAccount<'info>,
+
+    /// CHECK: 可能仍是旧布局，在指令中按版本加载并校验
+    #[account(
+        mut,
+        seeds = [b"mint_state", token_mint.key().as_ref()],
+        bump
+    )]
+    pub mint_state: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct MigratePauseState<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub token_mint: Account<'info, Mint>,
+
+    /// CHECK: 可能仍是旧布局，在指令中按版本加载并校验
+    #[account(
+        seeds = [b"authority", token_mint.key().as_ref()],
+        bump
+    )]
+    pub authority_state: UncheckedAccount<'info>,
+
+    /// CHECK: 可能仍是旧布局，在指令中按版本加载并校验
+    #[account(
+        mut,
+        seeds = [b"pause_state", token_mint.key().as_ref()],
+        bump
+    )]
+    pub pause_state: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateAccessRegistry<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    /// 注册表为全局账户，此处仅用于在事件中标识发起迁移的部署
+    pub token_mint: Account<'info, Mint>,
+
+    /// CHECK: 可能仍是旧布局，在指令中按版本加载并校验
+    #[account(
+        mut,
+        seeds = [b"access_registry"],
+        bump
+    )]
+    pub access_registry: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct MigrationStatusView<'info> {
+    pub token_mint: Account<'info, Mint>,
+
+    /// CHECK: 只读，按版本加载
+    #[account(seeds = [b"authority", token_mint.key().as_ref()], bump)]
+    pub authority_state: UncheckedAccount<'info>,
+
+    /// CHECK: 只读，按版本加载
+    #[account(seeds = [b"mint_state", token_mint.key().as_ref()], bump)]
+    pub mint_state: UncheckedAccount<'info>,
+
+    /// CHECK: 只读，按版本加载
+    #[account(seeds = [b"pause_state", token_mint.key().as_ref()], bump)]
+    pub pause_state: UncheckedAccount<'info>,
+
+    /// CHECK: 只读，按版本加载，可能尚未初始化
+    #[account(seeds = [b"access_registry"], bump)]
+    pub access_registry: UncheckedAccount<'info>,
+}
+
+/// 单个状态账户的版本信息
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct AccountVersionInfo {
+    /// 账户地址
+    pub address: Pubkey,
+    /// 账户是否已创建
+    pub initialized: bool,
+    /// 账户当前布局版本
+    pub version: u8,
+    /// 是否需要迁移
+    pub needs_migration: bool,
+}
+
+/// 部署的迁移情况汇总
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct MigrationStatus {
+    /// 程序当前的布局版本
+    pub current_version: u8,
+    pub authority_state: AccountVersionInfo,
+    pub mint_state: AccountVersionInfo,
+    pub pause_state: AccountVersionInfo,
+    pub access_registry: AccountVersionInfo,
+}
+
+/// 状态迁移事件
+#[event]
+pub struct StateMigrated {
+    /// 事件结构版本
+    pub version: u8,
+    /// 执行迁移的管理员
+    pub actor: Pubkey,
+    /// 代币铸币账户地址
+    pub mint: Pubkey,
+    /// 被迁移的账户
+    pub account: Pubkey,
+    /// 迁移前版本
+    pub from_version: u8,
+    /// 迁移后版本
+    pub to_version: u8,
+    pub timestamp: i64,
+}
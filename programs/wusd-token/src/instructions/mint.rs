@@ -1,7 +1,7 @@
 use anchor_lang::prelude::*;
 use crate::error::WusdError;   
 use anchor_spl::token::{self, Token, TokenAccount};
-use crate::utils::require_has_access;
+use crate::utils::{require_has_access, require_credit_access, record_checkpoint};
 use crate::state::{AuthorityState, MintState, PauseState, AccessRegistryState};
 
 pub fn mint(ctx: Context<MintAccounts>, amount: u64, bump: u8) -> Result<()> {
@@ -10,13 +10,23 @@ pub fn mint(ctx: Context<MintAccounts>, amount: u64, bump: u8) -> Result<()> {
         ctx.accounts.authority_state.is_minter(ctx.accounts.authority.key()), 
         WusdError::NotMinter
     );
-    // 验证访问权限
+    // 验证暂停状态和金额，入账资格只针对接收方，铸币者本身无需在白名单内
     require_has_access(
         ctx.accounts.authority.key(),
         false,
         Some(amount),
         &ctx.accounts.pause_state,
-        Some(&ctx.accounts.access_registry),
+        None,
+    )?;
+    require!(
+        ctx.accounts.access_registry.initialized,
+        WusdError::AccessRegistryNotInitialized
+    );
+    // 验证接收方入账资格
+    require_credit_access(
+        &ctx.accounts.access_registry,
+        ctx.accounts.token_account.owner,
+        &ctx.accounts.recipient_allowlist,
     )?;
 
     // 执行铸币
@@ -35,6 +45,21 @@ pub fn mint(ctx: Context<MintAccounts>, amount: u64, bump: u8) -> Result<()> {
         amount,
     )?;
 
+    // 更新接收方余额快照
+    record_checkpoint(
+        &ctx.accounts.checkpoints,
+        &mut ctx.accounts.token_account,
+    )?;
+
+    emit!(MintEvent {
+        version: crate::EVENT_VERSION,
+        actor: ctx.accounts.authority.key(),
+        mint: mint_key,
+        to: ctx.accounts.token_account.key(),
+        amount,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
     Ok(())
 }
 
@@ -55,4 +80,33 @@ pub struct MintAccounts<'info> {
     #[account(mut)]
     pub pause_state: Account<'info, PauseState>,
     pub access_registry: Account<'info, AccessRegistryState>,
-}
\ No newline at end of file
+    /// CHECK: 接收方余额快照账户，未开启快照时为空账户
+    #[account(
+        mut,
+        seeds = [b"checkpoint", token_account.key().as_ref()],
+        bump
+    )]
+    pub checkpoints: UncheckedAccount<'info>,
+    /// CHECK: 接收方入账白名单登记，未登记时为空账户
+    #[account(
+        seeds = [b"allowlist", token_account.owner.as_ref()],
+        bump
+    )]
+    pub recipient_allowlist: UncheckedAccount<'info>,
+}
+
+/// 铸币事件，记录代币铸造的详细信息
+#[event]
+pub struct MintEvent {
+    /// 事件结构版本
+    pub version: u8,
+    /// 执行铸币的地址
+    pub actor: Pubkey,
+    /// 代币铸币账户地址
+    pub mint: Pubkey,
+    /// 接收代币的账户
+    pub to: Pubkey,
+    /// 铸造数量
+    pub amount: u64,
+    pub timestamp: i64,
+}
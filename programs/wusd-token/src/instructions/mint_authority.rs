@@ -0,0 +1,209 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, Token, Mint};
+use crate::error::WusdError;
+use crate::state::{AuthorityState, MintAuthorityTransfer, MINT_AUTHORITY_TIMELOCK};
+
+/// 发起铸币权移交，等待期结束后才能确认执行
+/// * `new_authority` - 新的铸币权地址，如新程序的 PDA 或多签账户
+pub fn transfer_mint_authority(ctx: Context<TransferMintAuthority>, new_authority: Pubkey) -> Result<()> {
+    require!(new_authority != Pubkey::default(), WusdError::InvalidMintAuthority);
+    require!(
+        new_authority != ctx.accounts.authority_state.key(),
+        WusdError::InvalidMintAuthority
+    );
+
+    let now = Clock::get()?.unix_timestamp;
+    let eta = now.checked_add(MINT_AUTHORITY_TIMELOCK).ok_or(WusdError::InvalidAmount)?;
+
+    let pending = &mut ctx.accounts.pending_transfer;
+    pending.mint = ctx.accounts.token_mint.key();
+    pending.proposer = ctx.accounts.authority.key();
+    pending.new_authority = new_authority;
+    pending.proposed_at = now;
+    pending.eta = eta;
+
+    emit!(MintAuthorityTransferProposed {
+        version: crate::EVENT_VERSION,
+        actor: ctx.accounts.authority.key(),
+        mint: ctx.accounts.token_mint.key(),
+        new_authority,
+        eta,
+        timestamp: now,
+    });
+
+    Ok(())
+}
+
+/// 确认并执行铸币权移交
+/// * `new_authority` - 必须与待执行移交中的地址一致，作为显式确认
+pub fn confirm_mint_authority_transfer(
+    ctx: Context<ConfirmMintAuthorityTransfer>,
+    new_authority: Pubkey,
+) -> Result<()> {
+    let pending = &ctx.accounts.pending_transfer;
+    require!(pending.new_authority == new_authority, WusdError::ConfirmationMismatch);
+
+    let now = Clock::get()?.unix_timestamp;
+    require!(now >= pending.eta, WusdError::TimelockNotExpired);
+
+    // 由 authority_state PDA 签名移交铸币权
+    let mint_key = ctx.accounts.token_mint.key();
+    let bump = *ctx.bumps.get("authority_state").unwrap();
+    let seeds = &[b"authority", mint_key.as_ref(), &[bump]];
+    token::set_authority(
+        CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            token::SetAuthority {
+                current_authority: ctx.accounts.authority_state.to_account_info(),
+                account_or_mint: ctx.accounts.token_mint.to_account_info(),
+            },
+            &[&seeds[..]],
+        ),
+        token::spl_token::instruction::AuthorityType::MintTokens,
+        Some(new_authority),
+    )?;
+
+    emit!(MintAuthorityTransferred {
+        version: crate::EVENT_VERSION,
+        actor: ctx.accounts.authority.key(),
+        mint: mint_key,
+        old_authority: ctx.accounts.authority_state.key(),
+        new_authority,
+        timestamp: now,
+    });
+
+    Ok(())
+}
+
+/// 取消待执行的铸币权移交
+pub fn cancel_mint_authority_transfer(ctx: Context<CancelMintAuthorityTransfer>) -> Result<()> {
+    emit!(MintAuthorityTransferCancelled {
+        version: crate::EVENT_VERSION,
+        actor: ctx.accounts.authority.key(),
+        mint: ctx.accounts.token_mint.key(),
+        new_authority: ctx.accounts.pending_transfer.new_authority,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct TransferMintAuthority<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub token_mint: Account<'info, Mint>,
+
+    #[account(
+        seeds = [b"authority", token_mint.key().as_ref()],
+        bump,
+        constraint = authority_state.is_admin(authority.key()) @ WusdError::Unauthorized
+    )]
+    pub authority_state: Account<'info, AuthorityState>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = MintAuthorityTransfer::SIZE,
+        seeds = [b"mint_authority_transfer", token_mint.key().as_ref()],
+        bump
+    )]
+    pub pending_transfer: Account<'info, MintAuthorityTransfer>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ConfirmMintAuthorityTransfer<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        constraint = token_mint.mint_authority == Some(authority_state.key()).into() @ WusdError::InvalidMintAuthority
+    )]
+    pub token_mint: Account<'info, Mint>,
+
+    #[account(
+        seeds = [b"authority", token_mint.key().as_ref()],
+        bump,
+        constraint = authority_state.is_admin(authority.key()) @ WusdError::Unauthorized
+    )]
+    pub authority_state: Account<'info, AuthorityState>,
+
+    #[account(
+        mut,
+        close = authority,
+        seeds = [b"mint_authority_transfer", token_mint.key().as_ref()],
+        bump
+    )]
+    pub pending_transfer: Account<'info, MintAuthorityTransfer>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct CancelMintAuthorityTransfer<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub token_mint: Account<'info, Mint>,
+
+    #[account(
+        seeds = [b"authority", token_mint.key().as_ref()],
+        bump,
+        constraint = authority_state.is_admin(authority.key()) @ WusdError::Unauthorized
+    )]
+    pub authority_state: Account<'info, AuthorityState>,
+
+    #[account(
+        mut,
+        close = authority,
+        seeds = [b"mint_authority_transfer", token_mint.key().as_ref()],
+        bump
+    )]
+    pub pending_transfer: Account<'info, MintAuthorityTransfer>,
+}
+
+/// 铸币权移交发起事件
+#[event]
+pub struct MintAuthorityTransferProposed {
+    /// 事件结构版本
+    pub version: u8,
+    /// 执行操作的管理员
+    pub actor: Pubkey,
+    /// 代币铸币账户地址
+    pub mint: Pubkey,
+    pub new_authority: Pubkey,
+    /// 最早可执行时间
+    pub eta: i64,
+    pub timestamp: i64,
+}
+
+/// 铸币权移交完成事件
+#[event]
+pub struct MintAuthorityTransferred {
+    /// 事件结构版本
+    pub version: u8,
+    /// 执行操作的管理员
+    pub actor: Pubkey,
+    /// 代币铸币账户地址
+    pub mint: Pubkey,
+    pub old_authority: Pubkey,
+    pub new_authority: Pubkey,
+    pub timestamp: i64,
+}
+
+/// 铸币权移交取消事件
+#[event]
+pub struct MintAuthorityTransferCancelled {
+    /// 事件结构版本
+    pub version: u8,
+    /// 执行操作的管理员
+    pub actor: Pubkey,
+    /// 代币铸币账户地址
+    pub mint: Pubkey,
+    pub new_authority: Pubkey,
+    pub timestamp: i64,
+}
@@ -4,4 +4,17 @@ pub mod transfer;
 pub mod permit;
 pub mod operator;
 pub mod pause;
-pub mod freeze;
\ No newline at end of file
+pub mod freeze;
+pub mod migrate;
+pub mod checkpoint;
+pub mod velocity;
+pub mod mint_authority;
+pub mod metadata;
+pub mod redeem;
+pub mod bridge;
+pub mod relay;
+pub mod hold;
+pub mod stream;
+pub mod subscription;
+pub mod sanctions;
+pub mod travel_rule;
\ No newline at end of file
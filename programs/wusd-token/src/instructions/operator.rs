@@ -1,6 +1,7 @@
 use anchor_lang::prelude::*; 
+use anchor_spl::token::Mint;
 use crate::error::WusdError;  
-use crate::state::{AuthorityState, AccessRegistryState};
+use crate::state::{AuthorityState, AccessRegistryState, CreditAllowlistEntry, PendingRole, Role};
  
 /// 添加操作员
 pub fn add_operator(ctx: Context<ManageOperator>, operator: Pubkey) -> Result<()> {
@@ -14,7 +15,17 @@ pub fn add_operator(ctx: Context<ManageOperator>, operator: Pubkey) -> Result<()
     ); 
     
     // 添加操作员
-    access_registry.add_operator(operator) 
+    access_registry.add_operator(operator)?;
+
+    emit!(OperatorAdded {
+        version: crate::EVENT_VERSION,
+        actor: ctx.accounts.authority.key(),
+        mint: ctx.accounts.token_mint.key(),
+        operator,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
 }  
 
 /// 移除操作员
@@ -29,7 +40,127 @@ pub fn remove_operator(ctx: Context<ManageOperator>, operator: Pubkey) -> Result
     );
     
     // 移除操作员
-    access_registry.remove_operator(operator)
+    access_registry.remove_operator(operator)?;
+
+    emit!(OperatorRemoved {
+        version: crate::EVENT_VERSION,
+        actor: ctx.accounts.authority.key(),
+        mint: ctx.accounts.token_mint.key(),
+        operator,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+/// 发起角色变更，新地址签名接受后才生效
+/// * `role` - 要变更的角色
+/// * `account` - 新的角色地址
+pub fn propose_role(ctx: Context<ProposeRole>, role: Role, account: Pubkey) -> Result<()> {
+    require!(account != Pubkey::default(), WusdError::InvalidRoleAccount);
+
+    let now = Clock::get()?.unix_timestamp;
+    let pending = &mut ctx.accounts.pending_role;
+    pending.mint = ctx.accounts.token_mint.key();
+    pending.role = role;
+    pending.proposer = ctx.accounts.authority.key();
+    pending.new_account = account;
+    pending.proposed_at = now;
+
+    emit!(RoleChangeProposed {
+        version: crate::EVENT_VERSION,
+        actor: ctx.accounts.authority.key(),
+        mint: ctx.accounts.token_mint.key(),
+        role,
+        new_account: account,
+        timestamp: now,
+    });
+
+    Ok(())
+}
+
+/// 新地址接受角色变更
+/// * `role` - 要接受的角色
+pub fn accept_role(ctx: Context<AcceptRole>, role: Role) -> Result<()> {
+    let new_account = ctx.accounts.new_account.key();
+    let authority_state = &mut ctx.accounts.authority_state;
+    // 发起人在此期间失去管理员身份时，变更作废
+    require!(
+        authority_state.is_admin(ctx.accounts.pending_role.proposer),
+        WusdError::Unauthorized
+    );
+
+    let slot = match role {
+        Role::Admin => &mut authority_state.admin,
+        Role::Minter => &mut authority_state.minter,
+        Role::Pauser => &mut authority_state.pauser,
+    };
+    let old_account = *slot;
+    *slot = new_account;
+
+    emit!(RoleChanged {
+        version: crate::EVENT_VERSION,
+        actor: new_account,
+        mint: ctx.accounts.token_mint.key(),
+        role,
+        old_account,
+        new_account,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+/// 取消待接受的角色变更
+/// * `role` - 要取消的角色
+pub fn cancel_role(ctx: Context<CancelRole>, role: Role) -> Result<()> {
+    emit!(RoleChangeCancelled {
+        version: crate::EVENT_VERSION,
+        actor: ctx.accounts.authority.key(),
+        mint: ctx.accounts.token_mint.key(),
+        role,
+        new_account: ctx.accounts.pending_role.new_account,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+/// 开启或关闭入账白名单模式
+pub fn set_credit_allowlist_mode(ctx: Context<SetCreditAllowlistMode>, enabled: bool) -> Result<()> {
+    let access_registry = &mut ctx.accounts.access_registry;
+    require!(access_registry.initialized, WusdError::AccessRegistryNotInitialized);
+    access_registry.credit_allowlist = enabled;
+
+    emit!(CreditAllowlistModeSet {
+        version: crate::EVENT_VERSION,
+        actor: ctx.accounts.authority.key(),
+        mint: ctx.accounts.token_mint.key(),
+        enabled,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+/// 登记或撤销接收方的入账资格
+pub fn set_credit_allowlist(ctx: Context<ManageCreditAllowlist>, user: Pubkey, allowed: bool) -> Result<()> {
+    require!(ctx.accounts.access_registry.initialized, WusdError::AccessRegistryNotInitialized);
+
+    let allowlist_entry = &mut ctx.accounts.allowlist_entry;
+    allowlist_entry.user = user;
+    allowlist_entry.allowed = allowed;
+
+    emit!(CreditAllowlistUpdated {
+        version: crate::EVENT_VERSION,
+        actor: ctx.accounts.authority.key(),
+        mint: ctx.accounts.token_mint.key(),
+        user,
+        allowed,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
 }
 
 #[derive(Accounts)]
@@ -37,9 +168,15 @@ pub struct ManageOperator<'info> {
     #[account(mut)]
     pub authority: Signer<'info>,
 
-    #[account(constraint = authority_state.is_admin(authority.key()))]
+    #[account(
+        seeds = [b"authority", token_mint.key().as_ref()],
+        bump,
+        constraint = authority_state.is_admin(authority.key())
+    )]
     pub authority_state: Account<'info, AuthorityState>,
 
+    pub token_mint: Account<'info, Mint>,
+
     /// CHECK: 仅用于记录地址
     pub operator: AccountInfo<'info>,
 
@@ -47,4 +184,233 @@ pub struct ManageOperator<'info> {
     pub access_registry: Account<'info, AccessRegistryState>,
 
     pub system_program: Program<'info, System>,
-} 
\ No newline at end of file
+}
+
+#[derive(Accounts)]
+#[instruction(role: Role)]
+pub struct ProposeRole<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        seeds = [b"authority", token_mint.key().as_ref()],
+        bump,
+        constraint = authority_state.is_admin(authority.key()) @ WusdError::Unauthorized
+    )]
+    pub authority_state: Account<'info, AuthorityState>,
+
+    pub token_mint: Account<'info, Mint>,
+
+    /// 同一角色只保留一个待接受的变更，重复发起会覆盖
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = PendingRole::SIZE,
+        seeds = [b"pending_role", token_mint.key().as_ref(), &[role as u8]],
+        bump
+    )]
+    pub pending_role: Account<'info, PendingRole>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(role: Role)]
+pub struct AcceptRole<'info> {
+    pub new_account: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"authority", token_mint.key().as_ref()],
+        bump
+    )]
+    pub authority_state: Account<'info, AuthorityState>,
+
+    pub token_mint: Account<'info, Mint>,
+
+    #[account(
+        mut,
+        close = proposer,
+        seeds = [b"pending_role", token_mint.key().as_ref(), &[role as u8]],
+        bump,
+        constraint = pending_role.new_account == new_account.key() @ WusdError::Unauthorized
+    )]
+    pub pending_role: Account<'info, PendingRole>,
+
+    /// CHECK: 发起人，接收待变更账户退还的租金
+    #[account(mut, address = pending_role.proposer)]
+    pub proposer: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(role: Role)]
+pub struct CancelRole<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        seeds = [b"authority", token_mint.key().as_ref()],
+        bump,
+        constraint = authority_state.is_admin(authority.key()) @ WusdError::Unauthorized
+    )]
+    pub authority_state: Account<'info, AuthorityState>,
+
+    pub token_mint: Account<'info, Mint>,
+
+    #[account(
+        mut,
+        close = authority,
+        seeds = [b"pending_role", token_mint.key().as_ref(), &[role as u8]],
+        bump
+    )]
+    pub pending_role: Account<'info, PendingRole>,
+}
+
+#[derive(Accounts)]
+pub struct SetCreditAllowlistMode<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"access_registry"],
+        bump,
+        constraint = access_registry.authority == authority.key() @ WusdError::Unauthorized
+    )]
+    pub access_registry: Account<'info, AccessRegistryState>,
+
+    /// 注册表为全局账户，此处仅用于在事件中标识所属部署
+    pub token_mint: Account<'info, Mint>,
+}
+
+#[derive(Accounts)]
+#[instruction(user: Pubkey)]
+pub struct ManageCreditAllowlist<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        seeds = [b"access_registry"],
+        bump,
+        constraint = access_registry.authority == authority.key() @ WusdError::Unauthorized
+    )]
+    pub access_registry: Account<'info, AccessRegistryState>,
+
+    /// 注册表为全局账户，此处仅用于在事件中标识所属部署
+    pub token_mint: Account<'info, Mint>,
+
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = CreditAllowlistEntry::SIZE,
+        seeds = [b"allowlist", user.as_ref()],
+        bump
+    )]
+    pub allowlist_entry: Account<'info, CreditAllowlistEntry>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// 角色变更发起事件
+#[event]
+pub struct RoleChangeProposed {
+    /// 事件结构版本
+    pub version: u8,
+    /// 发起变更的管理员
+    pub actor: Pubkey,
+    /// 代币铸币账户地址
+    pub mint: Pubkey,
+    /// 变更的角色
+    pub role: Role,
+    /// 待接受的新角色地址
+    pub new_account: Pubkey,
+    pub timestamp: i64,
+}
+
+/// 角色变更取消事件
+#[event]
+pub struct RoleChangeCancelled {
+    /// 事件结构版本
+    pub version: u8,
+    /// 取消变更的管理员
+    pub actor: Pubkey,
+    /// 代币铸币账户地址
+    pub mint: Pubkey,
+    /// 变更的角色
+    pub role: Role,
+    /// 被取消的新角色地址
+    pub new_account: Pubkey,
+    pub timestamp: i64,
+}
+
+/// 入账白名单模式切换事件
+#[event]
+pub struct CreditAllowlistModeSet {
+    /// 事件结构版本
+    pub version: u8,
+    /// 执行操作的注册表管理员
+    pub actor: Pubkey,
+    /// 代币铸币账户地址
+    pub mint: Pubkey,
+    pub enabled: bool,
+    pub timestamp: i64,
+}
+
+/// 入账白名单登记变更事件
+#[event]
+pub struct CreditAllowlistUpdated {
+    /// 事件结构版本
+    pub version: u8,
+    /// 执行操作的注册表管理员
+    pub actor: Pubkey,
+    /// 代币铸币账户地址
+    pub mint: Pubkey,
+    pub user: Pubkey,
+    pub allowed: bool,
+    pub timestamp: i64,
+}
+
+/// 操作员添加事件
+#[event]
+pub struct OperatorAdded {
+    /// 事件结构版本
+    pub version: u8,
+    /// 执行操作的管理员
+    pub actor: Pubkey,
+    /// 代币铸币账户地址
+    pub mint: Pubkey,
+    /// 新增的操作员
+    pub operator: Pubkey,
+    pub timestamp: i64,
+}
+
+/// 操作员移除事件
+#[event]
+pub struct OperatorRemoved {
+    /// 事件结构版本
+    pub version: u8,
+    /// 执行操作的管理员
+    pub actor: Pubkey,
+    /// 代币铸币账户地址
+    pub mint: Pubkey,
+    /// 被移除的操作员
+    pub operator: Pubkey,
+    pub timestamp: i64,
+}
+
+/// 角色变更事件
+#[event]
+pub struct RoleChanged {
+    /// 事件结构版本
+    pub version: u8,
+    /// 接受变更的新角色地址
+    pub actor: Pubkey,
+    /// 代币铸币账户地址
+    pub mint: Pubkey,
+    /// 变更的角色
+    pub role: Role,
+    /// 原角色地址
+    pub old_account: Pubkey,
+    /// 新角色地址
+    pub new_account: Pubkey,
+    pub timestamp: i64,
+}
@@ -1,4 +1,5 @@
 use anchor_lang::prelude::*;
+use anchor_spl::token::Mint;
 use crate::error::WusdError;  
 use crate::state::{AuthorityState, PauseState};
 
@@ -10,6 +11,15 @@ pub fn pause(ctx: Context<Pause>) -> Result<()> {
         WusdError::NotPauser
     );
     ctx.accounts.pause_state.set_paused(true);
+
+    emit!(PauseEvent {
+        version: crate::EVENT_VERSION,
+        actor: ctx.accounts.authority.key(),
+        mint: ctx.accounts.token_mint.key(),
+        paused: true,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
     Ok(())
 }
 
@@ -21,21 +31,62 @@ pub fn unpause(ctx: Context<Unpause>) -> Result<()> {
         WusdError::NotPauser
     );
     ctx.accounts.pause_state.set_paused(false);
+
+    emit!(PauseEvent {
+        version: crate::EVENT_VERSION,
+        actor: ctx.accounts.authority.key(),
+        mint: ctx.accounts.token_mint.key(),
+        paused: false,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
     Ok(())
 }
 
 #[derive(Accounts)]
 pub struct Pause<'info> {
-    #[account(mut)]
+    #[account(
+        mut,
+        seeds = [b"pause_state", token_mint.key().as_ref()],
+        bump
+    )]
     pub pause_state: Account<'info, PauseState>,
     pub authority: Signer<'info>,
+    #[account(
+        seeds = [b"authority", token_mint.key().as_ref()],
+        bump
+    )]
     pub authority_state: Account<'info, AuthorityState>,
+    pub token_mint: Account<'info, Mint>,
 }
 
 #[derive(Accounts)]
 pub struct Unpause<'info> {
-    #[account(mut)]
+    #[account(
+        mut,
+        seeds = [b"pause_state", token_mint.key().as_ref()],
+        bump
+    )]
     pub pause_state: Account<'info, PauseState>,
     pub authority: Signer<'info>,
+    #[account(
+        seeds = [b"authority", token_mint.key().as_ref()],
+        bump
+    )]
     pub authority_state: Account<'info, AuthorityState>,
+    pub token_mint: Account<'info, Mint>,
+}
+
+/// 暂停状态变更事件
+#[event]
+pub struct PauseEvent {
+    /// 事件结构版本
+    pub version: u8,
+    /// 执行暂停/恢复的地址
+    pub actor: Pubkey,
+    /// 代币铸币账户地址
+    pub mint: Pubkey,
+    /// 变更后是否暂停
+    pub paused: bool,
+    pub timestamp: i64,
 }
\ No newline at end of file
@@ -29,10 +29,13 @@ pub fn permit(ctx: Context<Permit>, params: PermitParams) -> Result<()> {
     
     // 发出授权许可事件
     emit!(PermitGranted { 
-        owner: ctx.accounts.owner.key(),
+        version: crate::EVENT_VERSION,
+        actor: ctx.accounts.owner.key(),
+        mint: ctx.accounts.mint_state.mint,
         spender: ctx.accounts.spender.key(),
         amount: params.amount,
-        scope: PermitScope::TRANSFER
+        scope: PermitScope::TRANSFER,
+        timestamp: Clock::get()?.unix_timestamp,
     });
     
     Ok(())
@@ -119,14 +122,19 @@ pub struct PermitMessage {
 /// 许可授权事件，记录EIP-2612兼容的许可授权信息
 #[event]
 pub struct PermitGranted {
-    /// 代币所有者地址
-    pub owner: Pubkey,
+    /// 事件结构版本
+    pub version: u8,
+    /// 代币所有者地址，即发起授权的账户
+    pub actor: Pubkey,
+    /// 代币铸币账户地址
+    pub mint: Pubkey,
     /// 被授权者地址
     pub spender: Pubkey,
     /// 授权金额
     pub amount: u64,
     /// 授权范围
     pub scope: PermitScope,
+    pub timestamp: i64,
 }    
 
 impl PermitScope {
@@ -0,0 +1,292 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, Token, TokenAccount, Mint};
+use crate::{AccessLevel, error::WusdError};
+use crate::state::{AuthorityState, AccessRegistryState, PauseState, FreezeState, RedemptionRecord, RedemptionStatus};
+use crate::utils::{record_checkpoint, require_not_sanctioned};
+
+/// 销毁代币并登记赎回请求，线下打款后由操作员更新状态
+/// * `redemption_id` - 赎回编号
+/// * `bank_reference_hash` - 银行收款信息的哈希
+/// * `amount` - 赎回数量
+pub fn redeem(
+    ctx: Context<Redeem>,
+    redemption_id: u64,
+    bank_reference_hash: [u8; 32],
+    amount: u64,
+) -> Result<()> {
+    // 验证合约未暂停
+    ctx.accounts.pause_state.validate_not_paused()?;
+    require!(amount > 0, WusdError::InvalidAmount);
+
+    // 验证访问权限
+    require!(
+        ctx.accounts.access_registry.has_access(
+            ctx.accounts.owner.key(),
+            AccessLevel::Debit
+        ),
+        WusdError::AccessDenied
+    );
+
+    // 冻结或在制裁名单中的账户不能换取线下打款
+    ctx.accounts.freeze_state.check_frozen()?;
+    require_not_sanctioned(
+        &ctx.accounts.sanctions_config,
+        &ctx.accounts.owner_clearance,
+        ctx.accounts.owner.key(),
+    )?;
+
+    // 验证余额充足
+    require!(
+        ctx.accounts.token_account.amount >= amount,
+        WusdError::InsufficientBalance
+    );
+
+    token::burn(
+        CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            token::Burn {
+                mint: ctx.accounts.token_mint.to_account_info(),
+                from: ctx.accounts.token_account.to_account_info(),
+                authority: ctx.accounts.owner.to_account_info(),
+            },
+        ),
+        amount,
+    )?;
+
+    // 更新余额快照
+    record_checkpoint(
+        &ctx.accounts.checkpoints,
+        &mut ctx.accounts.token_account,
+    )?;
+
+    let now = Clock::get()?.unix_timestamp;
+    let record = &mut ctx.accounts.redemption_record;
+    record.owner = ctx.accounts.owner.key();
+    record.mint = ctx.accounts.token_mint.key();
+    record.token_account = ctx.accounts.token_account.key();
+    record.redemption_id = redemption_id;
+    record.bank_reference_hash = bank_reference_hash;
+    record.amount = amount;
+    record.status = RedemptionStatus::Pending;
+    record.created_at = now;
+    record.updated_at = now;
+
+    emit!(RedemptionRequested {
+        version: crate::EVENT_VERSION,
+        owner: ctx.accounts.owner.key(),
+        mint: ctx.accounts.token_mint.key(),
+        redemption_id,
+        bank_reference_hash,
+        amount,
+        timestamp: now,
+    });
+
+    Ok(())
+}
+
+/// 更新赎回状态，拒绝时将销毁的代币重新铸造给用户
+/// * `status` - 新状态，只能是 Paid 或 Rejected
+pub fn update_redemption_status(ctx: Context<UpdateRedemptionStatus>, status: RedemptionStatus) -> Result<()> {
+    // 验证操作员权限
+    require!(
+        ctx.accounts.access_registry.is_operator(ctx.accounts.operator.key()),
+        WusdError::Unauthorized
+    );
+    require!(
+        ctx.accounts.redemption_record.status == RedemptionStatus::Pending,
+        WusdError::RedemptionNotPending
+    );
+    require!(status != RedemptionStatus::Pending, WusdError::InvalidRedemptionStatus);
+
+    let amount = ctx.accounts.redemption_record.amount;
+    if status == RedemptionStatus::Rejected {
+        // 退回销毁的代币
+        let mint_key = ctx.accounts.token_mint.key();
+        let bump = *ctx.bumps.get("authority_state").unwrap();
+        let seeds = &[b"authority", mint_key.as_ref(), &[bump]];
+        token::mint_to(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                token::MintTo {
+                    mint: ctx.accounts.token_mint.to_account_info(),
+                    to: ctx.accounts.token_account.to_account_info(),
+                    authority: ctx.accounts.authority_state.to_account_info(),
+                },
+                &[&seeds[..]],
+            ),
+            amount,
+        )?;
+
+        record_checkpoint(
+            &ctx.accounts.checkpoints,
+            &mut ctx.accounts.token_account,
+        )?;
+    }
+
+    let now = Clock::get()?.unix_timestamp;
+    let record = &mut ctx.accounts.redemption_record;
+    let old_status = record.status;
+    record.status = status;
+    record.updated_at = now;
+
+    emit!(RedemptionStatusUpdated {
+        version: crate::EVENT_VERSION,
+        actor: ctx.accounts.operator.key(),
+        mint: record.mint,
+        owner: record.owner,
+        redemption_id: record.redemption_id,
+        old_status,
+        new_status: status,
+        amount,
+        timestamp: now,
+    });
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+#[instruction(redemption_id: u64)]
+pub struct Redeem<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(mut)]
+    pub token_mint: Account<'info, Mint>,
+
+    #[account(
+        mut,
+        constraint = token_account.owner == owner.key() @ WusdError::InvalidOwner,
+        constraint = token_account.mint == token_mint.key() @ WusdError::InvalidMint
+    )]
+    pub token_account: Account<'info, TokenAccount>,
+
+    #[account(
+        seeds = [b"pause_state", token_mint.key().as_ref()],
+        bump
+    )]
+    pub pause_state: Account<'info, PauseState>,
+
+    #[account(
+        seeds = [b"access_registry"],
+        bump
+    )]
+    pub access_registry: Account<'info, AccessRegistryState>,
+
+    #[account(
+        seeds = [b"freeze", token_account.key().as_ref()],
+        bump
+    )]
+    pub freeze_state: Account<'info, FreezeState>,
+
+    /// CHECK: 制裁名单配置，未配置时为空账户
+    #[account(
+        seeds = [b"sanctions", token_mint.key().as_ref()],
+        bump
+    )]
+    pub sanctions_config: UncheckedAccount<'info>,
+
+    /// CHECK: 赎回人的制裁名单检查缓存，未通过检查时为空账户
+    #[account(
+        seeds = [b"clearance", token_mint.key().as_ref(), owner.key().as_ref()],
+        bump
+    )]
+    pub owner_clearance: UncheckedAccount<'info>,
+
+    /// CHECK: 余额快照账户，未开启快照时为空账户
+    #[account(
+        mut,
+        seeds = [b"checkpoint", token_account.key().as_ref()],
+        bump
+    )]
+    pub checkpoints: UncheckedAccount<'info>,
+
+    #[account(
+        init,
+        payer = owner,
+        space = RedemptionRecord::SIZE,
+        seeds = [b"redemption", owner.key().as_ref(), &redemption_id.to_le_bytes()],
+        bump
+    )]
+    pub redemption_record: Account<'info, RedemptionRecord>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateRedemptionStatus<'info> {
+    pub operator: Signer<'info>,
+
+    #[account(
+        seeds = [b"access_registry"],
+        bump
+    )]
+    pub access_registry: Account<'info, AccessRegistryState>,
+
+    #[account(mut)]
+    pub token_mint: Account<'info, Mint>,
+
+    /// 铸币权 PDA，拒绝赎回时用于重新铸币
+    #[account(
+        seeds = [b"authority", token_mint.key().as_ref()],
+        bump
+    )]
+    pub authority_state: Account<'info, AuthorityState>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"redemption",
+            redemption_record.owner.as_ref(),
+            &redemption_record.redemption_id.to_le_bytes()
+        ],
+        bump,
+        constraint = redemption_record.mint == token_mint.key() @ WusdError::InvalidMint
+    )]
+    pub redemption_record: Account<'info, RedemptionRecord>,
+
+    #[account(
+        mut,
+        constraint = token_account.key() == redemption_record.token_account @ WusdError::InvalidOwner
+    )]
+    pub token_account: Account<'info, TokenAccount>,
+
+    /// CHECK: 余额快照账户，未开启快照时为空账户
+    #[account(
+        mut,
+        seeds = [b"checkpoint", token_account.key().as_ref()],
+        bump
+    )]
+    pub checkpoints: UncheckedAccount<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+/// 赎回请求事件
+#[event]
+pub struct RedemptionRequested {
+    /// 事件结构版本
+    pub version: u8,
+    pub owner: Pubkey,
+    pub mint: Pubkey,
+    pub redemption_id: u64,
+    pub bank_reference_hash: [u8; 32],
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+/// 赎回状态变更事件
+#[event]
+pub struct RedemptionStatusUpdated {
+    /// 事件结构版本
+    pub version: u8,
+    /// 执行变更的操作员
+    pub actor: Pubkey,
+    pub mint: Pubkey,
+    pub owner: Pubkey,
+    pub redemption_id: u64,
+    pub old_status: RedemptionStatus,
+    pub new_status: RedemptionStatus,
+    pub amount: u64,
+    pub timestamp: i64,
+}
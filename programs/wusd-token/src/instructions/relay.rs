@@ -0,0 +1,371 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::sysvar::instructions as ix_sysvar;
+use anchor_spl::token::{self, Token, TokenAccount};
+use crate::error::WusdError;
+use crate::state::{FreezeState, PermitState, MintState, AccessRegistryState, PauseState, TravelRuleLink};
+use crate::utils::{
+    require_has_access, require_credit_access, require_not_sanctioned, record_checkpoint, apply_velocity_limit,
+    consume_travel_rule, ed25519_signers,
+};
+
+/// 代付转账：持有人在链下签名转账意图，由中继者提交交易并支付 SOL 手续费，
+/// 中继者从转出账户获得不超过签名上限的 WUSD 手续费
+///
+/// 持有人需事先将转出代币账户委托给 `[b"relay", owner]` PDA（SPL approve，可由中继者代付），
+/// 委托额度即为代付转账的总上限；同一交易中须先包含 Ed25519 程序对签名消息的验签指令，
+/// 签名消息见 `RelayedTransferIntent::signing_message`
+///
+/// # 参数
+/// * `intent` - 持有人签名的转账意图
+/// * `fee` - 本次收取的手续费，不能超过 `intent.max_fee`
+pub fn relayed_transfer(ctx: Context<RelayedTransfer>, intent: RelayedTransferIntent, fee: u64) -> Result<()> {
+    ctx.accounts.pause_state.validate_not_paused()?;
+    require!(intent.amount > 0, WusdError::InvalidAmount);
+    require!(fee <= intent.max_fee, WusdError::RelayerFeeTooHigh);
+
+    // 签名意图必须与本次提交的账户一致
+    require!(intent.owner == ctx.accounts.owner.key(), WusdError::InvalidOwner);
+    require!(
+        intent.from_token == ctx.accounts.from_token.key()
+            && intent.to_token == ctx.accounts.to_token.key(),
+        WusdError::InvalidSignature
+    );
+
+    // 验证持有人签名
+    let signers = ed25519_signers(&ctx.accounts.instructions, &intent.signing_message()?)?;
+    require!(signers.contains(&intent.owner), WusdError::InvalidSignature);
+
+    // 与签名许可相同的随机数和期限语义
+    let now = Clock::get()?.unix_timestamp;
+    require!(intent.deadline > now, WusdError::PermitExpired);
+    let relay_bump = *ctx.bumps.get("relay_permit").unwrap();
+    let relay_key = ctx.accounts.relay_permit.key();
+    let relay_permit = &mut ctx.accounts.relay_permit;
+    if relay_permit.owner == Pubkey::default() {
+        relay_permit.set_inner(PermitState::initialize(intent.owner, relay_key, 0, 0, relay_bump));
+    }
+    relay_permit.validate_nonce(intent.nonce)?;
+    relay_permit.expiration = intent.deadline;
+    relay_permit.increment_nonce();
+
+    ctx.accounts.from_freeze_state.check_frozen()?;
+    ctx.accounts.to_freeze_state.check_frozen()?;
+    ctx.accounts.relayer_freeze_state.check_frozen()?;
+
+    let total = intent.amount
+        .checked_add(fee)
+        .ok_or(WusdError::InvalidAmount)?;
+    require!(
+        ctx.accounts.from_token.amount >= total,
+        WusdError::InsufficientBalance
+    );
+
+    require_has_access(
+        intent.owner,
+        true,
+        Some(intent.amount),
+        &ctx.accounts.pause_state,
+        Some(&ctx.accounts.access_registry),
+    )?;
+    require_credit_access(
+        &ctx.accounts.access_registry,
+        ctx.accounts.to_token.owner,
+        &ctx.accounts.to_allowlist,
+    )?;
+    // 手续费同样是一笔入账
+    require_credit_access(
+        &ctx.accounts.access_registry,
+        ctx.accounts.relayer_token.owner,
+        &ctx.accounts.relayer_allowlist,
+    )?;
+
+    // 检查制裁名单
+    require_not_sanctioned(
+        &ctx.accounts.sanctions_config,
+        &ctx.accounts.from_clearance,
+        ctx.accounts.from_token.owner,
+    )?;
+    require_not_sanctioned(
+        &ctx.accounts.sanctions_config,
+        &ctx.accounts.to_clearance,
+        ctx.accounts.to_token.owner,
+    )?;
+    require_not_sanctioned(
+        &ctx.accounts.sanctions_config,
+        &ctx.accounts.relayer_clearance,
+        ctx.accounts.relayer_token.owner,
+    )?;
+
+    // 大额转账需附带旅行规则记录
+    let travel_rule = consume_travel_rule(
+        &ctx.accounts.mint_state,
+        &ctx.accounts.travel_rule_record,
+        ctx.accounts.from_token.key(),
+        ctx.accounts.to_token.key(),
+        intent.amount,
+    )?;
+
+    apply_velocity_limit(
+        &ctx.accounts.from_velocity_limit,
+        &ctx.accounts.velocity_config,
+        ctx.accounts.from_token.key(),
+        &ctx.accounts.relayer.to_account_info(),
+        &ctx.accounts.system_program.to_account_info(),
+        total,
+    )?;
+
+    // 由委托 PDA 签名转出
+    let owner_key = intent.owner;
+    let seeds = &[b"relay", owner_key.as_ref(), &[relay_bump]];
+    token::transfer(
+        CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            token::Transfer {
+                from: ctx.accounts.from_token.to_account_info(),
+                to: ctx.accounts.to_token.to_account_info(),
+                authority: ctx.accounts.relay_permit.to_account_info(),
+            },
+            &[&seeds[..]],
+        ),
+        intent.amount,
+    )?;
+    if fee > 0 {
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                token::Transfer {
+                    from: ctx.accounts.from_token.to_account_info(),
+                    to: ctx.accounts.relayer_token.to_account_info(),
+                    authority: ctx.accounts.relay_permit.to_account_info(),
+                },
+                &[&seeds[..]],
+            ),
+            fee,
+        )?;
+    }
+
+    // 更新各方余额快照
+    record_checkpoint(
+        &ctx.accounts.from_checkpoints,
+        &mut ctx.accounts.from_token,
+    )?;
+    record_checkpoint(
+        &ctx.accounts.to_checkpoints,
+        &mut ctx.accounts.to_token,
+    )?;
+    record_checkpoint(
+        &ctx.accounts.relayer_checkpoints,
+        &mut ctx.accounts.relayer_token,
+    )?;
+
+    emit!(RelayedTransferEvent {
+        version: crate::EVENT_VERSION,
+        owner: intent.owner,
+        relayer: ctx.accounts.relayer.key(),
+        mint: ctx.accounts.from_token.mint,
+        to: ctx.accounts.to_token.owner,
+        amount: intent.amount,
+        fee,
+        nonce: intent.nonce,
+        timestamp: now,
+        travel_rule,
+    });
+
+    Ok(())
+}
+
+/// 代付转账签名消息的域标签，避免签名被当作其他用途的消息
+pub const RELAY_DOMAIN: &[u8] = b"WUSD_RELAYED_TRANSFER_V1";
+
+/// 持有人链下签名的转账意图
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct RelayedTransferIntent {
+    /// 转出代币账户的所有者
+    pub owner: Pubkey,
+    /// 转出代币账户
+    pub from_token: Pubkey,
+    /// 转入代币账户
+    pub to_token: Pubkey,
+    /// 转账金额
+    pub amount: u64,
+    /// 允许中继者收取的最高手续费
+    pub max_fee: u64,
+    /// 随机数，必须等于当前代付许可的随机数
+    pub nonce: u64,
+    /// 签名有效期
+    pub deadline: i64,
+}
+
+impl RelayedTransferIntent {
+    /// 持有人实际签名的消息：域标签 + 本程序地址 + 意图的 Borsh 序列化结果
+    /// 程序地址区分不同部署，签名不能在其他部署上重放
+    pub fn signing_message(&self) -> Result<Vec<u8>> {
+        let mut message = RELAY_DOMAIN.to_vec();
+        message.extend_from_slice(crate::ID.as_ref());
+        self.serialize(&mut message)?;
+        Ok(message)
+    }
+}
+
+#[derive(Accounts)]
+pub struct RelayedTransfer<'info> {
+    /// 中继者，支付交易手续费并收取 WUSD 手续费
+    #[account(mut)]
+    pub relayer: Signer<'info>,
+    /// CHECK: 转出代币账户的所有者，由签名意图验证
+    pub owner: AccountInfo<'info>,
+    /// 代付许可，保存随机数，同时作为转出代币账户的委托人
+    #[account(
+        init_if_needed,
+        payer = relayer,
+        space = PermitState::SIZE,
+        seeds = [b"relay", owner.key().as_ref()],
+        bump
+    )]
+    pub relay_permit: Box<Account<'info, PermitState>>,
+    #[account(
+        mut,
+        constraint = from_token.owner == owner.key() @ WusdError::InvalidOwner,
+        constraint = from_token.mint == to_token.mint @ WusdError::InvalidMint
+    )]
+    pub from_token: Box<Account<'info, TokenAccount>>,
+    #[account(mut)]
+    pub to_token: Box<Account<'info, TokenAccount>>,
+    #[account(
+        mut,
+        constraint = relayer_token.mint == from_token.mint @ WusdError::InvalidMint
+    )]
+    pub relayer_token: Box<Account<'info, TokenAccount>>,
+    #[account(
+        seeds = [b"pause_state", from_token.mint.as_ref()],
+        bump
+    )]
+    pub pause_state: Box<Account<'info, PauseState>>,
+    #[account(
+        seeds = [b"access_registry"],
+        bump
+    )]
+    pub access_registry: Box<Account<'info, AccessRegistryState>>,
+    #[account(
+        seeds = [b"freeze", from_token.key().as_ref()],
+        bump
+    )]
+    pub from_freeze_state: Box<Account<'info, FreezeState>>,
+    #[account(
+        seeds = [b"freeze", to_token.key().as_ref()],
+        bump
+    )]
+    pub to_freeze_state: Box<Account<'info, FreezeState>>,
+    #[account(
+        seeds = [b"freeze", relayer_token.key().as_ref()],
+        bump
+    )]
+    pub relayer_freeze_state: Box<Account<'info, FreezeState>>,
+    /// CHECK: 转出方余额快照账户，未开启快照时为空账户
+    #[account(
+        mut,
+        seeds = [b"checkpoint", from_token.key().as_ref()],
+        bump
+    )]
+    pub from_checkpoints: UncheckedAccount<'info>,
+    /// CHECK: 转入方余额快照账户，未开启快照时为空账户
+    #[account(
+        mut,
+        seeds = [b"checkpoint", to_token.key().as_ref()],
+        bump
+    )]
+    pub to_checkpoints: UncheckedAccount<'info>,
+    /// CHECK: 中继者余额快照账户，未开启快照时为空账户
+    #[account(
+        mut,
+        seeds = [b"checkpoint", relayer_token.key().as_ref()],
+        bump
+    )]
+    pub relayer_checkpoints: UncheckedAccount<'info>,
+    /// CHECK: 转出方限额状态账户，尚未创建时为空账户，配置默认档位后首次转出时创建
+    #[account(
+        mut,
+        seeds = [b"velocity", from_token.key().as_ref()],
+        bump
+    )]
+    pub from_velocity_limit: UncheckedAccount<'info>,
+    /// CHECK: 限额全局配置，未设置默认档位时为空账户
+    #[account(
+        seeds = [b"velocity_config", from_token.mint.as_ref()],
+        bump
+    )]
+    pub velocity_config: UncheckedAccount<'info>,
+    /// CHECK: 接收方入账白名单登记，未登记时为空账户
+    #[account(
+        seeds = [b"allowlist", to_token.owner.as_ref()],
+        bump
+    )]
+    pub to_allowlist: UncheckedAccount<'info>,
+    /// CHECK: 中继者入账白名单登记，未登记时为空账户
+    #[account(
+        seeds = [b"allowlist", relayer_token.owner.as_ref()],
+        bump
+    )]
+    pub relayer_allowlist: UncheckedAccount<'info>,
+    /// CHECK: 制裁名单配置，未配置时为空账户
+    #[account(
+        seeds = [b"sanctions", from_token.mint.as_ref()],
+        bump
+    )]
+    pub sanctions_config: UncheckedAccount<'info>,
+    /// CHECK: 转出方制裁名单检查缓存，未通过检查时为空账户
+    #[account(
+        seeds = [b"clearance", from_token.mint.as_ref(), from_token.owner.as_ref()],
+        bump
+    )]
+    pub from_clearance: UncheckedAccount<'info>,
+    /// CHECK: 转入方制裁名单检查缓存，未通过检查时为空账户
+    #[account(
+        seeds = [b"clearance", from_token.mint.as_ref(), to_token.owner.as_ref()],
+        bump
+    )]
+    pub to_clearance: UncheckedAccount<'info>,
+    /// CHECK: 中继者制裁名单检查缓存，未通过检查时为空账户
+    #[account(
+        seeds = [b"clearance", from_token.mint.as_ref(), relayer_token.owner.as_ref()],
+        bump
+    )]
+    pub relayer_clearance: UncheckedAccount<'info>,
+    #[account(
+        seeds = [b"mint_state", from_token.mint.as_ref()],
+        bump
+    )]
+    pub mint_state: Box<Account<'info, MintState>>,
+    /// CHECK: 转出方旅行规则记录，未登记时为空账户
+    #[account(
+        mut,
+        seeds = [b"travel_rule", from_token.key().as_ref()],
+        bump
+    )]
+    pub travel_rule_record: UncheckedAccount<'info>,
+    /// CHECK: 指令 sysvar，用于读取同一交易中的 Ed25519 验签指令
+    #[account(address = ix_sysvar::ID)]
+    pub instructions: UncheckedAccount<'info>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+/// 代付转账事件
+#[event]
+pub struct RelayedTransferEvent {
+    /// 事件结构版本
+    pub version: u8,
+    pub owner: Pubkey,
+    pub relayer: Pubkey,
+    pub mint: Pubkey,
+    /// 转入地址
+    pub to: Pubkey,
+    pub amount: u64,
+    /// 中继者收取的手续费
+    pub fee: u64,
+    /// 本次消耗的随机数
+    pub nonce: u64,
+    pub timestamp: i64,
+    /// 大额转账关联的旅行规则信息
+    pub travel_rule: Option<TravelRuleLink>,
+}
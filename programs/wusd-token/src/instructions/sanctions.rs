@@ -0,0 +1,246 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::hash::hashv;
+use anchor_spl::token::Mint;
+use crate::error::WusdError;
+use crate::state::{AuthorityState, SanctionsConfig, SanctionsClearance};
+
+/// Merkle 树叶子节点哈希前缀
+const LEAF_PREFIX: &[u8] = &[0];
+/// Merkle 树内部节点哈希前缀
+const NODE_PREFIX: &[u8] = &[1];
+
+/// 设置制裁名单的合规角色和强制检查开关
+/// * `compliance` - 合规角色地址
+/// * `enforced` - 是否在转账时强制检查
+pub fn configure_sanctions(ctx: Context<ConfigureSanctions>, compliance: Pubkey, enforced: bool) -> Result<()> {
+    let config = &mut ctx.accounts.sanctions_config;
+    config.mint = ctx.accounts.token_mint.key();
+    config.compliance = compliance;
+    config.enforced = enforced;
+
+    emit!(SanctionsConfigured {
+        version: crate::EVENT_VERSION,
+        actor: ctx.accounts.authority.key(),
+        mint: ctx.accounts.token_mint.key(),
+        compliance,
+        enforced,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+/// 合规角色更新被封禁所有者的 Merkle 根，已有的检查缓存随之失效
+/// * `merkle_root` - 新的 Merkle 根
+pub fn update_sanctions_root(ctx: Context<UpdateSanctionsRoot>, merkle_root: [u8; 32]) -> Result<()> {
+    let now = Clock::get()?.unix_timestamp;
+    let config = &mut ctx.accounts.sanctions_config;
+    let old_root = config.merkle_root;
+    config.merkle_root = merkle_root;
+    config.updated_at = now;
+
+    emit!(SanctionsRootUpdated {
+        version: crate::EVENT_VERSION,
+        actor: ctx.accounts.compliance.key(),
+        mint: config.mint,
+        old_root,
+        new_root: merkle_root,
+        timestamp: now,
+    });
+
+    Ok(())
+}
+
+/// 提交所有者不在制裁名单中的证明，通过后缓存至检查账户供转账使用
+/// 任何人都可以为任意所有者提交证明
+/// * `owner` - 被检查的所有者
+/// * `proof` - 非成员证明
+pub fn clear_sanctions(ctx: Context<ClearSanctions>, owner: Pubkey, proof: NonMembershipProof) -> Result<()> {
+    let config = &ctx.accounts.sanctions_config;
+    proof.verify(&config.merkle_root, &owner)?;
+
+    let now = Clock::get()?.unix_timestamp;
+    let clearance = &mut ctx.accounts.clearance;
+    clearance.owner = owner;
+    clearance.mint = config.mint;
+    clearance.merkle_root = config.merkle_root;
+    clearance.cleared_at = now;
+
+    emit!(SanctionsCleared {
+        version: crate::EVENT_VERSION,
+        actor: ctx.accounts.payer.key(),
+        mint: config.mint,
+        owner,
+        merkle_root: config.merkle_root,
+        timestamp: now,
+    });
+
+    Ok(())
+}
+
+/// 排序叶子相邻证明
+///
+/// 链下将被封禁所有者的哈希 `sha256(owner)` 升序排列作为叶子，并在两端加入全 0 和全 1 哨兵，
+/// 叶子数不足 2 的幂时用全 1 补齐。叶子节点为 `sha256(0x00 || value)`，
+/// 内部节点为 `sha256(0x01 || left || right)`。
+/// 证明给出两个相邻叶子，且被检查值严格介于两者之间，即可说明其不在名单中
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct NonMembershipProof {
+    /// 左侧叶子的值
+    pub left_value: [u8; 32],
+    /// 左侧叶子的位置
+    pub left_index: u32,
+    /// 左侧叶子的兄弟节点路径，自底向上
+    pub left_proof: Vec<[u8; 32]>,
+    /// 右侧叶子的值
+    pub right_value: [u8; 32],
+    /// 右侧叶子的兄弟节点路径，自底向上
+    pub right_proof: Vec<[u8; 32]>,
+}
+
+impl NonMembershipProof {
+    /// 验证所有者不在 Merkle 根对应的名单中
+    /// * `root` - Merkle 根
+    /// * `owner` - 被检查的所有者
+    pub fn verify(&self, root: &[u8; 32], owner: &Pubkey) -> Result<()> {
+        let target = hashv(&[owner.as_ref()]).to_bytes();
+        require!(
+            self.left_value < target && target < self.right_value,
+            WusdError::InvalidSanctionsProof
+        );
+
+        // 两条路径深度一致，保证都从叶子层开始
+        require!(
+            self.left_proof.len() == self.right_proof.len() && self.left_proof.len() < 32,
+            WusdError::InvalidSanctionsProof
+        );
+        let right_index = self.left_index
+            .checked_add(1)
+            .ok_or(WusdError::InvalidSanctionsProof)?;
+        require!(
+            right_index >> self.left_proof.len() == 0,
+            WusdError::InvalidSanctionsProof
+        );
+
+        require!(
+            compute_root(&self.left_value, self.left_index, &self.left_proof) == *root
+                && compute_root(&self.right_value, right_index, &self.right_proof) == *root,
+            WusdError::InvalidSanctionsProof
+        );
+        Ok(())
+    }
+}
+
+/// 按叶子位置自底向上计算 Merkle 根
+fn compute_root(value: &[u8; 32], index: u32, proof: &[[u8; 32]]) -> [u8; 32] {
+    let mut node = hashv(&[LEAF_PREFIX, value]).to_bytes();
+    let mut index = index;
+    for sibling in proof {
+        node = if index & 1 == 0 {
+            hashv(&[NODE_PREFIX, &node, sibling]).to_bytes()
+        } else {
+            hashv(&[NODE_PREFIX, sibling, &node]).to_bytes()
+        };
+        index >>= 1;
+    }
+    node
+}
+
+#[derive(Accounts)]
+pub struct ConfigureSanctions<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub token_mint: Account<'info, Mint>,
+
+    #[account(
+        seeds = [b"authority", token_mint.key().as_ref()],
+        bump,
+        constraint = authority_state.is_admin(authority.key()) @ WusdError::Unauthorized
+    )]
+    pub authority_state: Account<'info, AuthorityState>,
+
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = SanctionsConfig::SIZE,
+        seeds = [b"sanctions", token_mint.key().as_ref()],
+        bump
+    )]
+    pub sanctions_config: Account<'info, SanctionsConfig>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateSanctionsRoot<'info> {
+    pub compliance: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"sanctions", sanctions_config.mint.as_ref()],
+        bump,
+        constraint = sanctions_config.compliance == compliance.key() @ WusdError::Unauthorized
+    )]
+    pub sanctions_config: Account<'info, SanctionsConfig>,
+}
+
+#[derive(Accounts)]
+#[instruction(owner: Pubkey)]
+pub struct ClearSanctions<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(
+        seeds = [b"sanctions", sanctions_config.mint.as_ref()],
+        bump
+    )]
+    pub sanctions_config: Account<'info, SanctionsConfig>,
+
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = SanctionsClearance::SIZE,
+        seeds = [b"clearance", sanctions_config.mint.as_ref(), owner.as_ref()],
+        bump
+    )]
+    pub clearance: Account<'info, SanctionsClearance>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// 制裁名单配置更新事件
+#[event]
+pub struct SanctionsConfigured {
+    /// 事件结构版本
+    pub version: u8,
+    pub actor: Pubkey,
+    pub mint: Pubkey,
+    pub compliance: Pubkey,
+    pub enforced: bool,
+    pub timestamp: i64,
+}
+
+/// 制裁名单 Merkle 根更新事件
+#[event]
+pub struct SanctionsRootUpdated {
+    /// 事件结构版本
+    pub version: u8,
+    pub actor: Pubkey,
+    pub mint: Pubkey,
+    pub old_root: [u8; 32],
+    pub new_root: [u8; 32],
+    pub timestamp: i64,
+}
+
+/// 制裁名单检查通过事件
+#[event]
+pub struct SanctionsCleared {
+    /// 事件结构版本
+    pub version: u8,
+    pub actor: Pubkey,
+    pub mint: Pubkey,
+    pub owner: Pubkey,
+    pub merkle_root: [u8; 32],
+    pub timestamp: i64,
+}
@@ -0,0 +1,633 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, Token, TokenAccount, Mint};
+use crate::error::WusdError;
+use crate::state::{FreezeState, MintState, AccessRegistryState, PauseState, PaymentStream, TravelRuleLink};
+use crate::utils::{
+    require_has_access, require_credit_access, require_not_sanctioned, record_checkpoint, apply_velocity_limit,
+    consume_travel_rule,
+};
+
+/// 创建支付流，将 `rate_per_second * (stop - start)` 的资金转入托管账户
+/// * `recipient` - 接收方
+/// * `rate_per_second` - 每秒释放数量
+/// * `start` - 开始时间
+/// * `stop` - 结束时间
+pub fn create_stream(
+    ctx: Context<CreateStream>,
+    recipient: Pubkey,
+    rate_per_second: u64,
+    start: i64,
+    stop: i64,
+) -> Result<()> {
+    ctx.accounts.pause_state.validate_not_paused()?;
+    ctx.accounts.sender_freeze_state.check_frozen()?;
+
+    let now = Clock::get()?.unix_timestamp;
+    require!(
+        rate_per_second > 0 && start < stop && stop > now,
+        WusdError::InvalidStreamSchedule
+    );
+    let deposit = rate_per_second
+        .checked_mul((stop - start) as u64)
+        .ok_or(WusdError::InvalidStreamSchedule)?;
+
+    require_has_access(
+        ctx.accounts.sender.key(),
+        true,
+        Some(deposit),
+        &ctx.accounts.pause_state,
+        Some(&ctx.accounts.access_registry),
+    )?;
+    require!(
+        ctx.accounts.sender_token.amount >= deposit,
+        WusdError::InsufficientBalance
+    );
+    apply_velocity_limit(
+        &ctx.accounts.sender_velocity_limit,
+        &ctx.accounts.velocity_config,
+        ctx.accounts.sender_token.key(),
+        &ctx.accounts.sender.to_account_info(),
+        &ctx.accounts.system_program.to_account_info(),
+        deposit,
+    )?;
+
+    // 大额支付流在锁定全部资金时附带旅行规则记录，转入账户为托管账户，提取时不再校验
+    let travel_rule = consume_travel_rule(
+        &ctx.accounts.mint_state,
+        &ctx.accounts.travel_rule_record,
+        ctx.accounts.sender_token.key(),
+        ctx.accounts.escrow.key(),
+        deposit,
+    )?;
+
+    token::transfer(
+        CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            token::Transfer {
+                from: ctx.accounts.sender_token.to_account_info(),
+                to: ctx.accounts.escrow.to_account_info(),
+                authority: ctx.accounts.sender.to_account_info(),
+            },
+        ),
+        deposit,
+    )?;
+
+    record_checkpoint(
+        &ctx.accounts.sender_checkpoints,
+        &mut ctx.accounts.sender_token,
+    )?;
+
+    let stream = &mut ctx.accounts.stream;
+    stream.sender = ctx.accounts.sender.key();
+    stream.recipient = recipient;
+    stream.mint = ctx.accounts.token_mint.key();
+    stream.sender_token = ctx.accounts.sender_token.key();
+    stream.rate_per_second = rate_per_second;
+    stream.start = start;
+    stream.stop = stop;
+    stream.deposit = deposit;
+    stream.withdrawn = 0;
+    stream.bump = *ctx.bumps.get("stream").unwrap();
+
+    emit!(StreamCreated {
+        version: crate::EVENT_VERSION,
+        sender: stream.sender,
+        recipient,
+        mint: stream.mint,
+        rate_per_second,
+        start,
+        stop,
+        deposit,
+        timestamp: now,
+        travel_rule,
+    });
+
+    Ok(())
+}
+
+/// 接收方提取已释放的资金
+/// * `amount` - 提取数量，不能超过当前可提取数量
+pub fn withdraw_from_stream(ctx: Context<WithdrawFromStream>, amount: u64) -> Result<()> {
+    ctx.accounts.pause_state.validate_not_paused()?;
+    ctx.accounts.recipient_freeze_state.check_frozen()?;
+
+    let now = Clock::get()?.unix_timestamp;
+    let withdrawable = ctx.accounts.stream.withdrawable(now);
+    require!(withdrawable > 0, WusdError::NothingToWithdraw);
+    require!(amount > 0 && amount <= withdrawable, WusdError::InvalidAmount);
+
+    require_credit_access(
+        &ctx.accounts.access_registry,
+        ctx.accounts.recipient_token.owner,
+        &ctx.accounts.recipient_allowlist,
+    )?;
+
+    // 检查制裁名单
+    require_not_sanctioned(
+        &ctx.accounts.sanctions_config,
+        &ctx.accounts.sender_clearance,
+        ctx.accounts.stream.sender,
+    )?;
+    require_not_sanctioned(
+        &ctx.accounts.sanctions_config,
+        &ctx.accounts.recipient_clearance,
+        ctx.accounts.recipient_token.owner,
+    )?;
+
+    let stream = &ctx.accounts.stream;
+    let start_bytes = stream.start.to_le_bytes();
+    let seeds = &[
+        b"stream",
+        stream.sender.as_ref(),
+        stream.recipient.as_ref(),
+        start_bytes.as_ref(),
+        &[stream.bump],
+    ];
+    token::transfer(
+        CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            token::Transfer {
+                from: ctx.accounts.escrow.to_account_info(),
+                to: ctx.accounts.recipient_token.to_account_info(),
+                authority: ctx.accounts.stream.to_account_info(),
+            },
+            &[&seeds[..]],
+        ),
+        amount,
+    )?;
+
+    record_checkpoint(
+        &ctx.accounts.recipient_checkpoints,
+        &mut ctx.accounts.recipient_token,
+    )?;
+
+    let stream = &mut ctx.accounts.stream;
+    stream.withdrawn = stream.withdrawn
+        .checked_add(amount)
+        .ok_or(WusdError::InvalidAmount)?;
+
+    emit!(StreamWithdrawn {
+        version: crate::EVENT_VERSION,
+        sender: stream.sender,
+        recipient: stream.recipient,
+        mint: stream.mint,
+        amount,
+        withdrawn: stream.withdrawn,
+        timestamp: now,
+    });
+
+    Ok(())
+}
+
+/// 付款方取消支付流，未释放部分退回付款方，已释放未提取的部分支付给接收方
+/// 接收方被冻结、没有入账资格或未通过制裁名单检查时，其应得部分留在托管账户中，
+/// 支付流截止到当前时间后继续保留，接收方恢复后可自行提取，或由付款方再次调用本指令结算并关闭
+/// 支付流结束后付款方也可调用本指令结算并回收租金
+pub fn cancel_stream(ctx: Context<CancelStream>) -> Result<()> {
+    ctx.accounts.pause_state.validate_not_paused()?;
+    ctx.accounts.sender_freeze_state.check_frozen()?;
+    require_not_sanctioned(
+        &ctx.accounts.sanctions_config,
+        &ctx.accounts.sender_clearance,
+        ctx.accounts.stream.sender,
+    )?;
+
+    let now = Clock::get()?.unix_timestamp;
+    let stream = &ctx.accounts.stream;
+    let owed = stream.withdrawable(now);
+    let refund = stream.deposit - stream.accrued(now);
+    let recipient_can_receive = owed == 0
+        || (!ctx.accounts.recipient_freeze_state.is_frozen
+            && require_credit_access(
+                &ctx.accounts.access_registry,
+                ctx.accounts.recipient_token.owner,
+                &ctx.accounts.recipient_allowlist,
+            ).is_ok()
+            && require_not_sanctioned(
+                &ctx.accounts.sanctions_config,
+                &ctx.accounts.recipient_clearance,
+                ctx.accounts.recipient_token.owner,
+            ).is_ok());
+    let paid_to_recipient = if recipient_can_receive { owed } else { 0 };
+
+    let start_bytes = stream.start.to_le_bytes();
+    let seeds = &[
+        b"stream",
+        stream.sender.as_ref(),
+        stream.recipient.as_ref(),
+        start_bytes.as_ref(),
+        &[stream.bump],
+    ];
+
+    if paid_to_recipient > 0 {
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                token::Transfer {
+                    from: ctx.accounts.escrow.to_account_info(),
+                    to: ctx.accounts.recipient_token.to_account_info(),
+                    authority: ctx.accounts.stream.to_account_info(),
+                },
+                &[&seeds[..]],
+            ),
+            paid_to_recipient,
+        )?;
+    }
+    if refund > 0 {
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                token::Transfer {
+                    from: ctx.accounts.escrow.to_account_info(),
+                    to: ctx.accounts.sender_token.to_account_info(),
+                    authority: ctx.accounts.stream.to_account_info(),
+                },
+                &[&seeds[..]],
+            ),
+            refund,
+        )?;
+    }
+
+    if recipient_can_receive {
+        // 关闭托管账户和支付流，租金退回付款方
+        token::close_account(CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            token::CloseAccount {
+                account: ctx.accounts.escrow.to_account_info(),
+                destination: ctx.accounts.sender.to_account_info(),
+                authority: ctx.accounts.stream.to_account_info(),
+            },
+            &[&seeds[..]],
+        ))?;
+        ctx.accounts.stream.close(ctx.accounts.sender.to_account_info())?;
+    } else {
+        // 截止到当前时间，托管账户只保留接收方应得部分
+        let stream = &mut ctx.accounts.stream;
+        stream.stop = now.clamp(stream.start, stream.stop);
+        stream.deposit = stream.accrued(now);
+    }
+
+    record_checkpoint(
+        &ctx.accounts.recipient_checkpoints,
+        &mut ctx.accounts.recipient_token,
+    )?;
+    record_checkpoint(
+        &ctx.accounts.sender_checkpoints,
+        &mut ctx.accounts.sender_token,
+    )?;
+
+    let stream = &ctx.accounts.stream;
+    emit!(StreamCancelled {
+        version: crate::EVENT_VERSION,
+        sender: stream.sender,
+        recipient: stream.recipient,
+        mint: stream.mint,
+        paid_to_recipient,
+        refunded: refund,
+        retained: owed - paid_to_recipient,
+        timestamp: now,
+    });
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+#[instruction(recipient: Pubkey, rate_per_second: u64, start: i64)]
+pub struct CreateStream<'info> {
+    #[account(mut)]
+    pub sender: Signer<'info>,
+
+    pub token_mint: Box<Account<'info, Mint>>,
+
+    #[account(
+        mut,
+        constraint = sender_token.owner == sender.key() @ WusdError::InvalidOwner,
+        constraint = sender_token.mint == token_mint.key() @ WusdError::InvalidMint
+    )]
+    pub sender_token: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        init,
+        payer = sender,
+        space = PaymentStream::SIZE,
+        seeds = [b"stream", sender.key().as_ref(), recipient.as_ref(), &start.to_le_bytes()],
+        bump
+    )]
+    pub stream: Box<Account<'info, PaymentStream>>,
+
+    /// 支付流托管账户，由支付流 PDA 控制
+    #[account(
+        init,
+        payer = sender,
+        seeds = [b"stream_escrow", stream.key().as_ref()],
+        bump,
+        token::mint = token_mint,
+        token::authority = stream
+    )]
+    pub escrow: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        seeds = [b"pause_state", token_mint.key().as_ref()],
+        bump
+    )]
+    pub pause_state: Box<Account<'info, PauseState>>,
+
+    #[account(
+        seeds = [b"access_registry"],
+        bump
+    )]
+    pub access_registry: Box<Account<'info, AccessRegistryState>>,
+
+    #[account(
+        seeds = [b"freeze", sender_token.key().as_ref()],
+        bump
+    )]
+    pub sender_freeze_state: Box<Account<'info, FreezeState>>,
+
+    /// CHECK: 付款方余额快照账户，未开启快照时为空账户
+    #[account(
+        mut,
+        seeds = [b"checkpoint", sender_token.key().as_ref()],
+        bump
+    )]
+    pub sender_checkpoints: UncheckedAccount<'info>,
+
+    /// CHECK: 付款方限额状态账户，尚未创建时为空账户，配置默认档位后首次转出时创建
+    #[account(
+        mut,
+        seeds = [b"velocity", sender_token.key().as_ref()],
+        bump
+    )]
+    pub sender_velocity_limit: UncheckedAccount<'info>,
+
+    /// CHECK: 限额全局配置，未设置默认档位时为空账户
+    #[account(
+        seeds = [b"velocity_config", token_mint.key().as_ref()],
+        bump
+    )]
+    pub velocity_config: UncheckedAccount<'info>,
+
+    #[account(
+        seeds = [b"mint_state", token_mint.key().as_ref()],
+        bump
+    )]
+    pub mint_state: Box<Account<'info, MintState>>,
+
+    /// CHECK: 转出方旅行规则记录，未登记时为空账户
+    #[account(
+        mut,
+        seeds = [b"travel_rule", sender_token.key().as_ref()],
+        bump
+    )]
+    pub travel_rule_record: UncheckedAccount<'info>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawFromStream<'info> {
+    pub recipient: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"stream",
+            stream.sender.as_ref(),
+            stream.recipient.as_ref(),
+            &stream.start.to_le_bytes()
+        ],
+        bump = stream.bump,
+        has_one = recipient
+    )]
+    pub stream: Box<Account<'info, PaymentStream>>,
+
+    #[account(
+        mut,
+        seeds = [b"stream_escrow", stream.key().as_ref()],
+        bump
+    )]
+    pub escrow: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        constraint = recipient_token.owner == recipient.key() @ WusdError::InvalidOwner,
+        constraint = recipient_token.mint == stream.mint @ WusdError::InvalidMint
+    )]
+    pub recipient_token: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        seeds = [b"pause_state", stream.mint.as_ref()],
+        bump
+    )]
+    pub pause_state: Box<Account<'info, PauseState>>,
+
+    #[account(
+        seeds = [b"access_registry"],
+        bump
+    )]
+    pub access_registry: Box<Account<'info, AccessRegistryState>>,
+
+    #[account(
+        seeds = [b"freeze", recipient_token.key().as_ref()],
+        bump
+    )]
+    pub recipient_freeze_state: Box<Account<'info, FreezeState>>,
+
+    /// CHECK: 接收方入账白名单登记，未登记时为空账户
+    #[account(
+        seeds = [b"allowlist", recipient_token.owner.as_ref()],
+        bump
+    )]
+    pub recipient_allowlist: UncheckedAccount<'info>,
+
+    /// CHECK: 制裁名单配置，未配置时为空账户
+    #[account(
+        seeds = [b"sanctions", stream.mint.as_ref()],
+        bump
+    )]
+    pub sanctions_config: UncheckedAccount<'info>,
+
+    /// CHECK: 付款方制裁名单检查缓存，未通过检查时为空账户
+    #[account(
+        seeds = [b"clearance", stream.mint.as_ref(), stream.sender.as_ref()],
+        bump
+    )]
+    pub sender_clearance: UncheckedAccount<'info>,
+
+    /// CHECK: 接收方制裁名单检查缓存，未通过检查时为空账户
+    #[account(
+        seeds = [b"clearance", stream.mint.as_ref(), recipient_token.owner.as_ref()],
+        bump
+    )]
+    pub recipient_clearance: UncheckedAccount<'info>,
+
+    /// CHECK: 接收方余额快照账户，未开启快照时为空账户
+    #[account(
+        mut,
+        seeds = [b"checkpoint", recipient_token.key().as_ref()],
+        bump
+    )]
+    pub recipient_checkpoints: UncheckedAccount<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct CancelStream<'info> {
+    #[account(mut)]
+    pub sender: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"stream",
+            stream.sender.as_ref(),
+            stream.recipient.as_ref(),
+            &stream.start.to_le_bytes()
+        ],
+        bump = stream.bump,
+        has_one = sender
+    )]
+    pub stream: Box<Account<'info, PaymentStream>>,
+
+    #[account(
+        mut,
+        seeds = [b"stream_escrow", stream.key().as_ref()],
+        bump
+    )]
+    pub escrow: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        constraint = sender_token.key() == stream.sender_token @ WusdError::InvalidOwner
+    )]
+    pub sender_token: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        constraint = recipient_token.owner == stream.recipient @ WusdError::InvalidOwner,
+        constraint = recipient_token.mint == stream.mint @ WusdError::InvalidMint
+    )]
+    pub recipient_token: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        seeds = [b"pause_state", stream.mint.as_ref()],
+        bump
+    )]
+    pub pause_state: Box<Account<'info, PauseState>>,
+
+    #[account(
+        seeds = [b"access_registry"],
+        bump
+    )]
+    pub access_registry: Box<Account<'info, AccessRegistryState>>,
+
+    #[account(
+        seeds = [b"freeze", sender_token.key().as_ref()],
+        bump
+    )]
+    pub sender_freeze_state: Box<Account<'info, FreezeState>>,
+
+    #[account(
+        seeds = [b"freeze", recipient_token.key().as_ref()],
+        bump
+    )]
+    pub recipient_freeze_state: Box<Account<'info, FreezeState>>,
+
+    /// CHECK: 接收方入账白名单登记，未登记时为空账户
+    #[account(
+        seeds = [b"allowlist", recipient_token.owner.as_ref()],
+        bump
+    )]
+    pub recipient_allowlist: UncheckedAccount<'info>,
+
+    /// CHECK: 制裁名单配置，未配置时为空账户
+    #[account(
+        seeds = [b"sanctions", stream.mint.as_ref()],
+        bump
+    )]
+    pub sanctions_config: UncheckedAccount<'info>,
+
+    /// CHECK: 付款方制裁名单检查缓存，未通过检查时为空账户
+    #[account(
+        seeds = [b"clearance", stream.mint.as_ref(), stream.sender.as_ref()],
+        bump
+    )]
+    pub sender_clearance: UncheckedAccount<'info>,
+
+    /// CHECK: 接收方制裁名单检查缓存，未通过检查时为空账户
+    #[account(
+        seeds = [b"clearance", stream.mint.as_ref(), recipient_token.owner.as_ref()],
+        bump
+    )]
+    pub recipient_clearance: UncheckedAccount<'info>,
+
+    /// CHECK: 付款方余额快照账户，未开启快照时为空账户
+    #[account(
+        mut,
+        seeds = [b"checkpoint", sender_token.key().as_ref()],
+        bump
+    )]
+    pub sender_checkpoints: UncheckedAccount<'info>,
+
+    /// CHECK: 接收方余额快照账户，未开启快照时为空账户
+    #[account(
+        mut,
+        seeds = [b"checkpoint", recipient_token.key().as_ref()],
+        bump
+    )]
+    pub recipient_checkpoints: UncheckedAccount<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+/// 支付流创建事件
+#[event]
+pub struct StreamCreated {
+    /// 事件结构版本
+    pub version: u8,
+    pub sender: Pubkey,
+    pub recipient: Pubkey,
+    pub mint: Pubkey,
+    pub rate_per_second: u64,
+    pub start: i64,
+    pub stop: i64,
+    pub deposit: u64,
+    pub timestamp: i64,
+    /// 大额转账关联的旅行规则信息
+    pub travel_rule: Option<TravelRuleLink>,
+}
+
+/// 支付流提取事件
+#[event]
+pub struct StreamWithdrawn {
+    /// 事件结构版本
+    pub version: u8,
+    pub sender: Pubkey,
+    pub recipient: Pubkey,
+    pub mint: Pubkey,
+    /// 本次提取数量
+    pub amount: u64,
+    /// 累计提取数量
+    pub withdrawn: u64,
+    pub timestamp: i64,
+}
+
+/// 支付流取消事件
+#[event]
+pub struct StreamCancelled {
+    /// 事件结构版本
+    pub version: u8,
+    pub sender: Pubkey,
+    pub recipient: Pubkey,
+    pub mint: Pubkey,
+    /// 结算给接收方的已释放部分
+    pub paid_to_recipient: u64,
+    /// 退回付款方的未释放部分
+    pub refunded: u64,
+    /// 接收方无法入账时留在托管账户中的已释放部分
+    pub retained: u64,
+    pub timestamp: i64,
+}
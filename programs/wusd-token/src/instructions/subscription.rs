@@ -0,0 +1,568 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, Token, TokenAccount, Mint};
+use crate::error::WusdError;
+use crate::state::{FreezeState, MintState, AccessRegistryState, PauseState, Subscription, TravelRuleLink};
+use crate::utils::{
+    require_has_access, require_credit_access, require_not_sanctioned, record_checkpoint, apply_velocity_limit,
+    consume_travel_rule,
+};
+
+/// 创建订阅，将全部周期的总额转入订阅专属的托管账户
+/// 每个订阅独立托管资金，不占用付款代币账户的委托，多个订阅及其他委托可以并存
+/// 托管不计入转出限额，限额在每次扣款时按周期金额计入
+/// * `merchant` - 收款商户
+/// * `subscription_id` - 订阅编号，同一商户的多个订阅需使用不同编号
+/// * `amount_per_period` - 每周期扣款金额
+/// * `period` - 周期长度（秒）
+/// * `first_due` - 首次可扣款时间
+/// * `max_cycles` - 最大扣款次数
+pub fn create_subscription(
+    ctx: Context<CreateSubscription>,
+    merchant: Pubkey,
+    subscription_id: u64,
+    amount_per_period: u64,
+    period: i64,
+    first_due: i64,
+    max_cycles: u32,
+) -> Result<()> {
+    ctx.accounts.pause_state.validate_not_paused()?;
+    ctx.accounts.owner_freeze_state.check_frozen()?;
+    require!(
+        amount_per_period > 0 && period > 0 && max_cycles > 0,
+        WusdError::InvalidSubscription
+    );
+    let total = amount_per_period
+        .checked_mul(max_cycles as u64)
+        .ok_or(WusdError::InvalidSubscription)?;
+
+    require_has_access(
+        ctx.accounts.owner.key(),
+        true,
+        Some(total),
+        &ctx.accounts.pause_state,
+        Some(&ctx.accounts.access_registry),
+    )?;
+    require!(
+        ctx.accounts.owner_token.amount >= total,
+        WusdError::InsufficientBalance
+    );
+
+    // 大额订阅在锁定全部周期金额时附带旅行规则记录，转入账户为托管账户，扣款时不再校验
+    let travel_rule = consume_travel_rule(
+        &ctx.accounts.mint_state,
+        &ctx.accounts.travel_rule_record,
+        ctx.accounts.owner_token.key(),
+        ctx.accounts.escrow.key(),
+        total,
+    )?;
+    token::transfer(
+        CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            token::Transfer {
+                from: ctx.accounts.owner_token.to_account_info(),
+                to: ctx.accounts.escrow.to_account_info(),
+                authority: ctx.accounts.owner.to_account_info(),
+            },
+        ),
+        total,
+    )?;
+
+    record_checkpoint(
+        &ctx.accounts.owner_checkpoints,
+        &mut ctx.accounts.owner_token,
+    )?;
+
+    let subscription = &mut ctx.accounts.subscription;
+    subscription.owner = ctx.accounts.owner.key();
+    subscription.merchant = merchant;
+    subscription.subscription_id = subscription_id;
+    subscription.mint = ctx.accounts.token_mint.key();
+    subscription.owner_token = ctx.accounts.owner_token.key();
+    subscription.amount_per_period = amount_per_period;
+    subscription.period = period;
+    subscription.next_due = first_due;
+    subscription.max_cycles = max_cycles;
+    subscription.cycles_collected = 0;
+    subscription.bump = *ctx.bumps.get("subscription").unwrap();
+
+    emit!(SubscriptionCreated {
+        version: crate::EVENT_VERSION,
+        owner: subscription.owner,
+        merchant,
+        subscription_id,
+        mint: subscription.mint,
+        amount_per_period,
+        period,
+        first_due,
+        max_cycles,
+        timestamp: Clock::get()?.unix_timestamp,
+        travel_rule,
+    });
+
+    Ok(())
+}
+
+/// 商户按周期从托管账户扣款，每个周期只能扣款一次
+pub fn collect_subscription(ctx: Context<CollectSubscription>) -> Result<()> {
+    ctx.accounts.pause_state.validate_not_paused()?;
+    ctx.accounts.owner_freeze_state.check_frozen()?;
+    ctx.accounts.merchant_freeze_state.check_frozen()?;
+
+    let now = Clock::get()?.unix_timestamp;
+    ctx.accounts.subscription.collect(now)?;
+    let amount = ctx.accounts.subscription.amount_per_period;
+
+    require!(
+        ctx.accounts.access_registry.initialized,
+        WusdError::AccessRegistryNotInitialized
+    );
+    require_credit_access(
+        &ctx.accounts.access_registry,
+        ctx.accounts.merchant_token.owner,
+        &ctx.accounts.merchant_allowlist,
+    )?;
+
+    // 检查制裁名单
+    require_not_sanctioned(
+        &ctx.accounts.sanctions_config,
+        &ctx.accounts.owner_clearance,
+        ctx.accounts.subscription.owner,
+    )?;
+    require_not_sanctioned(
+        &ctx.accounts.sanctions_config,
+        &ctx.accounts.merchant_clearance,
+        ctx.accounts.merchant_token.owner,
+    )?;
+
+    // 按本期金额计入付款方的转出限额，超限时本期暂不能扣款
+    apply_velocity_limit(
+        &ctx.accounts.owner_velocity_limit,
+        &ctx.accounts.velocity_config,
+        ctx.accounts.subscription.owner_token,
+        &ctx.accounts.merchant.to_account_info(),
+        &ctx.accounts.system_program.to_account_info(),
+        amount,
+    )?;
+
+    let subscription = &ctx.accounts.subscription;
+    let subscription_id = subscription.subscription_id.to_le_bytes();
+    let seeds = &[
+        b"subscription",
+        subscription.owner.as_ref(),
+        subscription.merchant.as_ref(),
+        &subscription_id,
+        &[subscription.bump],
+    ];
+    token::transfer(
+        CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            token::Transfer {
+                from: ctx.accounts.escrow.to_account_info(),
+                to: ctx.accounts.merchant_token.to_account_info(),
+                authority: ctx.accounts.subscription.to_account_info(),
+            },
+            &[&seeds[..]],
+        ),
+        amount,
+    )?;
+
+    record_checkpoint(
+        &ctx.accounts.merchant_checkpoints,
+        &mut ctx.accounts.merchant_token,
+    )?;
+
+    let subscription = &ctx.accounts.subscription;
+    emit!(SubscriptionCollected {
+        version: crate::EVENT_VERSION,
+        owner: subscription.owner,
+        merchant: subscription.merchant,
+        subscription_id: subscription.subscription_id,
+        mint: subscription.mint,
+        amount,
+        cycle: subscription.cycles_collected,
+        next_due: subscription.next_due,
+        timestamp: now,
+    });
+
+    Ok(())
+}
+
+/// 所有者随时取消订阅，托管账户中剩余资金退回所有者并关闭订阅
+pub fn cancel_subscription(ctx: Context<CancelSubscription>) -> Result<()> {
+    ctx.accounts.pause_state.validate_not_paused()?;
+    ctx.accounts.owner_freeze_state.check_frozen()?;
+
+    require!(
+        ctx.accounts.access_registry.initialized,
+        WusdError::AccessRegistryNotInitialized
+    );
+    require_credit_access(
+        &ctx.accounts.access_registry,
+        ctx.accounts.owner_token.owner,
+        &ctx.accounts.owner_allowlist,
+    )?;
+
+    let subscription = &ctx.accounts.subscription;
+    let refund = ctx.accounts.escrow.amount;
+    let subscription_id = subscription.subscription_id.to_le_bytes();
+    let seeds = &[
+        b"subscription",
+        subscription.owner.as_ref(),
+        subscription.merchant.as_ref(),
+        &subscription_id,
+        &[subscription.bump],
+    ];
+
+    if refund > 0 {
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                token::Transfer {
+                    from: ctx.accounts.escrow.to_account_info(),
+                    to: ctx.accounts.owner_token.to_account_info(),
+                    authority: ctx.accounts.subscription.to_account_info(),
+                },
+                &[&seeds[..]],
+            ),
+            refund,
+        )?;
+    }
+
+    // 关闭托管账户，租金退回所有者
+    token::close_account(CpiContext::new_with_signer(
+        ctx.accounts.token_program.to_account_info(),
+        token::CloseAccount {
+            account: ctx.accounts.escrow.to_account_info(),
+            destination: ctx.accounts.owner.to_account_info(),
+            authority: ctx.accounts.subscription.to_account_info(),
+        },
+        &[&seeds[..]],
+    ))?;
+
+    record_checkpoint(
+        &ctx.accounts.owner_checkpoints,
+        &mut ctx.accounts.owner_token,
+    )?;
+
+    let subscription = &ctx.accounts.subscription;
+    emit!(SubscriptionCancelled {
+        version: crate::EVENT_VERSION,
+        owner: subscription.owner,
+        merchant: subscription.merchant,
+        subscription_id: subscription.subscription_id,
+        mint: subscription.mint,
+        cycles_collected: subscription.cycles_collected,
+        refunded: refund,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+#[instruction(merchant: Pubkey, subscription_id: u64)]
+pub struct CreateSubscription<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    pub token_mint: Box<Account<'info, Mint>>,
+
+    #[account(
+        mut,
+        constraint = owner_token.owner == owner.key() @ WusdError::InvalidOwner,
+        constraint = owner_token.mint == token_mint.key() @ WusdError::InvalidMint
+    )]
+    pub owner_token: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        init,
+        payer = owner,
+        space = Subscription::SIZE,
+        seeds = [b"subscription", owner.key().as_ref(), merchant.as_ref(), &subscription_id.to_le_bytes()],
+        bump
+    )]
+    pub subscription: Box<Account<'info, Subscription>>,
+
+    /// 订阅托管账户，由订阅 PDA 控制
+    #[account(
+        init,
+        payer = owner,
+        seeds = [b"subscription_escrow", subscription.key().as_ref()],
+        bump,
+        token::mint = token_mint,
+        token::authority = subscription
+    )]
+    pub escrow: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        seeds = [b"pause_state", token_mint.key().as_ref()],
+        bump
+    )]
+    pub pause_state: Box<Account<'info, PauseState>>,
+
+    #[account(
+        seeds = [b"access_registry"],
+        bump
+    )]
+    pub access_registry: Box<Account<'info, AccessRegistryState>>,
+
+    #[account(
+        seeds = [b"freeze", owner_token.key().as_ref()],
+        bump
+    )]
+    pub owner_freeze_state: Box<Account<'info, FreezeState>>,
+
+    /// CHECK: 付款方余额快照账户，未开启快照时为空账户
+    #[account(
+        mut,
+        seeds = [b"checkpoint", owner_token.key().as_ref()],
+        bump
+    )]
+    pub owner_checkpoints: UncheckedAccount<'info>,
+
+    #[account(
+        seeds = [b"mint_state", token_mint.key().as_ref()],
+        bump
+    )]
+    pub mint_state: Box<Account<'info, MintState>>,
+
+    /// CHECK: 转出方旅行规则记录，未登记时为空账户
+    #[account(
+        mut,
+        seeds = [b"travel_rule", owner_token.key().as_ref()],
+        bump
+    )]
+    pub travel_rule_record: UncheckedAccount<'info>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct CollectSubscription<'info> {
+    #[account(mut)]
+    pub merchant: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"subscription",
+            subscription.owner.as_ref(),
+            subscription.merchant.as_ref(),
+            &subscription.subscription_id.to_le_bytes()
+        ],
+        bump = subscription.bump,
+        has_one = merchant
+    )]
+    pub subscription: Box<Account<'info, Subscription>>,
+
+    #[account(
+        mut,
+        seeds = [b"subscription_escrow", subscription.key().as_ref()],
+        bump
+    )]
+    pub escrow: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        constraint = merchant_token.owner == merchant.key() @ WusdError::InvalidOwner,
+        constraint = merchant_token.mint == subscription.mint @ WusdError::InvalidMint
+    )]
+    pub merchant_token: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        seeds = [b"pause_state", subscription.mint.as_ref()],
+        bump
+    )]
+    pub pause_state: Box<Account<'info, PauseState>>,
+
+    #[account(
+        seeds = [b"access_registry"],
+        bump
+    )]
+    pub access_registry: Box<Account<'info, AccessRegistryState>>,
+
+    #[account(
+        seeds = [b"freeze", subscription.owner_token.as_ref()],
+        bump
+    )]
+    pub owner_freeze_state: Box<Account<'info, FreezeState>>,
+
+    #[account(
+        seeds = [b"freeze", merchant_token.key().as_ref()],
+        bump
+    )]
+    pub merchant_freeze_state: Box<Account<'info, FreezeState>>,
+
+    /// CHECK: 商户入账白名单登记，未登记时为空账户
+    #[account(
+        seeds = [b"allowlist", merchant_token.owner.as_ref()],
+        bump
+    )]
+    pub merchant_allowlist: UncheckedAccount<'info>,
+
+    /// CHECK: 制裁名单配置，未配置时为空账户
+    #[account(
+        seeds = [b"sanctions", subscription.mint.as_ref()],
+        bump
+    )]
+    pub sanctions_config: UncheckedAccount<'info>,
+
+    /// CHECK: 所有者制裁名单检查缓存，未通过检查时为空账户
+    #[account(
+        seeds = [b"clearance", subscription.mint.as_ref(), subscription.owner.as_ref()],
+        bump
+    )]
+    pub owner_clearance: UncheckedAccount<'info>,
+
+    /// CHECK: 商户制裁名单检查缓存，未通过检查时为空账户
+    #[account(
+        seeds = [b"clearance", subscription.mint.as_ref(), merchant_token.owner.as_ref()],
+        bump
+    )]
+    pub merchant_clearance: UncheckedAccount<'info>,
+
+    /// CHECK: 商户余额快照账户，未开启快照时为空账户
+    #[account(
+        mut,
+        seeds = [b"checkpoint", merchant_token.key().as_ref()],
+        bump
+    )]
+    pub merchant_checkpoints: UncheckedAccount<'info>,
+
+    /// CHECK: 付款方限额状态账户，尚未创建时为空账户，由商户付费创建
+    #[account(
+        mut,
+        seeds = [b"velocity", subscription.owner_token.as_ref()],
+        bump
+    )]
+    pub owner_velocity_limit: UncheckedAccount<'info>,
+
+    /// CHECK: 限额全局配置，未设置默认档位时为空账户
+    #[account(
+        seeds = [b"velocity_config", subscription.mint.as_ref()],
+        bump
+    )]
+    pub velocity_config: UncheckedAccount<'info>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CancelSubscription<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        close = owner,
+        seeds = [
+            b"subscription",
+            subscription.owner.as_ref(),
+            subscription.merchant.as_ref(),
+            &subscription.subscription_id.to_le_bytes()
+        ],
+        bump = subscription.bump,
+        has_one = owner
+    )]
+    pub subscription: Box<Account<'info, Subscription>>,
+
+    #[account(
+        mut,
+        seeds = [b"subscription_escrow", subscription.key().as_ref()],
+        bump
+    )]
+    pub escrow: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        constraint = owner_token.key() == subscription.owner_token @ WusdError::InvalidOwner
+    )]
+    pub owner_token: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        seeds = [b"pause_state", subscription.mint.as_ref()],
+        bump
+    )]
+    pub pause_state: Box<Account<'info, PauseState>>,
+
+    #[account(
+        seeds = [b"access_registry"],
+        bump
+    )]
+    pub access_registry: Box<Account<'info, AccessRegistryState>>,
+
+    #[account(
+        seeds = [b"freeze", owner_token.key().as_ref()],
+        bump
+    )]
+    pub owner_freeze_state: Box<Account<'info, FreezeState>>,
+
+    /// CHECK: 所有者入账白名单登记，未登记时为空账户
+    #[account(
+        seeds = [b"allowlist", owner_token.owner.as_ref()],
+        bump
+    )]
+    pub owner_allowlist: UncheckedAccount<'info>,
+
+    /// CHECK: 所有者余额快照账户，未开启快照时为空账户
+    #[account(
+        mut,
+        seeds = [b"checkpoint", owner_token.key().as_ref()],
+        bump
+    )]
+    pub owner_checkpoints: UncheckedAccount<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+/// 订阅创建事件
+#[event]
+pub struct SubscriptionCreated {
+    /// 事件结构版本
+    pub version: u8,
+    pub owner: Pubkey,
+    pub merchant: Pubkey,
+    pub subscription_id: u64,
+    pub mint: Pubkey,
+    pub amount_per_period: u64,
+    pub period: i64,
+    pub first_due: i64,
+    pub max_cycles: u32,
+    pub timestamp: i64,
+    /// 大额转账关联的旅行规则信息
+    pub travel_rule: Option<TravelRuleLink>,
+}
+
+/// 订阅扣款事件
+#[event]
+pub struct SubscriptionCollected {
+    /// 事件结构版本
+    pub version: u8,
+    pub owner: Pubkey,
+    pub merchant: Pubkey,
+    pub subscription_id: u64,
+    pub mint: Pubkey,
+    pub amount: u64,
+    /// 本次为第几次扣款
+    pub cycle: u32,
+    pub next_due: i64,
+    pub timestamp: i64,
+}
+
+/// 订阅取消事件
+#[event]
+pub struct SubscriptionCancelled {
+    /// 事件结构版本
+    pub version: u8,
+    pub owner: Pubkey,
+    pub merchant: Pubkey,
+    pub subscription_id: u64,
+    pub mint: Pubkey,
+    pub cycles_collected: u32,
+    /// 退回所有者的托管余额
+    pub refunded: u64,
+    pub timestamp: i64,
+}
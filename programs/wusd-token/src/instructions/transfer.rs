@@ -1,8 +1,11 @@
 use anchor_lang::prelude::*;
 use anchor_spl::token::{self, Token, TokenAccount}; 
 use crate::error::WusdError;  
-use crate::utils::require_has_access; 
-use crate::state::{FreezeState, PermitState, MintState, AccessRegistryState, PauseState};
+use crate::utils::{
+    require_has_access, require_credit_access, require_not_sanctioned, record_checkpoint, apply_velocity_limit,
+    consume_travel_rule,
+};
+use crate::state::{FreezeState, PermitState, MintState, AccessRegistryState, PauseState, TravelRuleLink};
 
 /// 转账WUSD代币
 /// * `ctx` - 转账上下文
@@ -23,6 +26,44 @@ pub fn transfer(ctx: Context<Transfer>, amount: u64) -> Result<()> {
         &ctx.accounts.pause_state,
         Some(&ctx.accounts.access_registry),
     )?; 
+
+    // 检查接收方入账资格
+    require_credit_access(
+        &ctx.accounts.access_registry,
+        ctx.accounts.to_token.owner,
+        &ctx.accounts.to_allowlist,
+    )?;
+
+    // 检查制裁名单
+    require_not_sanctioned(
+        &ctx.accounts.sanctions_config,
+        &ctx.accounts.from_clearance,
+        ctx.accounts.from_token.owner,
+    )?;
+    require_not_sanctioned(
+        &ctx.accounts.sanctions_config,
+        &ctx.accounts.to_clearance,
+        ctx.accounts.to_token.owner,
+    )?;
+
+    // 大额转账需附带旅行规则记录
+    let travel_rule = consume_travel_rule(
+        &ctx.accounts.mint_state,
+        &ctx.accounts.travel_rule_record,
+        ctx.accounts.from_token.key(),
+        ctx.accounts.to_token.key(),
+        amount,
+    )?;
+
+    // 检查转出限额
+    apply_velocity_limit(
+        &ctx.accounts.from_velocity_limit,
+        &ctx.accounts.velocity_config,
+        ctx.accounts.from_token.key(),
+        &ctx.accounts.from.to_account_info(),
+        &ctx.accounts.system_program.to_account_info(),
+        amount,
+    )?;
     
     // 执行转账
     token::transfer(
@@ -37,6 +78,16 @@ pub fn transfer(ctx: Context<Transfer>, amount: u64) -> Result<()> {
         amount,
     )?;
 
+    // 更新双方余额快照
+    record_checkpoint(
+        &ctx.accounts.from_checkpoints,
+        &mut ctx.accounts.from_token,
+    )?;
+    record_checkpoint(
+        &ctx.accounts.to_checkpoints,
+        &mut ctx.accounts.to_token,
+    )?;
+
     // 发送转账事件
     let clock = Clock::get()?;
     emit!(TransferEvent {
@@ -46,6 +97,7 @@ pub fn transfer(ctx: Context<Transfer>, amount: u64) -> Result<()> {
         fee: 0,
         timestamp: clock.unix_timestamp,
         memo: None,
+        travel_rule,
     });
 
     Ok(())
@@ -98,6 +150,44 @@ pub fn transfer_from(ctx: Context<TransferFrom>, amount: u64) -> Result<()> {
         WusdError::InsufficientBalance
     ); 
 
+    // 3.6 验证接收方入账资格
+    require_credit_access(
+        &ctx.accounts.access_registry,
+        ctx.accounts.to_token.owner,
+        &ctx.accounts.to_allowlist,
+    )?;
+
+    // 3.7 检查制裁名单
+    require_not_sanctioned(
+        &ctx.accounts.sanctions_config,
+        &ctx.accounts.from_clearance,
+        ctx.accounts.from_token.owner,
+    )?;
+    require_not_sanctioned(
+        &ctx.accounts.sanctions_config,
+        &ctx.accounts.to_clearance,
+        ctx.accounts.to_token.owner,
+    )?;
+
+    // 3.8 大额转账需附带旅行规则记录
+    let travel_rule = consume_travel_rule(
+        &ctx.accounts.mint_state,
+        &ctx.accounts.travel_rule_record,
+        ctx.accounts.from_token.key(),
+        ctx.accounts.to_token.key(),
+        amount,
+    )?;
+
+    // 4. 检查转出限额
+    apply_velocity_limit(
+        &ctx.accounts.from_velocity_limit,
+        &ctx.accounts.velocity_config,
+        ctx.accounts.from_token.key(),
+        &ctx.accounts.spender.to_account_info(),
+        &ctx.accounts.system_program.to_account_info(),
+        amount,
+    )?;
+
     // 5. 构建签名种子
     let seeds = &[
         b"permit",
@@ -124,6 +214,16 @@ pub fn transfer_from(ctx: Context<TransferFrom>, amount: u64) -> Result<()> {
     ctx.accounts.permit.amount = ctx.accounts.permit.amount
         .checked_sub(amount)
         .ok_or(WusdError::InsufficientAllowance)?;
+
+    // 更新双方余额快照
+    record_checkpoint(
+        &ctx.accounts.from_checkpoints,
+        &mut ctx.accounts.from_token,
+    )?;
+    record_checkpoint(
+        &ctx.accounts.to_checkpoints,
+        &mut ctx.accounts.to_token,
+    )?;
     
     // 8. 发送转账事件
     let clock = Clock::get()?;
@@ -136,6 +236,7 @@ pub fn transfer_from(ctx: Context<TransferFrom>, amount: u64) -> Result<()> {
         memo: Some(format!("Transfer from {} to {}", 
             transfer_context.owner_key.to_string(),
             ctx.accounts.to_token.owner.to_string())),
+        travel_rule,
     });
 
     Ok(())
@@ -192,6 +293,69 @@ pub struct Transfer<'info> {
         constraint = !to_freeze_state.is_frozen @ WusdError::AccountFrozen
     )]
     pub to_freeze_state: Account<'info, FreezeState>,
+    /// CHECK: 转出方余额快照账户，未开启快照时为空账户
+    #[account(
+        mut,
+        seeds = [b"checkpoint", from_token.key().as_ref()],
+        bump
+    )]
+    pub from_checkpoints: UncheckedAccount<'info>,
+    /// CHECK: 转入方余额快照账户，未开启快照时为空账户
+    #[account(
+        mut,
+        seeds = [b"checkpoint", to_token.key().as_ref()],
+        bump
+    )]
+    pub to_checkpoints: UncheckedAccount<'info>,
+    /// CHECK: 转出方限额状态账户，尚未创建时为空账户，配置默认档位后首次转出时创建
+    #[account(
+        mut,
+        seeds = [b"velocity", from_token.key().as_ref()],
+        bump
+    )]
+    pub from_velocity_limit: UncheckedAccount<'info>,
+    /// CHECK: 限额全局配置，未设置默认档位时为空账户
+    #[account(
+        seeds = [b"velocity_config", from_token.mint.as_ref()],
+        bump
+    )]
+    pub velocity_config: UncheckedAccount<'info>,
+    /// CHECK: 接收方入账白名单登记，未登记时为空账户
+    #[account(
+        seeds = [b"allowlist", to_token.owner.as_ref()],
+        bump
+    )]
+    pub to_allowlist: UncheckedAccount<'info>,
+    /// CHECK: 制裁名单配置，未配置时为空账户
+    #[account(
+        seeds = [b"sanctions", from_token.mint.as_ref()],
+        bump
+    )]
+    pub sanctions_config: UncheckedAccount<'info>,
+    /// CHECK: 转出方制裁名单检查缓存，未通过检查时为空账户
+    #[account(
+        seeds = [b"clearance", from_token.mint.as_ref(), from_token.owner.as_ref()],
+        bump
+    )]
+    pub from_clearance: UncheckedAccount<'info>,
+    /// CHECK: 转入方制裁名单检查缓存，未通过检查时为空账户
+    #[account(
+        seeds = [b"clearance", from_token.mint.as_ref(), to_token.owner.as_ref()],
+        bump
+    )]
+    pub to_clearance: UncheckedAccount<'info>,
+    #[account(
+        seeds = [b"mint_state", from_token.mint.as_ref()],
+        bump
+    )]
+    pub mint_state: Box<Account<'info, MintState>>,
+    /// CHECK: 转出方旅行规则记录，未登记时为空账户
+    #[account(
+        mut,
+        seeds = [b"travel_rule", from_token.key().as_ref()],
+        bump
+    )]
+    pub travel_rule_record: UncheckedAccount<'info>,
     pub system_program: Program<'info, System>,
 }
 
@@ -212,6 +376,8 @@ pub struct TransferEvent {
     pub timestamp: i64,
     /// 转账备注（可选）
     pub memo: Option<String>,
+    /// 大额转账关联的旅行规则信息
+    pub travel_rule: Option<TravelRuleLink>,
 }
 
 #[derive(Accounts)]
@@ -238,7 +404,10 @@ pub struct TransferFrom<'info> {
         has_one = spender,
     )]
     pub permit: Account<'info, PermitState>, 
-    #[account(mut)]
+    #[account(
+        seeds = [b"mint_state", from_token.mint.as_ref()],
+        bump
+    )]
     pub mint_state: Box<Account<'info, MintState>>, 
     pub pause_state: Account<'info, PauseState>, 
     pub access_registry: Account<'info, AccessRegistryState>, 
@@ -260,6 +429,64 @@ pub struct TransferFrom<'info> {
         bump
     )]
     pub to_freeze_state: Account<'info, FreezeState>,
+    /// CHECK: 转出方余额快照账户，未开启快照时为空账户
+    #[account(
+        mut,
+        seeds = [b"checkpoint", from_token.key().as_ref()],
+        bump
+    )]
+    pub from_checkpoints: UncheckedAccount<'info>,
+    /// CHECK: 转入方余额快照账户，未开启快照时为空账户
+    #[account(
+        mut,
+        seeds = [b"checkpoint", to_token.key().as_ref()],
+        bump
+    )]
+    pub to_checkpoints: UncheckedAccount<'info>,
+    /// CHECK: 转出方限额状态账户，尚未创建时为空账户，配置默认档位后首次转出时创建
+    #[account(
+        mut,
+        seeds = [b"velocity", from_token.key().as_ref()],
+        bump
+    )]
+    pub from_velocity_limit: UncheckedAccount<'info>,
+    /// CHECK: 限额全局配置，未设置默认档位时为空账户
+    #[account(
+        seeds = [b"velocity_config", from_token.mint.as_ref()],
+        bump
+    )]
+    pub velocity_config: UncheckedAccount<'info>,
+    /// CHECK: 接收方入账白名单登记，未登记时为空账户
+    #[account(
+        seeds = [b"allowlist", to_token.owner.as_ref()],
+        bump
+    )]
+    pub to_allowlist: UncheckedAccount<'info>,
+    /// CHECK: 制裁名单配置，未配置时为空账户
+    #[account(
+        seeds = [b"sanctions", from_token.mint.as_ref()],
+        bump
+    )]
+    pub sanctions_config: UncheckedAccount<'info>,
+    /// CHECK: 转出方制裁名单检查缓存，未通过检查时为空账户
+    #[account(
+        seeds = [b"clearance", from_token.mint.as_ref(), from_token.owner.as_ref()],
+        bump
+    )]
+    pub from_clearance: UncheckedAccount<'info>,
+    /// CHECK: 转入方制裁名单检查缓存，未通过检查时为空账户
+    #[account(
+        seeds = [b"clearance", from_token.mint.as_ref(), to_token.owner.as_ref()],
+        bump
+    )]
+    pub to_clearance: UncheckedAccount<'info>,
+    /// CHECK: 转出方旅行规则记录，未登记时为空账户
+    #[account(
+        mut,
+        seeds = [b"travel_rule", from_token.key().as_ref()],
+        bump
+    )]
+    pub travel_rule_record: UncheckedAccount<'info>,
     pub token_program: Program<'info, Token>,
     pub system_program: Program<'info, System>,
 }
\ No newline at end of file
@@ -0,0 +1,133 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{Mint, TokenAccount};
+use crate::error::WusdError;
+use crate::state::{AuthorityState, MintState, TravelRuleRecord};
+
+/// 设置需要附带旅行规则记录的转账金额门槛
+/// * `threshold` - 门槛金额，0 表示不启用
+pub fn set_travel_rule_threshold(ctx: Context<SetTravelRuleThreshold>, threshold: u64) -> Result<()> {
+    let mint_state = &mut ctx.accounts.mint_state;
+    let old_threshold = mint_state.travel_rule_threshold;
+    mint_state.travel_rule_threshold = threshold;
+
+    emit!(TravelRuleThresholdSet {
+        version: crate::EVENT_VERSION,
+        actor: ctx.accounts.authority.key(),
+        mint: ctx.accounts.token_mint.key(),
+        old_threshold,
+        new_threshold: threshold,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+/// 转出方为下一笔大额转账登记旅行规则记录，未使用的旧记录会被覆盖
+/// * `to_token` - 转入代币账户，预授权、支付流和订阅为对应的托管账户
+/// * `amount` - 转账金额
+/// * `payload_hash` - 加密的发起方和受益方信息的哈希
+/// * `vasp_id` - 受益方 VASP 标识
+pub fn attach_travel_rule(
+    ctx: Context<AttachTravelRule>,
+    to_token: Pubkey,
+    amount: u64,
+    payload_hash: [u8; 32],
+    vasp_id: [u8; 32],
+) -> Result<()> {
+    require!(amount > 0, WusdError::InvalidAmount);
+
+    let now = Clock::get()?.unix_timestamp;
+    let record = &mut ctx.accounts.travel_rule_record;
+    record.originator = ctx.accounts.owner.key();
+    record.from_token = ctx.accounts.from_token.key();
+    record.to_token = to_token;
+    record.amount = amount;
+    record.payload_hash = payload_hash;
+    record.vasp_id = vasp_id;
+    record.consumed = false;
+    record.created_at = now;
+
+    emit!(TravelRuleAttached {
+        version: crate::EVENT_VERSION,
+        record: ctx.accounts.travel_rule_record.key(),
+        originator: ctx.accounts.owner.key(),
+        from_token: ctx.accounts.from_token.key(),
+        to_token,
+        amount,
+        payload_hash,
+        vasp_id,
+        timestamp: now,
+    });
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct SetTravelRuleThreshold<'info> {
+    pub authority: Signer<'info>,
+
+    pub token_mint: Account<'info, Mint>,
+
+    #[account(
+        seeds = [b"authority", token_mint.key().as_ref()],
+        bump,
+        constraint = authority_state.is_admin(authority.key()) @ WusdError::Unauthorized
+    )]
+    pub authority_state: Account<'info, AuthorityState>,
+
+    #[account(
+        mut,
+        seeds = [b"mint_state", token_mint.key().as_ref()],
+        bump
+    )]
+    pub mint_state: Account<'info, MintState>,
+}
+
+#[derive(Accounts)]
+pub struct AttachTravelRule<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        constraint = from_token.owner == owner.key() @ WusdError::InvalidOwner
+    )]
+    pub from_token: Account<'info, TokenAccount>,
+
+    #[account(
+        init_if_needed,
+        payer = owner,
+        space = TravelRuleRecord::SIZE,
+        seeds = [b"travel_rule", from_token.key().as_ref()],
+        bump
+    )]
+    pub travel_rule_record: Account<'info, TravelRuleRecord>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// 旅行规则门槛更新事件
+#[event]
+pub struct TravelRuleThresholdSet {
+    /// 事件结构版本
+    pub version: u8,
+    pub actor: Pubkey,
+    pub mint: Pubkey,
+    pub old_threshold: u64,
+    pub new_threshold: u64,
+    pub timestamp: i64,
+}
+
+/// 旅行规则记录登记事件，记录账户会被后续登记覆盖，完整内容以事件为准
+#[event]
+pub struct TravelRuleAttached {
+    /// 事件结构版本
+    pub version: u8,
+    pub record: Pubkey,
+    pub originator: Pubkey,
+    pub from_token: Pubkey,
+    pub to_token: Pubkey,
+    pub amount: u64,
+    pub payload_hash: [u8; 32],
+    pub vasp_id: [u8; 32],
+    pub timestamp: i64,
+}
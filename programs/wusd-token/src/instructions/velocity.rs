@@ -0,0 +1,126 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{Mint, TokenAccount};
+use crate::error::WusdError;
+use crate::state::{AuthorityState, VelocityConfig, VelocityLimit, VelocityTier};
+
+/// 设置全局默认转出限额档位
+/// * `tier` - 默认档位
+pub fn set_default_velocity_tier(ctx: Context<SetDefaultVelocityTier>, tier: VelocityTier) -> Result<()> {
+    tier.validate()?;
+
+    let velocity_config = &mut ctx.accounts.velocity_config;
+    velocity_config.mint = ctx.accounts.token_mint.key();
+    velocity_config.default_tier = tier;
+
+    emit!(DefaultVelocityTierSet {
+        version: crate::EVENT_VERSION,
+        actor: ctx.accounts.authority.key(),
+        mint: ctx.accounts.token_mint.key(),
+        tier,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+/// 为代币账户设置转出限额
+/// * `tier` - 单独的限额档位，为空时使用全局默认档位
+pub fn set_velocity_limit(ctx: Context<SetVelocityLimit>, tier: Option<VelocityTier>) -> Result<()> {
+    if let Some(tier) = tier {
+        tier.validate()?;
+    }
+
+    let velocity_limit = &mut ctx.accounts.velocity_limit;
+    velocity_limit.token_account = ctx.accounts.token_account.key();
+    velocity_limit.custom_tier = tier;
+
+    emit!(VelocityLimitSet {
+        version: crate::EVENT_VERSION,
+        actor: ctx.accounts.authority.key(),
+        mint: ctx.accounts.token_account.mint,
+        token_account: ctx.accounts.token_account.key(),
+        tier,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct SetDefaultVelocityTier<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub token_mint: Account<'info, Mint>,
+
+    #[account(
+        seeds = [b"authority", token_mint.key().as_ref()],
+        bump,
+        constraint = authority_state.is_admin(authority.key()) @ WusdError::Unauthorized
+    )]
+    pub authority_state: Account<'info, AuthorityState>,
+
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = VelocityConfig::SIZE,
+        seeds = [b"velocity_config", token_mint.key().as_ref()],
+        bump
+    )]
+    pub velocity_config: Account<'info, VelocityConfig>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetVelocityLimit<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub token_account: Account<'info, TokenAccount>,
+
+    #[account(
+        seeds = [b"authority", token_account.mint.as_ref()],
+        bump,
+        constraint = authority_state.is_admin(authority.key()) @ WusdError::Unauthorized
+    )]
+    pub authority_state: Account<'info, AuthorityState>,
+
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = VelocityLimit::SIZE,
+        seeds = [b"velocity", token_account.key().as_ref()],
+        bump
+    )]
+    pub velocity_limit: Account<'info, VelocityLimit>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// 默认限额档位更新事件
+#[event]
+pub struct DefaultVelocityTierSet {
+    /// 事件结构版本
+    pub version: u8,
+    /// 执行操作的管理员
+    pub actor: Pubkey,
+    /// 代币铸币账户地址
+    pub mint: Pubkey,
+    pub tier: VelocityTier,
+    pub timestamp: i64,
+}
+
+/// 账户限额更新事件
+#[event]
+pub struct VelocityLimitSet {
+    /// 事件结构版本
+    pub version: u8,
+    /// 执行操作的管理员
+    pub actor: Pubkey,
+    /// 代币铸币账户地址
+    pub mint: Pubkey,
+    pub token_account: Pubkey,
+    pub tier: Option<VelocityTier>,
+    pub timestamp: i64,
+}
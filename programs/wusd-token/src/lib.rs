@@ -15,7 +15,7 @@ mod error;
 mod state; 
 mod utils;
 
-use state::{AuthorityState, MintState, PauseState, AccessRegistryState};
+use state::{AuthorityState, MintState, PauseState, AccessRegistryState, VelocityTier, RedemptionStatus, Role, STATE_VERSION};
 
 use instructions::mint::*; 
 use instructions::burn::*;
@@ -24,6 +24,19 @@ use instructions::permit::*;
 use instructions::operator::*;
 use instructions::pause::*;
 use instructions::freeze::*; 
+use instructions::migrate::*;
+use instructions::checkpoint::*;
+use instructions::velocity::*;
+use instructions::mint_authority::*;
+use instructions::metadata::*;
+use instructions::redeem::*;
+use instructions::bridge::*;
+use instructions::relay::*;
+use instructions::hold::*;
+use instructions::stream::*;
+use instructions::subscription::*;
+use instructions::sanctions::*;
+use instructions::travel_rule::*;
 
 declare_id!("AaophXdbkkqvFJx1XqroNPdwLTPAwyS5z4hqj7Efh1a1");
 
@@ -36,6 +49,8 @@ pub mod wusd_token {
         access_registry.operator_count = 0;
         access_registry.operators = [Pubkey::default(); 10];
         access_registry.initialized = true;
+        access_registry.version = STATE_VERSION;
+        access_registry.credit_allowlist = false;
         Ok(())
     }
 
@@ -44,38 +59,18 @@ pub mod wusd_token {
         msg!("Authority: {}", ctx.accounts.authority.key());
         msg!("Mint: {}", ctx.accounts.token_mint.key());
 
-        // 1. 初始化状态账户
-        let authority_state = &mut ctx.accounts.authority_state;
-        authority_state.admin = ctx.accounts.authority.key();
-        authority_state.minter = ctx.accounts.authority.key();
-        authority_state.pauser = ctx.accounts.authority.key();
-
-        let mint_state = &mut ctx.accounts.mint_state;
-        mint_state.mint = ctx.accounts.token_mint.key();
-        mint_state.decimals = decimals;
-
-        let pause_state = &mut ctx.accounts.pause_state;
-        pause_state.paused = false;
-
-        // 2. 转移mint的authority给authority_state PDA
-        let mint_key = ctx.accounts.token_mint.key();
-        let seeds = &[b"authority", mint_key.as_ref()]; 
-        let (_authority_pda, bump) = Pubkey::find_program_address(seeds, ctx.program_id);
-        let _bump_bytes = &[bump];
-
-        token::set_authority(
-            CpiContext::new(
-                ctx.accounts.token_program.to_account_info(),
-                token::SetAuthority {
-                    current_authority: ctx.accounts.authority.to_account_info(),
-                    account_or_mint: ctx.accounts.token_mint.to_account_info(),
-                }
-            ),
-            token::spl_token::instruction::AuthorityType::MintTokens,
-            Some(ctx.accounts.authority_state.key()),
+        // 1. 初始化状态账户并转移mint的authority给authority_state PDA
+        setup_mint_state(
+            &ctx.accounts.authority,
+            &ctx.accounts.token_mint,
+            &mut ctx.accounts.authority_state,
+            &mut ctx.accounts.mint_state,
+            &mut ctx.accounts.pause_state,
+            &ctx.accounts.token_program,
+            decimals,
         )?;
 
-        // 3. 发出初始化事件
+        // 2. 发出初始化事件
         emit!(InitializeEvent {
             authority: ctx.accounts.authority.key(),
             mint: ctx.accounts.token_mint.key(),
@@ -86,6 +81,39 @@ pub mod wusd_token {
         Ok(())
     } 
     
+    /// 使用已有的铸币账户初始化，调用者必须是该铸币账户当前的铸币权地址
+    pub fn initialize_with_existing_mint(ctx: Context<InitializeWithExistingMint>) -> Result<()> {
+        let decimals = ctx.accounts.token_mint.decimals;
+        setup_mint_state(
+            &ctx.accounts.authority,
+            &ctx.accounts.token_mint,
+            &mut ctx.accounts.authority_state,
+            &mut ctx.accounts.mint_state,
+            &mut ctx.accounts.pause_state,
+            &ctx.accounts.token_program,
+            decimals,
+        )?;
+
+        emit!(InitializeEvent {
+            authority: ctx.accounts.authority.key(),
+            mint: ctx.accounts.token_mint.key(),
+            decimals
+        });
+
+        msg!("Adopted existing mint {}", ctx.accounts.token_mint.key());
+        Ok(())
+    }
+
+    /// 创建代币元数据（名称、符号、URI）
+    pub fn create_token_metadata(ctx: Context<CreateTokenMetadata>, params: TokenMetadataParams) -> Result<()> {
+        instructions::metadata::create_token_metadata(ctx, params)
+    }
+
+    /// 更新代币元数据
+    pub fn update_token_metadata(ctx: Context<UpdateTokenMetadata>, params: TokenMetadataParams) -> Result<()> {
+        instructions::metadata::update_token_metadata(ctx, params)
+    }
+
     /// 铸造WUSD代币 
     pub fn mint(ctx: Context<MintAccounts>, amount: u64, bump: u8) -> Result<()> {
         instructions::mint::mint(ctx, amount, bump) 
@@ -106,6 +134,78 @@ pub mod wusd_token {
         instructions::transfer::transfer_from(ctx, amount) 
     } 
 
+    /// 中继者代付手续费的转账
+    pub fn relayed_transfer(ctx: Context<RelayedTransfer>, intent: RelayedTransferIntent, fee: u64) -> Result<()> {
+        instructions::relay::relayed_transfer(ctx, intent, fee)
+    }
+
+    /// 创建商户预授权
+    pub fn create_hold(
+        ctx: Context<CreateHold>,
+        merchant: Pubkey,
+        hold_id: u64,
+        amount: u64,
+        expiry: i64,
+    ) -> Result<()> {
+        instructions::hold::create_hold(ctx, merchant, hold_id, amount, expiry)
+    }
+
+    /// 商户扣款并结束预授权
+    pub fn capture_hold(ctx: Context<CaptureHold>, amount: u64) -> Result<()> {
+        instructions::hold::capture_hold(ctx, amount)
+    }
+
+    /// 取消或到期释放预授权
+    pub fn release_hold(ctx: Context<ReleaseHold>) -> Result<()> {
+        instructions::hold::release_hold(ctx)
+    }
+
+    /// 创建按秒释放的支付流
+    pub fn create_stream(ctx: Context<CreateStream>, recipient: Pubkey, rate_per_second: u64, start: i64, stop: i64) -> Result<()> {
+        instructions::stream::create_stream(ctx, recipient, rate_per_second, start, stop)
+    }
+
+    /// 提取支付流中已释放的资金
+    pub fn withdraw_from_stream(ctx: Context<WithdrawFromStream>, amount: u64) -> Result<()> {
+        instructions::stream::withdraw_from_stream(ctx, amount)
+    }
+
+    /// 取消支付流并退回未释放部分
+    pub fn cancel_stream(ctx: Context<CancelStream>) -> Result<()> {
+        instructions::stream::cancel_stream(ctx)
+    }
+
+    /// 创建周期性扣款订阅
+    pub fn create_subscription(
+        ctx: Context<CreateSubscription>,
+        merchant: Pubkey,
+        subscription_id: u64,
+        amount_per_period: u64,
+        period: i64,
+        first_due: i64,
+        max_cycles: u32,
+    ) -> Result<()> {
+        instructions::subscription::create_subscription(
+            ctx,
+            merchant,
+            subscription_id,
+            amount_per_period,
+            period,
+            first_due,
+            max_cycles,
+        )
+    }
+
+    /// 商户按周期扣款
+    pub fn collect_subscription(ctx: Context<CollectSubscription>) -> Result<()> {
+        instructions::subscription::collect_subscription(ctx)
+    }
+
+    /// 取消订阅
+    pub fn cancel_subscription(ctx: Context<CancelSubscription>) -> Result<()> {
+        instructions::subscription::cancel_subscription(ctx)
+    }
+
     /// 暂停合约
     pub fn pause(ctx: Context<Pause>) -> Result<()> {
         instructions::pause::pause(ctx)  
@@ -121,6 +221,73 @@ pub mod wusd_token {
         instructions::burn::burn(ctx, amount)
     } 
 
+    /// 销毁WUSD代币并登记赎回请求
+    pub fn redeem(ctx: Context<Redeem>, redemption_id: u64, bank_reference_hash: [u8; 32], amount: u64) -> Result<()> {
+        instructions::redeem::redeem(ctx, redemption_id, bank_reference_hash, amount)
+    }
+
+    /// 更新赎回状态
+    pub fn update_redemption_status(ctx: Context<UpdateRedemptionStatus>, status: RedemptionStatus) -> Result<()> {
+        instructions::redeem::update_redemption_status(ctx, status)
+    }
+
+    /// 设置跨链桥守护者集合
+    pub fn configure_bridge(ctx: Context<ConfigureBridge>, guardians: Vec<Pubkey>, quorum: u8) -> Result<()> {
+        instructions::bridge::configure_bridge(ctx, guardians, quorum)
+    }
+
+    /// 设置对端链的开关和速率限制
+    pub fn set_bridge_chain(
+        ctx: Context<SetBridgeChain>,
+        chain_id: u16,
+        enabled: bool,
+        outbound_tier: VelocityTier,
+        inbound_tier: VelocityTier,
+    ) -> Result<()> {
+        instructions::bridge::set_bridge_chain(ctx, chain_id, enabled, outbound_tier, inbound_tier)
+    }
+
+    /// 销毁WUSD并发出跨链消息
+    pub fn bridge_out(ctx: Context<BridgeOut>, destination_chain: u16, recipient: [u8; 32], amount: u64) -> Result<()> {
+        instructions::bridge::bridge_out(ctx, destination_chain, recipient, amount)
+    }
+
+    /// 凭守护者签名铸造跨链转入的WUSD
+    pub fn bridge_in(ctx: Context<BridgeIn>, source_chain: u16, sequence: u64, amount: u64) -> Result<()> {
+        instructions::bridge::bridge_in(ctx, source_chain, sequence, amount)
+    }
+
+    /// 设置制裁名单的合规角色和强制检查开关
+    pub fn configure_sanctions(ctx: Context<ConfigureSanctions>, compliance: Pubkey, enforced: bool) -> Result<()> {
+        instructions::sanctions::configure_sanctions(ctx, compliance, enforced)
+    }
+
+    /// 更新制裁名单 Merkle 根
+    pub fn update_sanctions_root(ctx: Context<UpdateSanctionsRoot>, merkle_root: [u8; 32]) -> Result<()> {
+        instructions::sanctions::update_sanctions_root(ctx, merkle_root)
+    }
+
+    /// 提交非成员证明并缓存检查结果
+    pub fn clear_sanctions(ctx: Context<ClearSanctions>, owner: Pubkey, proof: NonMembershipProof) -> Result<()> {
+        instructions::sanctions::clear_sanctions(ctx, owner, proof)
+    }
+
+    /// 设置旅行规则门槛金额
+    pub fn set_travel_rule_threshold(ctx: Context<SetTravelRuleThreshold>, threshold: u64) -> Result<()> {
+        instructions::travel_rule::set_travel_rule_threshold(ctx, threshold)
+    }
+
+    /// 为大额转账登记旅行规则记录
+    pub fn attach_travel_rule(
+        ctx: Context<AttachTravelRule>,
+        to_token: Pubkey,
+        amount: u64,
+        payload_hash: [u8; 32],
+        vasp_id: [u8; 32],
+    ) -> Result<()> {
+        instructions::travel_rule::attach_travel_rule(ctx, to_token, amount, payload_hash, vasp_id)
+    }
+
     /// 添加操作员
     pub fn add_operator(ctx: Context<ManageOperator>, operator: Pubkey) -> Result<()> {
         instructions::operator::add_operator(ctx, operator)
@@ -131,6 +298,31 @@ pub mod wusd_token {
         instructions::operator::remove_operator(ctx, operator)
     }
 
+    /// 发起角色变更，新地址接受后生效
+    pub fn propose_role(ctx: Context<ProposeRole>, role: Role, account: Pubkey) -> Result<()> {
+        instructions::operator::propose_role(ctx, role, account)
+    }
+
+    /// 新地址接受角色变更
+    pub fn accept_role(ctx: Context<AcceptRole>, role: Role) -> Result<()> {
+        instructions::operator::accept_role(ctx, role)
+    }
+
+    /// 取消待接受的角色变更
+    pub fn cancel_role(ctx: Context<CancelRole>, role: Role) -> Result<()> {
+        instructions::operator::cancel_role(ctx, role)
+    }
+
+    /// 开启或关闭入账白名单模式
+    pub fn set_credit_allowlist_mode(ctx: Context<SetCreditAllowlistMode>, enabled: bool) -> Result<()> {
+        instructions::operator::set_credit_allowlist_mode(ctx, enabled)
+    }
+
+    /// 登记或撤销接收方的入账资格
+    pub fn set_credit_allowlist(ctx: Context<ManageCreditAllowlist>, user: Pubkey, allowed: bool) -> Result<()> {
+        instructions::operator::set_credit_allowlist(ctx, user, allowed)
+    }
+
     pub fn initialize_freeze_state(ctx: Context<InitializeFreezeState>) -> Result<()> {
         instructions::freeze::initialize_freeze_state(ctx)
     }
@@ -145,6 +337,71 @@ pub mod wusd_token {
         instructions::freeze::unfreeze_account(ctx) 
     }
 
+    /// 迁移权限管理账户到当前布局
+    pub fn migrate_authority_state(ctx: Context<MigrateAuthorityState>) -> Result<()> {
+        instructions::migrate::migrate_authority_state(ctx)
+    }
+
+    /// 迁移铸币状态账户到当前布局
+    pub fn migrate_mint_state(ctx: Context<MigrateMintState>) -> Result<()> {
+        instructions::migrate::migrate_mint_state(ctx)
+    }
+
+    /// 迁移暂停状态账户到当前布局
+    pub fn migrate_pause_state(ctx: Context<MigratePauseState>) -> Result<()> {
+        instructions::migrate::migrate_pause_state(ctx)
+    }
+
+    /// 迁移访问权限注册表到当前布局
+    pub fn migrate_access_registry(ctx: Context<MigrateAccessRegistry>) -> Result<()> {
+        instructions::migrate::migrate_access_registry(ctx)
+    }
+
+    /// 查询各状态账户的迁移情况（只读）
+    pub fn get_migration_status(ctx: Context<MigrationStatusView>) -> Result<MigrationStatus> {
+        instructions::migrate::get_migration_status(ctx)
+    }
+
+    /// 为代币账户开启余额快照
+    pub fn enable_checkpoints(ctx: Context<EnableCheckpoints>) -> Result<()> {
+        instructions::checkpoint::enable_checkpoints(ctx)
+    }
+
+    /// 扩大余额快照账户容量
+    pub fn extend_checkpoints(ctx: Context<ExtendCheckpoints>, additional: u16) -> Result<()> {
+        instructions::checkpoint::extend_checkpoints(ctx, additional)
+    }
+
+    /// 查询代币账户在指定 slot 的余额（只读）
+    pub fn get_balance_at(ctx: Context<BalanceAt>, slot: u64) -> Result<u64> {
+        instructions::checkpoint::get_balance_at(ctx, slot)
+    }
+
+    /// 设置全局默认转出限额档位
+    pub fn set_default_velocity_tier(ctx: Context<SetDefaultVelocityTier>, tier: VelocityTier) -> Result<()> {
+        instructions::velocity::set_default_velocity_tier(ctx, tier)
+    }
+
+    /// 设置代币账户的转出限额
+    pub fn set_velocity_limit(ctx: Context<SetVelocityLimit>, tier: Option<VelocityTier>) -> Result<()> {
+        instructions::velocity::set_velocity_limit(ctx, tier)
+    }
+
+    /// 发起铸币权移交（有等待期）
+    pub fn transfer_mint_authority(ctx: Context<TransferMintAuthority>, new_authority: Pubkey) -> Result<()> {
+        instructions::mint_authority::transfer_mint_authority(ctx, new_authority)
+    }
+
+    /// 等待期结束后确认铸币权移交
+    pub fn confirm_mint_authority_transfer(ctx: Context<ConfirmMintAuthorityTransfer>, new_authority: Pubkey) -> Result<()> {
+        instructions::mint_authority::confirm_mint_authority_transfer(ctx, new_authority)
+    }
+
+    /// 取消待执行的铸币权移交
+    pub fn cancel_mint_authority_transfer(ctx: Context<CancelMintAuthorityTransfer>) -> Result<()> {
+        instructions::mint_authority::cancel_mint_authority_transfer(ctx)
+    }
+
 }
 
 #[derive(Accounts)]
@@ -197,6 +454,52 @@ pub struct Initialize<'info> {
     pub rent: Sysvar<'info, Rent>,
 }   
 
+#[derive(Accounts)]
+pub struct InitializeWithExistingMint<'info> {
+    /// 管理员账户，必须是铸币账户当前的铸币权地址
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    /// 权限管理账户
+    #[account(
+        init,
+        payer = authority, 
+        space = AuthorityState::SIZE,
+        seeds = [b"authority", token_mint.key().as_ref()],
+        bump
+    )]
+    pub authority_state: Account<'info, AuthorityState>,
+
+    /// 已有的代币铸币账户
+    #[account(
+        mut,
+        constraint = token_mint.mint_authority == Some(authority.key()).into() @ error::WusdError::InvalidMintAuthority
+    )]
+    pub token_mint: Account<'info, Mint>,
+
+    /// 铸币状态账户
+    #[account(
+        init,
+        payer = authority, 
+        space = MintState::SIZE,
+        seeds = [b"mint_state", token_mint.key().as_ref()],
+        bump
+    )]
+    pub mint_state: Account<'info, MintState>,
+
+    /// 暂停状态账户
+    #[account(
+        init,
+        payer = authority, 
+        space = PauseState::SIZE,
+        seeds = [b"pause_state", token_mint.key().as_ref()],
+        bump
+    )]
+    pub pause_state: Account<'info, PauseState>,
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+}
+
 #[derive(Accounts)]
 pub struct InitializeAccessRegistry<'info> {
     #[account(mut)]
@@ -214,6 +517,45 @@ pub struct InitializeAccessRegistry<'info> {
     pub system_program: Program<'info, System>,
 }   
 
+/// 初始化状态账户，并把铸币权移交给 authority_state PDA
+fn setup_mint_state<'info>(
+    authority: &Signer<'info>,
+    token_mint: &Account<'info, Mint>,
+    authority_state: &mut Account<'info, AuthorityState>,
+    mint_state: &mut Account<'info, MintState>,
+    pause_state: &mut Account<'info, PauseState>,
+    token_program: &Program<'info, Token>,
+    decimals: u8,
+) -> Result<()> {
+    authority_state.admin = authority.key();
+    authority_state.minter = authority.key();
+    authority_state.pauser = authority.key();
+    authority_state.version = STATE_VERSION;
+
+    mint_state.mint = token_mint.key();
+    mint_state.decimals = decimals;
+    mint_state.version = STATE_VERSION;
+    mint_state.travel_rule_threshold = 0;
+
+    pause_state.paused = false;
+    pause_state.version = STATE_VERSION;
+
+    token::set_authority(
+        CpiContext::new(
+            token_program.to_account_info(),
+            token::SetAuthority {
+                current_authority: authority.to_account_info(),
+                account_or_mint: token_mint.to_account_info(),
+            }
+        ),
+        token::spl_token::instruction::AuthorityType::MintTokens,
+        Some(authority_state.key()),
+    )
+}
+
+/// 生命周期事件的结构版本，事件字段变更时递增，供索引服务区分解析方式
+pub const EVENT_VERSION: u8 = 1;
+
 /// 访问级别枚举，用于控制账户的操作权限
 #[derive(Clone, Copy, PartialEq, Eq)]
 pub enum AccessLevel {
@@ -1,6 +1,23 @@
 use anchor_lang::prelude::*;
 use crate::{AccessLevel, error::WusdError};
 
+/// 状态账户当前布局版本
+/// 新字段只能追加在结构体末尾，且零值必须是合法的默认值，
+/// 这样旧账户扩容补零后即可按新布局反序列化
+pub const STATE_VERSION: u8 = 3;
+
+/// 带版本号的状态账户，供 `migrate_*` 指令原地升级
+pub trait Versioned {
+    /// 当前布局下的账户大小
+    const CURRENT_SIZE: usize;
+
+    /// 账户布局版本，旧账户为 0
+    fn version(&self) -> u8;
+
+    /// 设置账户布局版本
+    fn set_version(&mut self, version: u8);
+}
+
 /// 授权额度状态账户，存储代币授权信息
 #[account]
 pub struct AllowanceState {
@@ -53,6 +70,68 @@ impl AllowanceState {
     }
 }
 
+/// 周期性扣款订阅，商户每个周期按固定金额拉取一次
+/// 全部周期的总额在创建时转入订阅专属的托管账户，商户从托管账户扣款
+#[account]
+pub struct Subscription {
+    /// 代币所有者地址
+    pub owner: Pubkey,
+    /// 收款商户
+    pub merchant: Pubkey,
+    /// 订阅编号，由所有者指定，同一所有者和商户之间可以并存多个订阅
+    pub subscription_id: u64,
+    /// 代币铸币账户地址
+    pub mint: Pubkey,
+    /// 付款代币账户
+    pub owner_token: Pubkey,
+    /// 每周期扣款金额
+    pub amount_per_period: u64,
+    /// 周期长度（秒）
+    pub period: i64,
+    /// 下一次可扣款时间
+    pub next_due: i64,
+    /// 最大扣款次数
+    pub max_cycles: u32,
+    /// 已扣款次数
+    pub cycles_collected: u32,
+    /// PDA bump
+    pub bump: u8,
+}
+
+impl Subscription {
+    pub const SIZE: usize = 8 + // discriminator
+        32 + // owner
+        32 + // merchant
+        8 + // subscription_id
+        32 + // mint
+        32 + // owner_token
+        8 + // amount_per_period
+        8 + // period
+        8 + // next_due
+        4 + // max_cycles
+        4 + // cycles_collected
+        1; // bump
+
+    /// 记录一次扣款，下一次可扣款时间推进到当前时间之后的第一个周期点
+    /// 错过的周期不能补扣
+    /// * `now` - 当前时间
+    pub fn collect(&mut self, now: i64) -> Result<()> {
+        require!(now >= self.next_due, WusdError::SubscriptionNotDue);
+        require!(
+            self.cycles_collected < self.max_cycles,
+            WusdError::SubscriptionCompleted
+        );
+
+        let periods = (now - self.next_due) / self.period + 1;
+        self.next_due = periods
+            .checked_mul(self.period)
+            .and_then(|delta| self.next_due.checked_add(delta))
+            .ok_or(WusdError::InvalidAmount)?;
+        self.cycles_collected += 1;
+        Ok(())
+    }
+}
+
 /// 签名许可状态账户，用于EIP-2612兼容的签名授权
 #[account]
 pub struct PermitState {
@@ -109,18 +188,21 @@ pub struct AuthorityState {
     pub minter: Pubkey,
     /// 暂停权限地址
     pub pauser: Pubkey,
+    /// 账户布局版本
+    pub version: u8,
 }
 
 impl AuthorityState {
     /// 权限管理状态账户大小
-    /// discriminator + admin + minter + pauser
-    pub const SIZE: usize = 8 + 32 * 3;
+    /// discriminator + admin + minter + pauser + version
+    pub const SIZE: usize = 8 + 32 * 3 + 1;
 
     pub fn initialize(admin: Pubkey) -> Self {
         Self {
-            admin: admin,
+            admin,
             minter: admin,
             pauser: admin,
+            version: STATE_VERSION,
         }
     }
 
@@ -137,6 +219,41 @@ impl AuthorityState {
     } 
 }
 
+/// 可变更的角色
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Role {
+    /// 管理员
+    Admin,
+    /// 铸币权限
+    Minter,
+    /// 暂停权限
+    Pauser,
+}
+
+/// 待接受的角色变更，新地址签名接受后才生效
+#[account]
+pub struct PendingRole {
+    /// 代币铸币账户地址
+    pub mint: Pubkey,
+    /// 要变更的角色
+    pub role: Role,
+    /// 发起变更的管理员
+    pub proposer: Pubkey,
+    /// 新的角色地址
+    pub new_account: Pubkey,
+    /// 发起时间
+    pub proposed_at: i64,
+}
+
+impl PendingRole {
+    pub const SIZE: usize = 8 + // discriminator
+        32 + // mint
+        1 + // role
+        32 + // proposer
+        32 + // new_account
+        8; // proposed_at
+}
+
 /// 访问权限注册表状态
 #[account]
 #[derive(Default)]
@@ -149,6 +266,10 @@ pub struct AccessRegistryState {
     pub operators: [Pubkey; 10],  // 支持最多10个操作员
     /// 当前操作员数量
     pub operator_count: u8,
+    /// 账户布局版本
+    pub version: u8,
+    /// 入账白名单模式，开启后接收方也必须已登记
+    pub credit_allowlist: bool,
 }
 
 impl AccessRegistryState {
@@ -156,7 +277,9 @@ impl AccessRegistryState {
         32 + // authority
         4 + // operator_count
         (32 * 10) + // operators array
-        1; // initialized
+        1 + // initialized
+        1 + // version
+        1; // credit_allowlist
 
     pub fn new(authority: Pubkey) -> Self {
         Self {
@@ -164,6 +287,8 @@ impl AccessRegistryState {
             operator_count: 0,
             operators: [Pubkey::default(); 10],
             initialized: false,
+            version: STATE_VERSION,
+            credit_allowlist: false,
         }
     }
 
@@ -210,10 +335,16 @@ impl AccessRegistryState {
         Ok(())
     }
 
+    /// 检查是否是管理员或操作员
+    pub fn is_operator(&self, user: Pubkey) -> bool {
+        user == self.authority || self.operators[..self.operator_count as usize].contains(&user)
+    }
+
     /// 检查是否有访问权限
+    /// 入账白名单模式下，未在注册表中的接收方还需单独登记，见 `CreditAllowlistEntry`
     pub fn has_access(&self, user: Pubkey, level: AccessLevel) -> bool {
-        // 如果是 Credit 操作（接收代币），直接允许
-        if matches!(level, AccessLevel::Credit) {
+        // 如果是 Credit 操作（接收代币）且未开启白名单模式，直接允许
+        if matches!(level, AccessLevel::Credit) && !self.credit_allowlist {
             return true;
         }
 
@@ -233,6 +364,21 @@ impl AccessRegistryState {
     }
 }
 
+/// 入账白名单登记，白名单模式下接收方需有此登记才能入账
+#[account]
+pub struct CreditAllowlistEntry {
+    /// 登记的接收方地址
+    pub user: Pubkey,
+    /// 是否允许入账
+    pub allowed: bool,
+}
+
+impl CreditAllowlistEntry {
+    pub const SIZE: usize = 8 + // discriminator
+        32 + // user
+        1; // allowed
+}
+
 /// 铸币状态账户，存储代币铸造相关信息
 #[account]
 pub struct MintState {
@@ -240,12 +386,24 @@ pub struct MintState {
     pub mint: Pubkey,
     /// 代币精度
     pub decimals: u8,
+    /// 账户布局版本
+    pub version: u8,
+    /// 需要附带旅行规则记录的转账金额门槛，0 表示不启用
+    pub travel_rule_threshold: u64,
 }
 
 impl MintState {
     pub const SIZE: usize = 8 + // discriminator
         32 + // mint
-        1;  // decimals
+        1 + // decimals
+        1 + // version
+        8;  // travel_rule_threshold
+
+    /// 转账金额是否需要附带旅行规则记录
+    /// * `amount` - 转账金额
+    pub fn requires_travel_rule(&self, amount: u64) -> bool {
+        self.travel_rule_threshold > 0 && amount > self.travel_rule_threshold
+    }
 }
 
 /// 暂停状态账户，用于控制合约的暂停/恢复
@@ -253,11 +411,14 @@ impl MintState {
 pub struct PauseState {
     /// 合约是否暂停
     pub paused: bool,
+    /// 账户布局版本
+    pub version: u8,
 }
 
 impl PauseState {
     pub const SIZE: usize = 8 + // discriminator
-        1;  // paused
+        1 + // paused
+        1;  // version
 
     /// 设置暂停状态
     pub fn set_paused(&mut self, paused: bool) {
@@ -271,6 +432,54 @@ impl PauseState {
     }
 }
 
+impl Versioned for AuthorityState {
+    const CURRENT_SIZE: usize = Self::SIZE;
+
+    fn version(&self) -> u8 {
+        self.version
+    }
+
+    fn set_version(&mut self, version: u8) {
+        self.version = version;
+    }
+}
+
+impl Versioned for MintState {
+    const CURRENT_SIZE: usize = Self::SIZE;
+
+    fn version(&self) -> u8 {
+        self.version
+    }
+
+    fn set_version(&mut self, version: u8) {
+        self.version = version;
+    }
+}
+
+impl Versioned for PauseState {
+    const CURRENT_SIZE: usize = Self::SIZE;
+
+    fn version(&self) -> u8 {
+        self.version
+    }
+
+    fn set_version(&mut self, version: u8) {
+        self.version = version;
+    }
+}
+
+impl Versioned for AccessRegistryState {
+    const CURRENT_SIZE: usize = Self::SIZE;
+
+    fn version(&self) -> u8 {
+        self.version
+    }
+
+    fn set_version(&mut self, version: u8) {
+        self.version = version;
+    }
+}
+
 /// 账户冻结状态，用于控制账户的冻结/解冻
 #[account]
 pub struct FreezeState {
@@ -299,4 +508,603 @@ impl FreezeState {
     pub fn unfreeze(&mut self) {
         self.is_frozen = false;
     }
-}
\ No newline at end of file
+}
+
+/// 开启余额快照时预留的快照数量，写满后需调用 `extend_checkpoints` 扩容
+pub const INITIAL_CHECKPOINTS: u16 = 64;
+
+/// 单次扩容最多增加的快照数量，受单条指令账户扩容上限约束
+pub const MAX_CHECKPOINT_GROWTH: u16 =
+    (anchor_lang::solana_program::entrypoint::MAX_PERMITTED_DATA_INCREASE / 16) as u16;
+
+/// 单条余额快照
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default, Debug)]
+pub struct Checkpoint {
+    /// 记录时的 slot
+    pub slot: u64,
+    /// 该 slot 结束时的余额
+    pub balance: u64,
+}
+
+/// 持有人余额快照账户，用于按历史 slot 查询余额
+#[account]
+pub struct BalanceCheckpoints {
+    /// 代币账户地址
+    pub token_account: Pubkey,
+    /// 代币账户所有者
+    pub owner: Pubkey,
+    /// 账户空间可容纳的快照数量
+    pub capacity: u16,
+    /// 按 slot 升序排列的快照
+    pub checkpoints: Vec<Checkpoint>,
+}
+
+impl BalanceCheckpoints {
+    /// 可容纳 `capacity` 条快照时的账户大小
+    pub const fn space(capacity: u16) -> usize {
+        8 + // discriminator
+        32 + // token_account
+        32 + // owner
+        2 + // capacity
+        4 + (8 + 8) * capacity as usize // checkpoints
+    }
+
+    /// 记录余额，同一 slot 内多次变动只保留最后一次
+    /// 已满时拒绝写入，不合并或丢弃已有快照，避免 `balance_at` 返回近似值
+    pub fn record(&mut self, slot: u64, balance: u64) -> Result<()> {
+        if let Some(last) = self.checkpoints.last_mut() {
+            require!(slot >= last.slot, WusdError::InvalidCheckpoint);
+            if last.slot == slot {
+                last.balance = balance;
+                return Ok(());
+            }
+        }
+
+        require!(
+            self.checkpoints.len() < self.capacity as usize,
+            WusdError::CheckpointsFull
+        );
+        self.checkpoints.push(Checkpoint { slot, balance });
+        Ok(())
+    }
+
+    /// 查询指定 slot 结束时的余额
+    pub fn balance_at(&self, slot: u64) -> Result<u64> {
+        // 第一条快照之前的余额无从得知
+        let index = self.checkpoints.partition_point(|c| c.slot <= slot);
+        require!(index > 0, WusdError::CheckpointNotFound);
+        Ok(self.checkpoints[index - 1].balance)
+    }
+}
+
+/// 转出限额档位
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct VelocityTier {
+    /// 单个时间窗口内允许转出的最大金额
+    pub limit: u64,
+    /// 时间窗口长度（秒）
+    pub window: i64,
+}
+
+impl VelocityTier {
+    /// 验证档位参数
+    pub fn validate(&self) -> Result<()> {
+        require!(self.limit > 0 && self.window > 0, WusdError::InvalidVelocityTier);
+        Ok(())
+    }
+}
+
+/// 滚动窗口划分的子窗口数量，额度最多比窗口长度晚一个子窗口释放
+pub const WINDOW_BUCKETS: usize = 8;
+
+/// 按子窗口计数的滚动窗口限额
+/// 子窗口长度为 `ceil(window / WINDOW_BUCKETS)`，统计当前子窗口及之前 `WINDOW_BUCKETS` 个子窗口，
+/// 覆盖的时间跨度不小于 `window`，因此任意长度为 `window` 的时间段内通过的总额都不超过 `limit`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default)]
+pub struct RollingWindow {
+    /// 记账时使用的子窗口长度（秒），档位变更后据此判断旧记录的归属
+    pub bucket_len: i64,
+    /// 最近一次记账所在子窗口的序号
+    pub last_bucket: i64,
+    /// 按子窗口序号环形存储的已通过金额
+    pub buckets: [u64; WINDOW_BUCKETS + 1],
+}
+
+impl RollingWindow {
+    pub const SIZE: usize = 8 + // bucket_len
+        8 + // last_bucket
+        8 * (WINDOW_BUCKETS + 1); // buckets
+
+    /// 记录一笔金额，窗口内总额超出限额时不记账并返回 false
+    /// * `amount` - 金额
+    /// * `tier` - 生效的限额档位
+    /// * `now` - 当前时间
+    pub fn consume(&mut self, amount: u64, tier: VelocityTier, now: i64) -> bool {
+        let slots = (WINDOW_BUCKETS + 1) as i64;
+        let bucket_len = (tier.window + WINDOW_BUCKETS as i64 - 1) / WINDOW_BUCKETS as i64;
+        let current = now.max(0) / bucket_len;
+
+        if self.bucket_len != bucket_len {
+            // 档位变更后旧记录无法对应到新的子窗口，全部计入当前子窗口，只会推迟释放
+            let total = self.total();
+            self.buckets = [0; WINDOW_BUCKETS + 1];
+            self.buckets[current.rem_euclid(slots) as usize] = total;
+            self.bucket_len = bucket_len;
+            self.last_bucket = current;
+        } else if current > self.last_bucket {
+            // 清空已滑出窗口的子窗口
+            let expired = (current - self.last_bucket).min(slots);
+            for offset in 0..expired {
+                self.buckets[(current - offset).rem_euclid(slots) as usize] = 0;
+            }
+            self.last_bucket = current;
+        }
+
+        match self.total().checked_add(amount) {
+            Some(total) if total <= tier.limit => {
+                let index = self.last_bucket.rem_euclid(slots) as usize;
+                self.buckets[index] += amount;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// 窗口内已通过的总额
+    pub fn total(&self) -> u64 {
+        self.buckets.iter().fold(0u64, |total, amount| total.saturating_add(*amount))
+    }
+}
+
+/// 转出限额全局配置，存储默认档位
+#[account]
+pub struct VelocityConfig {
+    /// 代币铸币账户地址
+    pub mint: Pubkey,
+    /// 未单独设置档位的账户使用的默认档位
+    pub default_tier: VelocityTier,
+}
+
+impl VelocityConfig {
+    pub const SIZE: usize = 8 + // discriminator
+        32 + // mint
+        8 + 8; // default_tier
+}
+
+/// 单个代币账户的转出限额状态
+#[account]
+pub struct VelocityLimit {
+    /// 受限的代币账户
+    pub token_account: Pubkey,
+    /// 单独设置的档位，为空时使用全局默认档位
+    pub custom_tier: Option<VelocityTier>,
+    /// 滚动窗口内的已转出金额
+    pub window: RollingWindow,
+}
+
+impl VelocityLimit {
+    pub const SIZE: usize = 8 + // discriminator
+        32 + // token_account
+        1 + 8 + 8 + // custom_tier
+        RollingWindow::SIZE; // window
+
+    /// 记录一笔转出，任意长度为窗口的时间段内转出总额超出限额时拒绝
+    /// * `amount` - 转出金额
+    /// * `tier` - 生效的限额档位
+    /// * `now` - 当前时间
+    pub fn consume(&mut self, amount: u64, tier: VelocityTier, now: i64) -> Result<()> {
+        require!(
+            self.window.consume(amount, tier, now),
+            WusdError::VelocityLimitExceeded
+        );
+        Ok(())
+    }
+}
+
+/// 铸币权移交的等待期（秒）
+#[cfg(not(feature = "short-timelock"))]
+pub const MINT_AUTHORITY_TIMELOCK: i64 = 2 * 24 * 60 * 60;
+/// 测试构建使用的等待期，便于在本地验证器上走完确认流程
+#[cfg(feature = "short-timelock")]
+pub const MINT_AUTHORITY_TIMELOCK: i64 = 5;
+
+/// 待执行的铸币权移交
+#[account]
+pub struct MintAuthorityTransfer {
+    /// 代币铸币账户地址
+    pub mint: Pubkey,
+    /// 发起移交的管理员
+    pub proposer: Pubkey,
+    /// 新的铸币权地址
+    pub new_authority: Pubkey,
+    /// 发起时间
+    pub proposed_at: i64,
+    /// 最早可执行时间
+    pub eta: i64,
+}
+
+impl MintAuthorityTransfer {
+    pub const SIZE: usize = 8 + // discriminator
+        32 + // mint
+        32 + // proposer
+        32 + // new_authority
+        8 + // proposed_at
+        8; // eta
+}
+
+/// 赎回状态
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum RedemptionStatus {
+    /// 已销毁，等待线下打款
+    Pending,
+    /// 已打款
+    Paid,
+    /// 已拒绝，销毁的代币已退回
+    Rejected,
+}
+
+/// 赎回记录，记录一次销毁换取线下打款的请求
+#[account]
+pub struct RedemptionRecord {
+    /// 赎回发起人
+    pub owner: Pubkey,
+    /// 代币铸币账户地址
+    pub mint: Pubkey,
+    /// 被销毁代币的账户，拒绝时退回至此
+    pub token_account: Pubkey,
+    /// 赎回编号，由赎回台分配
+    pub redemption_id: u64,
+    /// 银行收款信息的哈希，原文仅保存在链下
+    pub bank_reference_hash: [u8; 32],
+    /// 赎回金额
+    pub amount: u64,
+    /// 赎回状态
+    pub status: RedemptionStatus,
+    /// 发起时间
+    pub created_at: i64,
+    /// 最近一次状态变更时间
+    pub updated_at: i64,
+}
+
+impl RedemptionRecord {
+    pub const SIZE: usize = 8 + // discriminator
+        32 + // owner
+        32 + // mint
+        32 + // token_account
+        8 + // redemption_id
+        32 + // bank_reference_hash
+        8 + // amount
+        1 + // status
+        8 + // created_at
+        8; // updated_at
+}
+
+/// 跨链桥守护者数量上限
+pub const MAX_GUARDIANS: usize = 10;
+
+/// 跨链桥配置，存储守护者集合和出站序号
+#[account]
+pub struct BridgeConfig {
+    /// 代币铸币账户地址
+    pub mint: Pubkey,
+    /// 守护者 Ed25519 公钥
+    pub guardians: [Pubkey; MAX_GUARDIANS],
+    /// 有效守护者数量
+    pub guardian_count: u8,
+    /// 入站消息所需的最少守护者签名数
+    pub quorum: u8,
+    /// 下一条出站消息的序号
+    pub outbound_sequence: u64,
+}
+
+impl BridgeConfig {
+    pub const SIZE: usize = 8 + // discriminator
+        32 + // mint
+        32 * MAX_GUARDIANS + // guardians
+        1 + // guardian_count
+        1 + // quorum
+        8; // outbound_sequence
+
+    /// 设置守护者集合
+    /// * `guardians` - 守护者公钥，不能重复
+    /// * `quorum` - 最少签名数
+    pub fn set_guardians(&mut self, guardians: &[Pubkey], quorum: u8) -> Result<()> {
+        require!(
+            !guardians.is_empty() && guardians.len() <= MAX_GUARDIANS,
+            WusdError::InvalidGuardianSet
+        );
+        require!(
+            quorum > 0 && quorum as usize <= guardians.len(),
+            WusdError::InvalidGuardianSet
+        );
+        for (i, guardian) in guardians.iter().enumerate() {
+            require!(
+                *guardian != Pubkey::default() && !guardians[..i].contains(guardian),
+                WusdError::InvalidGuardianSet
+            );
+        }
+
+        self.guardians = [Pubkey::default(); MAX_GUARDIANS];
+        self.guardians[..guardians.len()].copy_from_slice(guardians);
+        self.guardian_count = guardians.len() as u8;
+        self.quorum = quorum;
+        Ok(())
+    }
+
+    /// 检查地址是否为守护者
+    pub fn guardian_index(&self, key: &Pubkey) -> Option<usize> {
+        self.guardians[..self.guardian_count as usize]
+            .iter()
+            .position(|guardian| guardian == key)
+    }
+}
+
+/// 跨链桥单方向的速率限制，与转出限额共用滚动窗口实现
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default)]
+pub struct BridgeRateLimit {
+    /// 限额档位
+    pub tier: VelocityTier,
+    /// 滚动窗口内已通过的金额
+    pub window: RollingWindow,
+}
+
+impl BridgeRateLimit {
+    pub const SIZE: usize = 8 + 8 + // tier
+        RollingWindow::SIZE; // window
+
+    /// 记录一笔跨链金额，任意长度为窗口的时间段内总额超出限额时拒绝
+    /// * `amount` - 跨链金额
+    /// * `now` - 当前时间
+    pub fn consume(&mut self, amount: u64, now: i64) -> Result<()> {
+        require!(
+            self.window.consume(amount, self.tier, now),
+            WusdError::BridgeRateLimitExceeded
+        );
+        Ok(())
+    }
+}
+
+/// 对端链配置
+#[account]
+pub struct BridgeChain {
+    /// 代币铸币账户地址
+    pub mint: Pubkey,
+    /// 对端链编号
+    pub chain_id: u16,
+    /// 是否开放跨链
+    pub enabled: bool,
+    /// 出站速率限制
+    pub outbound: BridgeRateLimit,
+    /// 入站速率限制
+    pub inbound: BridgeRateLimit,
+}
+
+impl BridgeChain {
+    pub const SIZE: usize = 8 + // discriminator
+        32 + // mint
+        2 + // chain_id
+        1 + // enabled
+        BridgeRateLimit::SIZE + // outbound
+        BridgeRateLimit::SIZE; // inbound
+}
+
+/// 已处理的入站消息，账户存在即表示该 (源链, 序号) 已被消费
+#[account]
+pub struct BridgeReceipt {
+    /// 源链编号
+    pub source_chain: u16,
+    /// 源链上的消息序号
+    pub sequence: u64,
+    /// 收款代币账户
+    pub recipient: Pubkey,
+    /// 铸造数量
+    pub amount: u64,
+    /// 处理时间
+    pub processed_at: i64,
+}
+
+impl BridgeReceipt {
+    pub const SIZE: usize = 8 + // discriminator
+        2 + // source_chain
+        8 + // sequence
+        32 + // recipient
+        8 + // amount
+        8; // processed_at
+}
+
+/// 守护者签名的入站消息内容
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct BridgeMessage {
+    /// 本链代币铸币账户地址
+    pub mint: Pubkey,
+    /// 源链编号
+    pub source_chain: u16,
+    /// 源链上的消息序号
+    pub sequence: u64,
+    /// 收款代币账户
+    pub recipient: Pubkey,
+    /// 铸造数量
+    pub amount: u64,
+}
+
+/// 商户预授权，资金暂存于托管账户，商户在到期前扣款
+#[account]
+pub struct PaymentHold {
+    /// 付款人
+    pub payer: Pubkey,
+    /// 收款商户
+    pub merchant: Pubkey,
+    /// 付款人为同一商户创建的预授权编号，区分并存的多笔预授权
+    pub hold_id: u64,
+    /// 代币铸币账户地址
+    pub mint: Pubkey,
+    /// 付款代币账户，剩余资金退回至此
+    pub payer_token: Pubkey,
+    /// 冻结金额
+    pub amount: u64,
+    /// 预授权到期时间，到期后商户不能扣款，付款人可自行释放
+    pub expiry: i64,
+    /// 创建时间
+    pub created_at: i64,
+    /// PDA bump
+    pub bump: u8,
+}
+
+impl PaymentHold {
+    pub const SIZE: usize = 8 + // discriminator
+        32 + // payer
+        32 + // merchant
+        8 + // hold_id
+        32 + // mint
+        32 + // payer_token
+        8 + // amount
+        8 + // expiry
+        8 + // created_at
+        1; // bump
+}
+
+/// 按秒释放的支付流，资金托管于支付流账户，接收方随时提取已释放部分
+#[account]
+pub struct PaymentStream {
+    /// 付款方
+    pub sender: Pubkey,
+    /// 接收方
+    pub recipient: Pubkey,
+    /// 代币铸币账户地址
+    pub mint: Pubkey,
+    /// 付款代币账户，取消时未释放部分退回至此
+    pub sender_token: Pubkey,
+    /// 每秒释放数量
+    pub rate_per_second: u64,
+    /// 开始时间
+    pub start: i64,
+    /// 结束时间
+    pub stop: i64,
+    /// 托管总额
+    pub deposit: u64,
+    /// 接收方已提取数量
+    pub withdrawn: u64,
+    /// PDA bump
+    pub bump: u8,
+}
+
+impl PaymentStream {
+    pub const SIZE: usize = 8 + // discriminator
+        32 + // sender
+        32 + // recipient
+        32 + // mint
+        32 + // sender_token
+        8 + // rate_per_second
+        8 + // start
+        8 + // stop
+        8 + // deposit
+        8 + // withdrawn
+        1; // bump
+
+    /// 截至指定时间已释放的总数量
+    /// * `now` - 当前时间
+    pub fn accrued(&self, now: i64) -> u64 {
+        let elapsed = now.clamp(self.start, self.stop) - self.start;
+        // 总额在创建时已按 rate * (stop - start) 校验不会溢出
+        self.rate_per_second * elapsed as u64
+    }
+
+    /// 截至指定时间接收方可提取的数量
+    /// * `now` - 当前时间
+    pub fn withdrawable(&self, now: i64) -> u64 {
+        self.accrued(now).saturating_sub(self.withdrawn)
+    }
+}
+
+/// 制裁名单配置，存储被封禁所有者的 Merkle 根
+#[account]
+pub struct SanctionsConfig {
+    /// 代币铸币账户地址
+    pub mint: Pubkey,
+    /// 合规角色，负责更新 Merkle 根
+    pub compliance: Pubkey,
+    /// 被封禁所有者的 Merkle 根
+    pub merkle_root: [u8; 32],
+    /// 是否在转账时强制检查
+    pub enforced: bool,
+    /// 根的最近更新时间
+    pub updated_at: i64,
+}
+
+impl SanctionsConfig {
+    pub const SIZE: usize = 8 + // discriminator
+        32 + // mint
+        32 + // compliance
+        32 + // merkle_root
+        1 + // enforced
+        8; // updated_at
+}
+
+/// 制裁名单检查结果缓存，记录所有者在某个 Merkle 根下不在名单中
+/// 根更新后缓存自动失效，需要重新提交证明
+#[account]
+pub struct SanctionsClearance {
+    /// 被检查的所有者
+    pub owner: Pubkey,
+    /// 代币铸币账户地址
+    pub mint: Pubkey,
+    /// 通过检查时的 Merkle 根
+    pub merkle_root: [u8; 32],
+    /// 通过检查的时间
+    pub cleared_at: i64,
+}
+
+impl SanctionsClearance {
+    pub const SIZE: usize = 8 + // discriminator
+        32 + // owner
+        32 + // mint
+        32 + // merkle_root
+        8; // cleared_at
+}
+
+/// 转账事件中关联的旅行规则信息
+/// 记录账户会被转出方的下一次登记覆盖，事件中保留本次使用的内容
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct TravelRuleLink {
+    /// 旅行规则记录账户
+    pub record: Pubkey,
+    /// 加密的发起方和受益方信息的哈希
+    pub payload_hash: [u8; 32],
+    /// 受益方 VASP 标识
+    pub vasp_id: [u8; 32],
+}
+
+/// 旅行规则记录，大额转账前由转出方登记，转账时校验并标记为已使用
+/// 托管类付款（预授权、支付流、订阅）在锁定资金时校验，转入账户为对应的托管账户
+/// 发起方和受益方信息加密保存在链下，链上只保留密文哈希
+#[account]
+pub struct TravelRuleRecord {
+    /// 发起方（转出代币账户所有者）
+    pub originator: Pubkey,
+    /// 转出代币账户
+    pub from_token: Pubkey,
+    /// 转入代币账户
+    pub to_token: Pubkey,
+    /// 转账金额
+    pub amount: u64,
+    /// 加密的发起方和受益方信息的哈希
+    pub payload_hash: [u8; 32],
+    /// 受益方 VASP 标识
+    pub vasp_id: [u8; 32],
+    /// 是否已被转账使用
+    pub consumed: bool,
+    /// 登记时间
+    pub created_at: i64,
+}
+
+impl TravelRuleRecord {
+    pub const SIZE: usize = 8 + // discriminator
+        32 + // originator
+        32 + // from_token
+        32 + // to_token
+        8 + // amount
+        32 + // payload_hash
+        32 + // vasp_id
+        1 + // consumed
+        8; // created_at
+}
@@ -1,6 +1,12 @@
 use anchor_lang::prelude::*;
+use anchor_lang::system_program;
+use anchor_lang::solana_program::{ed25519_program, sysvar::instructions as ix_sysvar};
+use anchor_spl::token::TokenAccount;
 use crate::{AccessLevel, error::WusdError};
-use crate::state::{PauseState, AccessRegistryState}; 
+use crate::state::{
+    PauseState, AccessRegistryState, BalanceCheckpoints, VelocityConfig, VelocityLimit, RollingWindow, CreditAllowlistEntry,
+    SanctionsConfig, SanctionsClearance, MintState, TravelRuleRecord, TravelRuleLink,
+}; 
 
 /// 检查用户是否具有执行操作的权限
 /// 
@@ -45,4 +51,314 @@ pub fn require_has_access(
     }
 
     Ok(())
-}
\ No newline at end of file
+}
+
+/// 入账白名单模式下，检查接收方是否允许入账
+/// 白名单登记账户地址由调用方按种子传入，接收方未登记时该账户为空
+///
+/// # 参数
+/// * `access_registry` - 访问权限注册表
+/// * `recipient` - 接收方地址（代币账户所有者）
+/// * `allowlist_entry` - 接收方的白名单登记账户
+///
+/// # 错误
+/// * `WusdError::RecipientNotAllowlisted` - 接收方未登记
+pub fn require_credit_access<'info>(
+    access_registry: &AccessRegistryState,
+    recipient: Pubkey,
+    allowlist_entry: &AccountInfo<'info>,
+) -> Result<()> {
+    // 默认开放模式，或接收方本身是管理员/操作员
+    if access_registry.has_access(recipient, AccessLevel::Credit) {
+        return Ok(());
+    }
+
+    require!(!allowlist_entry.data_is_empty(), WusdError::RecipientNotAllowlisted);
+    let entry = Account::<CreditAllowlistEntry>::try_from(allowlist_entry)?;
+    require!(
+        entry.user == recipient && entry.allowed,
+        WusdError::RecipientNotAllowlisted
+    );
+    Ok(())
+}
+
+/// 持有人开启了余额快照时，记录代币账户变动后的余额
+/// 快照账户地址由调用方按种子传入，未开启快照时该账户为空
+///
+/// # 参数
+/// * `checkpoints` - 余额快照账户
+/// * `token_account` - 余额发生变动的代币账户
+pub fn record_checkpoint<'info>(
+    checkpoints: &AccountInfo<'info>,
+    token_account: &mut Account<'info, TokenAccount>,
+) -> Result<()> {
+    if checkpoints.data_is_empty() {
+        return Ok(());
+    }
+
+    let mut checkpoints = Box::new(Account::<BalanceCheckpoints>::try_from(checkpoints)?);
+    // CPI 之后重新加载余额
+    token_account.reload()?;
+    checkpoints.record(Clock::get()?.slot, token_account.amount)?;
+    checkpoints.exit(&crate::ID)
+}
+
+/// 累计本次转出金额并检查是否超出限额
+/// 限额账户和全局配置地址由调用方按种子传入，未设置时对应账户为空
+/// 账户尚无限额状态但已配置默认档位时，由付款人出资创建限额状态并按默认档位计数
+///
+/// # 参数
+/// * `velocity_limit` - 转出账户的限额状态
+/// * `velocity_config` - 限额全局配置，账户使用默认档位时读取
+/// * `token_account` - 转出的代币账户
+/// * `payer` - 创建限额状态时支付租金的账户
+/// * `system_program` - 系统程序
+/// * `amount` - 转出金额
+pub fn apply_velocity_limit<'info>(
+    velocity_limit: &AccountInfo<'info>,
+    velocity_config: &AccountInfo<'info>,
+    token_account: Pubkey,
+    payer: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    amount: u64,
+) -> Result<()> {
+    let now = Clock::get()?.unix_timestamp;
+
+    if velocity_limit.data_is_empty() {
+        if velocity_config.data_is_empty() {
+            return Ok(());
+        }
+        let tier = Account::<VelocityConfig>::try_from(velocity_config)?.default_tier;
+
+        let (_, bump) = Pubkey::find_program_address(
+            &[b"velocity", token_account.as_ref()],
+            &crate::ID,
+        );
+        create_pda_account(
+            payer,
+            velocity_limit,
+            system_program,
+            VelocityLimit::SIZE,
+            &[b"velocity", token_account.as_ref(), &[bump]],
+        )?;
+
+        let mut state = VelocityLimit {
+            token_account,
+            custom_tier: None,
+            window: RollingWindow::default(),
+        };
+        state.consume(amount, tier, now)?;
+        let mut data = velocity_limit.try_borrow_mut_data()?;
+        let mut writer: &mut [u8] = &mut data;
+        return state.try_serialize(&mut writer);
+    }
+
+    let mut velocity_limit = Box::new(Account::<VelocityLimit>::try_from(velocity_limit)?);
+    let tier = match velocity_limit.custom_tier {
+        Some(tier) => tier,
+        None => {
+            require!(!velocity_config.data_is_empty(), WusdError::VelocityConfigNotFound);
+            Account::<VelocityConfig>::try_from(velocity_config)?.default_tier
+        }
+    };
+
+    velocity_limit.consume(amount, tier, now)?;
+    velocity_limit.exit(&crate::ID)
+}
+
+/// 由付款人出资创建归本程序所有的 PDA 账户
+/// 目标地址已被预先转入 lamports 时，补足租金后分配空间并转移所有权
+fn create_pda_account<'info>(
+    payer: &AccountInfo<'info>,
+    account: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    space: usize,
+    seeds: &[&[u8]],
+) -> Result<()> {
+    let rent = Rent::get()?.minimum_balance(space);
+    let signer_seeds = &[seeds];
+
+    if account.lamports() == 0 {
+        return system_program::create_account(
+            CpiContext::new_with_signer(
+                system_program.clone(),
+                system_program::CreateAccount {
+                    from: payer.clone(),
+                    to: account.clone(),
+                },
+                signer_seeds,
+            ),
+            rent,
+            space as u64,
+            &crate::ID,
+        );
+    }
+
+    let shortfall = rent.saturating_sub(account.lamports());
+    if shortfall > 0 {
+        system_program::transfer(
+            CpiContext::new(
+                system_program.clone(),
+                system_program::Transfer {
+                    from: payer.clone(),
+                    to: account.clone(),
+                },
+            ),
+            shortfall,
+        )?;
+    }
+    system_program::allocate(
+        CpiContext::new_with_signer(
+            system_program.clone(),
+            system_program::Allocate { account_to_allocate: account.clone() },
+            signer_seeds,
+        ),
+        space as u64,
+    )?;
+    system_program::assign(
+        CpiContext::new_with_signer(
+            system_program.clone(),
+            system_program::Assign { account_to_assign: account.clone() },
+            signer_seeds,
+        ),
+        &crate::ID,
+    )
+}
+
+/// 制裁名单开启强制检查时，要求所有者持有当前 Merkle 根下的检查缓存
+/// 配置和缓存账户地址由调用方按种子传入，未配置名单或未通过检查时对应账户为空
+///
+/// # 参数
+/// * `sanctions_config` - 制裁名单配置
+/// * `clearance` - 所有者的检查缓存
+/// * `owner` - 代币账户所有者
+///
+/// # 错误
+/// * `WusdError::SanctionsNotCleared` - 缓存不存在或已随根更新失效
+pub fn require_not_sanctioned<'info>(
+    sanctions_config: &AccountInfo<'info>,
+    clearance: &AccountInfo<'info>,
+    owner: Pubkey,
+) -> Result<()> {
+    if sanctions_config.data_is_empty() {
+        return Ok(());
+    }
+    let config = Account::<SanctionsConfig>::try_from(sanctions_config)?;
+    if !config.enforced {
+        return Ok(());
+    }
+
+    require!(!clearance.data_is_empty(), WusdError::SanctionsNotCleared);
+    let clearance = Account::<SanctionsClearance>::try_from(clearance)?;
+    require!(
+        clearance.owner == owner && clearance.merkle_root == config.merkle_root,
+        WusdError::SanctionsNotCleared
+    );
+    Ok(())
+}
+
+/// 转账金额超过旅行规则门槛时，校验并使用转出方登记的旅行规则记录
+/// 记录账户地址由调用方按种子传入，未登记时该账户为空
+///
+/// # 参数
+/// * `mint_state` - 铸币状态，保存门槛金额
+/// * `record` - 转出方的旅行规则记录
+/// * `from_token` - 转出代币账户
+/// * `to_token` - 转入代币账户
+/// * `amount` - 转账金额
+///
+/// # 返回值
+/// * 使用了记录时返回记录地址及其内容，供转账事件关联
+///
+/// # 错误
+/// * `WusdError::TravelRuleRequired` - 超过门槛但未登记记录
+/// * `WusdError::TravelRuleMismatch` - 记录与本次转账不一致或已被使用
+pub fn consume_travel_rule<'info>(
+    mint_state: &MintState,
+    record: &AccountInfo<'info>,
+    from_token: Pubkey,
+    to_token: Pubkey,
+    amount: u64,
+) -> Result<Option<TravelRuleLink>> {
+    if !mint_state.requires_travel_rule(amount) {
+        return Ok(None);
+    }
+
+    require!(!record.data_is_empty(), WusdError::TravelRuleRequired);
+    let mut entry = Box::new(Account::<TravelRuleRecord>::try_from(record)?);
+    require!(
+        !entry.consumed
+            && entry.from_token == from_token
+            && entry.to_token == to_token
+            && entry.amount == amount,
+        WusdError::TravelRuleMismatch
+    );
+    entry.consumed = true;
+    entry.exit(&crate::ID)?;
+    Ok(Some(TravelRuleLink {
+        record: record.key(),
+        payload_hash: entry.payload_hash,
+        vasp_id: entry.vasp_id,
+    }))
+}
+
+/// Ed25519 程序指令中每个签名的偏移量描述长度
+const ED25519_OFFSETS_SIZE: usize = 14;
+/// Ed25519 程序指令数据的头部长度（签名数量 + 填充）
+const ED25519_HEADER_SIZE: usize = 2;
+
+/// 扫描当前指令之前的 Ed25519 验签指令，返回对指定消息签名的公钥
+/// 验签本身由 Ed25519 程序完成，交易中任一验签失败都会整体回滚，
+/// 这里只检查签名公钥和消息内容
+///
+/// # 参数
+/// * `instructions` - 指令 sysvar 账户
+/// * `message` - 期望被签名的消息
+///
+/// # 错误
+/// * `WusdError::InvalidSignature` - 验签指令格式无效，或签名数据不在该指令内
+pub fn ed25519_signers(instructions: &AccountInfo, message: &[u8]) -> Result<Vec<Pubkey>> {
+    let mut signers = Vec::new();
+    let current_index = ix_sysvar::load_current_index_checked(instructions)?;
+
+    for index in 0..current_index {
+        let ix = ix_sysvar::load_instruction_at_checked(index as usize, instructions)?;
+        if ix.program_id != ed25519_program::ID {
+            continue;
+        }
+
+        let data = &ix.data;
+        require!(data.len() >= ED25519_HEADER_SIZE, WusdError::InvalidSignature);
+        let count = data[0] as usize;
+
+        for i in 0..count {
+            let start = ED25519_HEADER_SIZE + i * ED25519_OFFSETS_SIZE;
+            let offsets = data
+                .get(start..start + ED25519_OFFSETS_SIZE)
+                .ok_or(WusdError::InvalidSignature)?;
+            let read_u16 = |at: usize| u16::from_le_bytes([offsets[at], offsets[at + 1]]);
+
+            // 签名、公钥和消息都必须位于该指令自身的数据中
+            require!(
+                read_u16(2) == u16::MAX && read_u16(6) == u16::MAX && read_u16(12) == u16::MAX,
+                WusdError::InvalidSignature
+            );
+
+            let pubkey_offset = read_u16(4) as usize;
+            let message_offset = read_u16(8) as usize;
+            let message_size = read_u16(10) as usize;
+
+            let pubkey = data
+                .get(pubkey_offset..pubkey_offset + 32)
+                .ok_or(WusdError::InvalidSignature)?;
+            let signed_message = data
+                .get(message_offset..message_offset + message_size)
+                .ok_or(WusdError::InvalidSignature)?;
+            if signed_message == message {
+                signers.push(Pubkey::try_from(pubkey).map_err(|_| WusdError::InvalidSignature)?);
+            }
+        }
+    }
+
+    Ok(signers)
+}
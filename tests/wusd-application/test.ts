@@ -11,6 +11,7 @@ import {
   createAssociatedTokenAccountInstruction,
   createInitializeMintInstruction,
   getMinimumBalanceForRentExemptMint,
+  mintTo,
   MINT_SIZE,
 } from "@solana/spl-token";
 import { WusdApplication } from "../../target/types/wusd_application";
@@ -62,6 +63,7 @@ describe("WUSD Application Tests", () => {
 
   // 定义PDA账户
   let statePda: PublicKey;
+  let userStakePda: PublicKey;
   let stakeAccountPda: PublicKey;
   let softStakeAccountPda: PublicKey; 
   let stateBump: number;
@@ -71,6 +73,35 @@ describe("WUSD Application Tests", () => {
   let userCollateralAccount: PublicKey;
   let treasuryAccount: PublicKey; 
 
+  // 用户第 n 个质押仓位的地址
+  const positionPda = (positionId: anchor.BN) =>
+    PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("stake_account"),
+        user.publicKey.toBuffer(),
+        positionId.toArrayLike(Buffer, "le", 8),
+      ],
+      program.programId
+    )[0];
+  const rewardVaultPda = (stakingPoolId: anchor.BN) =>
+    PublicKey.findProgramAddressSync(
+      [Buffer.from("reward_vault"), stakingPoolId.toArrayLike(Buffer, "le", 8)],
+      program.programId
+    )[0];
+
+  // 解析交易日志中的事件
+  const parseEvents = async (signature: string) => {
+    const tx = await provider.connection.getTransaction(signature, {
+      commitment: "confirmed",
+      maxSupportedTransactionVersion: 0,
+    });
+    const parser = new anchor.EventParser(
+      program.programId,
+      new anchor.BorshCoder(program.idl)
+    );
+    return Array.from(parser.parseLogs(tx.meta.logMessages));
+  };
+
   before(async () => {
     try {
       console.log("Starting initialization..."); 
@@ -87,13 +118,23 @@ describe("WUSD Application Tests", () => {
         program.programId
       );
 
+      [userStakePda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("user_stake"), user.publicKey.toBuffer()],
+        program.programId
+      );
+
+      // 用户的第一个质押仓位
       [stakeAccountPda] = PublicKey.findProgramAddressSync(
-        [Buffer.from("stake_account"), user.publicKey.toBuffer()],
+        [
+          Buffer.from("stake_account"),
+          user.publicKey.toBuffer(),
+          new anchor.BN(0).toArrayLike(Buffer, "le", 8),
+        ],
         program.programId
       );
 
       [softStakeAccountPda] = PublicKey.findProgramAddressSync(
-        [Buffer.from("soft_stake"), user.publicKey.toBuffer()],
+        [Buffer.from("soft_stake_account"), user.publicKey.toBuffer()],
         program.programId
       );
 
@@ -284,18 +325,17 @@ describe("WUSD Application Tests", () => {
           .initializeStakeAccount()
           .accounts({
             user: user.publicKey,
-            stakeAccount: stakeAccountPda,
-            state: statePda,
+            userStake: userStakePda,
             systemProgram: SystemProgram.programId,
           })
           .signers([user])
           .rpc();
 
-        const stakeAccount = await program.account.stakeAccount.fetch(
-          stakeAccountPda
+        const userStake = await program.account.userStakeState.fetch(
+          userStakePda
         );
-        assert.equal(stakeAccount.owner.toString(), user.publicKey.toString());
-        assert.equal(stakeAccount.amount.toNumber(), 0);
+        assert.equal(userStake.owner.toString(), user.publicKey.toString());
+        assert.equal(userStake.positionCount.toNumber(), 0);
         console.log("Stake account initialized successfully");
       } catch (error) {
         console.error("Failed to initialize stake account:", error);
@@ -307,141 +347,1061 @@ describe("WUSD Application Tests", () => {
     }
   });
 
+  it("Create staking pool", async () => {
+    try {
+      const apy = new anchor.BN(100000); // 10% APY
+      const duration = new anchor.BN(0); // 无锁定期
+      const minStakeAmount = new anchor.BN(1000000); // 1 WUSD
+
+      await program.methods
+        .createStakingPool(apy, duration, minStakeAmount)
+        .accounts({
+          authority: provider.wallet.publicKey,
+          state: statePda,
+        })
+        .rpc();
+
+      const state = await program.account.stateAccount.fetch(statePda);
+      const pool = state.stakingPools[0];
+      assert.equal(pool.apy.toNumber(), apy.toNumber());
+      assert.equal(pool.minStakeAmount.toNumber(), minStakeAmount.toNumber());
+      assert.ok(state.stakingConfig.totalStakingPlans.toNumber() >= 1);
+      console.log("Staking pool created");
+    } catch (error) {
+      console.error("Create staking pool failed:", error);
+      throw error;
+    }
+  });
+
   it("Stake WUSD tokens", async () => {
     try {
       const stakeAmount = new anchor.BN(1000000000); // 1000 WUSD
       const stakingPoolId = new anchor.BN(0);
 
-      // 创建质押金库账户
-      const [stakeVaultPda] = PublicKey.findProgramAddressSync(
-        [Buffer.from("stake_vault"), stakeAccountPda.toBuffer()],
+      // 质押金库由程序在首次质押时创建
+      const [stakeVaultAccount] = PublicKey.findProgramAddressSync(
+        [Buffer.from("stake_vault"), wusdMint.publicKey.toBuffer()],
+        program.programId
+      );
+      const [rewardVaultAccount] = PublicKey.findProgramAddressSync(
+        [Buffer.from("reward_vault"), stakingPoolId.toArrayLike(Buffer, "le", 8)],
+        program.programId
+      );
+      const [poolTiersPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("pool_tiers"), stakingPoolId.toArrayLike(Buffer, "le", 8)],
         program.programId
       );
 
-      const stakeVaultAccount = await anchor.utils.token.associatedAddress({
-        mint: wusdMint.publicKey,
-        owner: stakeVaultPda
-      });
+      const stake = () =>
+        program.methods
+          .stake(stakeAmount, stakingPoolId)
+          .accounts({
+            user: user.publicKey,
+            userStake: userStakePda,
+            stakeAccount: stakeAccountPda,
+            state: statePda,
+            wusdMint: wusdMint.publicKey,
+            userWusd: userWusdAccount,
+            stakeVault: stakeVaultAccount,
+            rewardVault: rewardVaultAccount,
+            poolTiers: poolTiersPda,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            systemProgram: SystemProgram.programId,
+            rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+          })
+          .signers([user])
+          .rpc();
 
-      // 创建质押金库的代币账户
-      const createStakeVaultAccountIx = createAssociatedTokenAccountInstruction(
+      // 无锁定期的质押池也须覆盖至少一天的发放，奖励金库为空时应拒绝
+      let rejected = false;
+      try {
+        await stake();
+      } catch (error) {
+        rejected = true;
+        assert.include(error.toString(), "InsufficientRewardBalance");
+      }
+      assert.ok(rejected, "stake should fail while the reward vault is empty");
+
+      // 1000 WUSD 按 10% APY 每天约发放 0.27 WUSD，注入 1 WUSD
+      const fundAmount = new anchor.BN(1000000);
+      await mintTo(
+        provider.connection,
+        provider.wallet.payer,
+        wusdMint.publicKey,
+        treasuryAccount,
         provider.wallet.publicKey,
-        stakeVaultAccount,
-        stakeVaultPda,
+        BigInt(fundAmount.toString())
+      );
+      await program.methods
+        .fundRewards(stakingPoolId, fundAmount)
+        .accounts({
+          funder: treasury.publicKey,
+          state: statePda,
+          wusdMint: wusdMint.publicKey,
+          treasury: treasuryAccount,
+          rewardVault: rewardVaultAccount,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+          rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+        })
+        .signers([treasury])
+        .rpc();
+
+      // 执行质押操作
+      await stake();
+
+      const stakeAccount = await program.account.stakeAccount.fetch(
+        stakeAccountPda
+      );
+      assert.equal(stakeAccount.stakeInfo.amount.toNumber(), stakeAmount.toNumber());
+
+      const vaultBalance = await provider.connection.getTokenAccountBalance(
+        stakeVaultAccount
+      );
+      assert.equal(vaultBalance.value.amount, stakeAmount.toString());
+      console.log("Staking successful");
+    } catch (error) {
+      console.error("Staking failed:", error);
+      throw error;
+    }
+  });
+
+  it("Open a second concurrent position in another pool", async () => {
+    try {
+      const stakeAmount = new anchor.BN(500000000); // 500 WUSD
+      const fundAmount = new anchor.BN(1000000); // 1 WUSD
+
+      // 锁定 60 秒的另一个质押池
+      const state = await program.account.stateAccount.fetch(statePda);
+      const stakingPoolId = new anchor.BN(state.stakingConfig.totalStakingPlans);
+      await program.methods
+        .createStakingPool(new anchor.BN(100000), new anchor.BN(60), new anchor.BN(1000000))
+        .accounts({
+          authority: provider.wallet.publicKey,
+          state: statePda,
+        })
+        .rpc();
+
+      await mintTo(
+        provider.connection,
+        provider.wallet.payer,
+        wusdMint.publicKey,
+        userWusdAccount,
+        provider.wallet.publicKey,
+        BigInt(stakeAmount.toString())
+      );
+      await mintTo(
+        provider.connection,
+        provider.wallet.payer,
         wusdMint.publicKey,
-        TOKEN_PROGRAM_ID
+        treasuryAccount,
+        provider.wallet.publicKey,
+        BigInt(fundAmount.toString())
       );
+      const rewardVault = rewardVaultPda(stakingPoolId);
+      await program.methods
+        .fundRewards(stakingPoolId, fundAmount)
+        .accounts({
+          funder: treasury.publicKey,
+          state: statePda,
+          wusdMint: wusdMint.publicKey,
+          treasury: treasuryAccount,
+          rewardVault,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+          rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+        })
+        .signers([treasury])
+        .rpc();
 
-      const stakeVaultTx = new anchor.web3.Transaction().add(createStakeVaultAccountIx);
-      await provider.sendAndConfirm(stakeVaultTx);
-      await sleep(1000);
+      const [stakeVaultAccount] = PublicKey.findProgramAddressSync(
+        [Buffer.from("stake_vault"), wusdMint.publicKey.toBuffer()],
+        program.programId
+      );
+      const [poolTiersPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("pool_tiers"), stakingPoolId.toArrayLike(Buffer, "le", 8)],
+        program.programId
+      );
+      const firstBefore = await program.account.stakeAccount.fetch(stakeAccountPda);
+      const userStakeBefore = await program.account.userStakeState.fetch(userStakePda);
+      const positionId = userStakeBefore.positionCount;
+      const position = positionPda(positionId);
 
-      // 执行质押操作
       await program.methods
         .stake(stakeAmount, stakingPoolId)
         .accounts({
           user: user.publicKey,
-          stakeAccount: stakeAccountPda,
+          userStake: userStakePda,
+          stakeAccount: position,
           state: statePda,
           wusdMint: wusdMint.publicKey,
           userWusd: userWusdAccount,
           stakeVault: stakeVaultAccount,
+          rewardVault,
+          poolTiers: poolTiersPda,
           tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+          rent: anchor.web3.SYSVAR_RENT_PUBKEY,
         })
         .signers([user])
         .rpc();
 
-      const stakeAccount = await program.account.stakeAccount.fetch(
-        stakeAccountPda
+      // 新仓位按计数器编号，原仓位不受影响
+      const userStake = await program.account.userStakeState.fetch(userStakePda);
+      assert.equal(userStake.positionCount.toNumber(), positionId.toNumber() + 1);
+      const second = await program.account.stakeAccount.fetch(position);
+      assert.equal(second.positionId.toNumber(), positionId.toNumber());
+      assert.equal(second.stakingPoolId.toNumber(), stakingPoolId.toNumber());
+      assert.equal(second.stakeInfo.amount.toString(), stakeAmount.toString());
+      assert.equal(
+        second.timeInfo.endTime.toNumber() - second.timeInfo.startTime.toNumber(),
+        60
       );
-      assert.equal(stakeAccount.amount.toNumber(), stakeAmount.toNumber());
-      console.log("Staking successful");
+      const first = await program.account.stakeAccount.fetch(stakeAccountPda);
+      assert.equal(first.positionId.toNumber(), 0);
+      assert.equal(first.stakingPoolId.toNumber(), firstBefore.stakingPoolId.toNumber());
+      assert.equal(first.stakeInfo.amount.toString(), firstBefore.stakeInfo.amount.toString());
+      assert.ok(first.status.active !== undefined);
+      console.log("Concurrent positions:", [0, positionId.toNumber()]);
     } catch (error) {
-      console.error("Staking failed:", error);
+      console.error("Second position failed:", error);
+      throw error;
+    }
+  });
+
+  it("Assign APY tiers and upgrade them by extending the lock", async () => {
+    try {
+      const smallAmount = new anchor.BN(50000000); // 50 WUSD
+      const largeAmount = new anchor.BN(200000000); // 200 WUSD
+      const fundAmount = new anchor.BN(1000000); // 1 WUSD
+
+      // 锁定 60 秒的质押池，档位 1：锁定 60 秒且不少于 100 WUSD 为 1.5 倍；档位 2：锁定 120 秒为 2 倍
+      const state = await program.account.stateAccount.fetch(statePda);
+      const stakingPoolId = new anchor.BN(state.stakingConfig.totalStakingPlans);
+      await program.methods
+        .createStakingPool(new anchor.BN(100000), new anchor.BN(60), new anchor.BN(1000000))
+        .accounts({
+          authority: provider.wallet.publicKey,
+          state: statePda,
+        })
+        .rpc();
+      const [poolTiersPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("pool_tiers"), stakingPoolId.toArrayLike(Buffer, "le", 8)],
+        program.programId
+      );
+      await program.methods
+        .setPoolTiers(stakingPoolId, [
+          {
+            minLockDuration: new anchor.BN(60),
+            minAmount: new anchor.BN(100000000),
+            multiplier: new anchor.BN(15000),
+          },
+          {
+            minLockDuration: new anchor.BN(120),
+            minAmount: new anchor.BN(100000000),
+            multiplier: new anchor.BN(20000),
+          },
+        ])
+        .accounts({
+          authority: provider.wallet.publicKey,
+          state: statePda,
+          poolTiers: poolTiersPda,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+
+      await mintTo(
+        provider.connection,
+        provider.wallet.payer,
+        wusdMint.publicKey,
+        userWusdAccount,
+        provider.wallet.publicKey,
+        BigInt(smallAmount.add(largeAmount).toString())
+      );
+      await mintTo(
+        provider.connection,
+        provider.wallet.payer,
+        wusdMint.publicKey,
+        treasuryAccount,
+        provider.wallet.publicKey,
+        BigInt(fundAmount.toString())
+      );
+      const rewardVault = rewardVaultPda(stakingPoolId);
+      await program.methods
+        .fundRewards(stakingPoolId, fundAmount)
+        .accounts({
+          funder: treasury.publicKey,
+          state: statePda,
+          wusdMint: wusdMint.publicKey,
+          treasury: treasuryAccount,
+          rewardVault,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+          rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+        })
+        .signers([treasury])
+        .rpc();
+
+      const [stakeVaultAccount] = PublicKey.findProgramAddressSync(
+        [Buffer.from("stake_vault"), wusdMint.publicKey.toBuffer()],
+        program.programId
+      );
+      const stake = async (amount: anchor.BN) => {
+        const userStake = await program.account.userStakeState.fetch(userStakePda);
+        const position = positionPda(userStake.positionCount);
+        await program.methods
+          .stake(amount, stakingPoolId)
+          .accounts({
+            user: user.publicKey,
+            userStake: userStakePda,
+            stakeAccount: position,
+            state: statePda,
+            wusdMint: wusdMint.publicKey,
+            userWusd: userWusdAccount,
+            stakeVault: stakeVaultAccount,
+            rewardVault,
+            poolTiers: poolTiersPda,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            systemProgram: SystemProgram.programId,
+            rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+          })
+          .signers([user])
+          .rpc();
+        return position;
+      };
+
+      // 金额未达门槛的仓位无加成，达到门槛的仓位分配到档位 1
+      const small = await program.account.stakeAccount.fetch(await stake(smallAmount));
+      assert.equal(small.stakeInfo.apyTier, 0);
+      assert.equal(small.stakeInfo.apyMultiplier.toNumber(), 10000);
+      const largePosition = await stake(largeAmount);
+      let large = await program.account.stakeAccount.fetch(largePosition);
+      assert.equal(large.stakeInfo.apyTier, 1);
+      assert.equal(large.stakeInfo.apyMultiplier.toNumber(), 15000);
+
+      const weighted = async () =>
+        BigInt(
+          (await program.account.stateAccount.fetch(statePda))
+            .stakingPools[stakingPoolId.toNumber()].totalWeightedStaked.toString()
+        );
+      const expectedBefore =
+        BigInt(smallAmount.toString()) + (BigInt(largeAmount.toString()) * BigInt(3)) / BigInt(2);
+      assert.equal((await weighted()).toString(), expectedBefore.toString());
+
+      // 锁定期延长到 120 秒后升到档位 2，质押池的加权质押随之调整
+      const newEndTime = large.timeInfo.startTime.add(new anchor.BN(120));
+      await program.methods
+        .extendLock(newEndTime)
+        .accounts({
+          user: user.publicKey,
+          stakeAccount: largePosition,
+          state: statePda,
+          poolTiers: poolTiersPda,
+        })
+        .signers([user])
+        .rpc();
+      large = await program.account.stakeAccount.fetch(largePosition);
+      assert.equal(large.timeInfo.endTime.toString(), newEndTime.toString());
+      assert.equal(large.stakeInfo.apyTier, 2);
+      assert.equal(large.stakeInfo.apyMultiplier.toNumber(), 20000);
+      const expectedAfter =
+        BigInt(smallAmount.toString()) + BigInt(largeAmount.toString()) * BigInt(2);
+      assert.equal((await weighted()).toString(), expectedAfter.toString());
+      console.log("APY tiers:", { small: small.stakeInfo.apyTier, large: large.stakeInfo.apyTier });
+    } catch (error) {
+      console.error("APY tiers failed:", error);
       throw error;
     }
   });
 
-  it("Soft stake WUSD tokens", async () => {
+  it("Fund rewards and read the runway", async () => {
     try {
-      const softStakeAmount = new anchor.BN(500000000); // 500 WUSD
       const stakingPoolId = new anchor.BN(0);
-      const accessKey = new Uint8Array(32); // Generate proper access key in production
+      const fundAmount = new anchor.BN(100000000); // 100 WUSD
+      const rewardVault = rewardVaultPda(stakingPoolId);
+      const runway = () =>
+        program.methods
+          .getRewardRunway(stakingPoolId)
+          .accounts({
+            state: statePda,
+            rewardVault,
+          })
+          .view();
+
+      const vaultBefore = Number(
+        (await provider.connection.getTokenAccountBalance(rewardVault)).value.amount
+      );
+      await mintTo(
+        provider.connection,
+        provider.wallet.payer,
+        wusdMint.publicKey,
+        treasuryAccount,
+        provider.wallet.publicKey,
+        BigInt(fundAmount.toString())
+      );
+      await program.methods
+        .fundRewards(stakingPoolId, fundAmount)
+        .accounts({
+          funder: treasury.publicKey,
+          state: statePda,
+          wusdMint: wusdMint.publicKey,
+          treasury: treasuryAccount,
+          rewardVault,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+          rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+        })
+        .signers([treasury])
+        .rpc();
+
+      const vaultAfter = Number(
+        (await provider.connection.getTokenAccountBalance(rewardVault)).value.amount
+      );
+      assert.equal(vaultAfter - vaultBefore, fundAmount.toNumber());
+
+      // 按 APY 发放时，剩余时长约为金库余额除以每秒发放量
+      const state = await program.account.stateAccount.fetch(statePda);
+      const pool = state.stakingPools[0];
+      const perSecond =
+        (Number(pool.totalWeightedStaked.toString()) * pool.apy.toNumber()) /
+        (365 * 24 * 60 * 60 * 1000000);
+      assert.isAbove(perSecond, 0);
+      const apyRunway = (await runway()).toNumber();
+      assert.isAbove(apyRunway, 0);
+      assert.isAtMost(apyRunway, Math.ceil(vaultAfter / Math.floor(perSecond)));
 
-      // 创建质押金库账户
-      const [stakeVaultPda] = PublicKey.findProgramAddressSync(
-        [Buffer.from("stake_vault"), softStakeAccountPda.toBuffer()],
+      // 设置了结束时间的发放计划，剩余时长不超过计划结束时间
+      const now = Math.floor(Date.now() / 1000);
+      const setSchedule = (rate: number, start: number, end: number) =>
+        program.methods
+          .setEmissionSchedule(
+            stakingPoolId,
+            new anchor.BN(rate),
+            new anchor.BN(start),
+            new anchor.BN(end)
+          )
+          .accounts({
+            authority: provider.wallet.publicKey,
+            state: statePda,
+          })
+          .rpc();
+      await setSchedule(Math.floor(perSecond), now, now + 600);
+      try {
+        const scheduledRunway = (await runway()).toNumber();
+        assert.isAtMost(scheduledRunway, 600);
+        assert.isAbove(scheduledRunway, 0);
+        console.log("Reward runway:", { apyRunway, scheduledRunway });
+      } finally {
+        // 恢复为只按 APY 发放
+        await setSchedule(0, 0, 0);
+      }
+    } catch (error) {
+      console.error("Fund rewards failed:", error);
+      throw error;
+    }
+  });
+
+  it("Compound rewards into principal", async () => {
+    try {
+      const stakeAmount = new anchor.BN(10000000000); // 10000 WUSD
+      const fundAmount = new anchor.BN(1000000000); // 1000 WUSD
+      const tipBps = new anchor.BN(100); // 1%
+
+      // 使用单独的高 APY 质押池，使几秒内累计的奖励足以产生小费
+      let state = await program.account.stateAccount.fetch(statePda);
+      const stakingPoolId = new anchor.BN(state.stakingConfig.totalStakingPlans);
+      await program.methods
+        .createStakingPool(new anchor.BN(1000000), new anchor.BN(0), new anchor.BN(1000000))
+        .accounts({
+          authority: provider.wallet.publicKey,
+          state: statePda,
+        })
+        .rpc();
+      const tipTx = await program.methods
+        .setCompoundTip(tipBps)
+        .accounts({
+          authority: provider.wallet.publicKey,
+          state: statePda,
+        })
+        .rpc({ commitment: "confirmed" });
+      const tipEvent = (await parseEvents(tipTx)).find(
+        (event) => event.name === "CompoundTipSet"
+      );
+      assert.ok(tipEvent, "CompoundTipSet not emitted");
+      assert.equal(tipEvent.data.tipBps.toString(), tipBps.toString());
+
+      // 为用户和金库铸造 WUSD，并注入奖励
+      await mintTo(
+        provider.connection,
+        provider.wallet.payer,
+        wusdMint.publicKey,
+        userWusdAccount,
+        provider.wallet.publicKey,
+        BigInt(stakeAmount.toString())
+      );
+      await mintTo(
+        provider.connection,
+        provider.wallet.payer,
+        wusdMint.publicKey,
+        treasuryAccount,
+        provider.wallet.publicKey,
+        BigInt(fundAmount.toString())
+      );
+      const rewardVault = rewardVaultPda(stakingPoolId);
+      await program.methods
+        .fundRewards(stakingPoolId, fundAmount)
+        .accounts({
+          funder: treasury.publicKey,
+          state: statePda,
+          wusdMint: wusdMint.publicKey,
+          treasury: treasuryAccount,
+          rewardVault,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+          rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+        })
+        .signers([treasury])
+        .rpc();
+
+      const [stakeVaultAccount] = PublicKey.findProgramAddressSync(
+        [Buffer.from("stake_vault"), wusdMint.publicKey.toBuffer()],
+        program.programId
+      );
+      const [poolTiersPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("pool_tiers"), stakingPoolId.toArrayLike(Buffer, "le", 8)],
         program.programId
       );
+      const userStake = await program.account.userStakeState.fetch(userStakePda);
+      const position = positionPda(userStake.positionCount);
+      await program.methods
+        .stake(stakeAmount, stakingPoolId)
+        .accounts({
+          user: user.publicKey,
+          userStake: userStakePda,
+          stakeAccount: position,
+          state: statePda,
+          wusdMint: wusdMint.publicKey,
+          userWusd: userWusdAccount,
+          stakeVault: stakeVaultAccount,
+          rewardVault,
+          poolTiers: poolTiersPda,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+          rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+        })
+        .signers([user])
+        .rpc();
 
-      const stakeVaultAccount = await anchor.utils.token.associatedAddress({
+      // 调用者的小费账户
+      const cranker = Keypair.generate();
+      const crankerWusd = await anchor.utils.token.associatedAddress({
         mint: wusdMint.publicKey,
-        owner: stakeVaultPda
+        owner: cranker.publicKey,
+      });
+      await provider.sendAndConfirm(
+        new anchor.web3.Transaction().add(
+          createAssociatedTokenAccountInstruction(
+            provider.wallet.publicKey,
+            crankerWusd,
+            cranker.publicKey,
+            wusdMint.publicKey,
+            TOKEN_PROGRAM_ID
+          )
+        )
+      );
+      const compoundAccounts = {
+        cranker: cranker.publicKey,
+        stakeAccount: position,
+        state: statePda,
+        wusdMint: wusdMint.publicKey,
+        stakeVault: stakeVaultAccount,
+        rewardVault,
+        crankerWusd,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      };
+
+      await sleep(3000);
+
+      // 未开启自动复投时应拒绝
+      let rejected = false;
+      try {
+        await program.methods
+          .compound()
+          .accounts(compoundAccounts)
+          .signers([cranker])
+          .rpc();
+      } catch (error) {
+        rejected = true;
+        assert.include(error.toString(), "InvalidStakingStatus");
+      }
+      assert.ok(rejected, "compound should fail while auto compound is off");
+
+      await program.methods
+        .setAutoCompound(true)
+        .accounts({
+          user: user.publicKey,
+          stakeAccount: position,
+        })
+        .signers([user])
+        .rpc();
+
+      const balance = async (account: PublicKey) =>
+        BigInt((await provider.connection.getTokenAccountBalance(account)).value.amount);
+      const rewardVaultBefore = await balance(rewardVault);
+      const stakeVaultBefore = await balance(stakeVaultAccount);
+
+      await program.methods
+        .compound()
+        .accounts(compoundAccounts)
+        .signers([cranker])
+        .rpc();
+
+      // 奖励金库转出的奖励按小费比例拆分给调用者，其余转入质押金库成为本金
+      const rewards = rewardVaultBefore - (await balance(rewardVault));
+      const tip = await balance(crankerWusd);
+      const compounded = (await balance(stakeVaultAccount)) - stakeVaultBefore;
+      assert.ok(rewards > BigInt(0));
+      assert.equal((tip + compounded).toString(), rewards.toString());
+      assert.equal(
+        tip.toString(),
+        ((rewards * BigInt(tipBps.toString())) / BigInt(10000)).toString()
+      );
+
+      const stakeAccount = await program.account.stakeAccount.fetch(position);
+      assert.equal(
+        stakeAccount.stakeInfo.amount.toString(),
+        (BigInt(stakeAmount.toString()) + compounded).toString()
+      );
+      assert.equal(stakeAccount.stakeInfo.rewardsEarned.toNumber(), 0);
+      state = await program.account.stateAccount.fetch(statePda);
+      assert.equal(
+        state.stakingPools[stakingPoolId.toNumber()].totalStaked.toString(),
+        stakeAccount.stakeInfo.amount.toString()
+      );
+      console.log("Compounded:", compounded.toString(), "tip:", tip.toString());
+    } catch (error) {
+      console.error("Compound failed:", error);
+      throw error;
+    }
+  });
+
+  it("Early exit keeps, forfeits and claws back rewards", async () => {
+    try {
+      const stakeAmount = new anchor.BN(10000000000); // 10000 WUSD
+      const haircut = 100000; // 剩余锁定期全部时扣除 10% 本金
+
+      // 锁定 60 秒、APY 100% 的质押池
+      const state = await program.account.stateAccount.fetch(statePda);
+      const stakingPoolId = new anchor.BN(state.stakingConfig.totalStakingPlans);
+      await program.methods
+        .createStakingPool(new anchor.BN(1000000), new anchor.BN(60), new anchor.BN(1000000))
+        .accounts({
+          authority: provider.wallet.publicKey,
+          state: statePda,
+        })
+        .rpc();
+      const haircutTx = await program.methods
+        .setEarlyExitHaircut(stakingPoolId, new anchor.BN(haircut))
+        .accounts({
+          authority: provider.wallet.publicKey,
+          state: statePda,
+        })
+        .rpc({ commitment: "confirmed" });
+      const haircutEvent = (await parseEvents(haircutTx)).find(
+        (event) => event.name === "EarlyExitHaircutSet"
+      );
+      assert.ok(haircutEvent, "EarlyExitHaircutSet not emitted");
+      assert.equal(haircutEvent.data.stakingPoolId.toNumber(), stakingPoolId.toNumber());
+      assert.equal(haircutEvent.data.earlyExitHaircut.toNumber(), haircut);
+
+      const rewardVault = rewardVaultPda(stakingPoolId);
+      await mintTo(
+        provider.connection,
+        provider.wallet.payer,
+        wusdMint.publicKey,
+        treasuryAccount,
+        provider.wallet.publicKey,
+        BigInt(100000000)
+      );
+      await program.methods
+        .fundRewards(stakingPoolId, new anchor.BN(100000000))
+        .accounts({
+          funder: treasury.publicKey,
+          state: statePda,
+          wusdMint: wusdMint.publicKey,
+          treasury: treasuryAccount,
+          rewardVault,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+          rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+        })
+        .signers([treasury])
+        .rpc();
+
+      const [stakeVaultAccount] = PublicKey.findProgramAddressSync(
+        [Buffer.from("stake_vault"), wusdMint.publicKey.toBuffer()],
+        program.programId
+      );
+      const [poolTiersPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("pool_tiers"), stakingPoolId.toArrayLike(Buffer, "le", 8)],
+        program.programId
+      );
+      const openPosition = async () => {
+        await mintTo(
+          provider.connection,
+          provider.wallet.payer,
+          wusdMint.publicKey,
+          userWusdAccount,
+          provider.wallet.publicKey,
+          BigInt(stakeAmount.toString())
+        );
+        const userStake = await program.account.userStakeState.fetch(userStakePda);
+        const position = positionPda(userStake.positionCount);
+        await program.methods
+          .stake(stakeAmount, stakingPoolId)
+          .accounts({
+            user: user.publicKey,
+            userStake: userStakePda,
+            stakeAccount: position,
+            state: statePda,
+            wusdMint: wusdMint.publicKey,
+            userWusd: userWusdAccount,
+            stakeVault: stakeVaultAccount,
+            rewardVault,
+            poolTiers: poolTiersPda,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            systemProgram: SystemProgram.programId,
+            rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+          })
+          .signers([user])
+          .rpc();
+        return position;
+      };
+
+      const userBalance = async () =>
+        BigInt(
+          (await provider.connection.getTokenAccountBalance(userWusdAccount)).value
+            .amount
+        );
+      const eventParser = new anchor.EventParser(program.programId, program.coder);
+      // 提前退出并读取事件，核对各项金额之间的关系
+      const earlyExit = async (position: PublicKey) => {
+        const balanceBefore = await userBalance();
+        const signature = await program.methods
+          .earlyExit()
+          .accounts({
+            user: user.publicKey,
+            stakeAccount: position,
+            state: statePda,
+            wusdMint: wusdMint.publicKey,
+            userWusd: userWusdAccount,
+            stakeVault: stakeVaultAccount,
+            rewardVault,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          })
+          .signers([user])
+          .rpc({ commitment: "confirmed" });
+        const tx = await provider.connection.getTransaction(signature, {
+          commitment: "confirmed",
+          maxSupportedTransactionVersion: 0,
+        });
+        const event = [...eventParser.parseLogs(tx.meta.logMessages)].find(
+          (e) => e.name === "EarlyExitEvent"
+        ).data;
+        const value = (key: string) => BigInt(event[key].toString());
+
+        const principal = value("principal");
+        const served = value("timeServed");
+        const lock = value("lockDuration");
+        const earned = value("rewardsEarned");
+        const lost = earned - (earned * served) / lock;
+        const pending = value("rewardsForfeited") + value("rewardsPaid");
+
+        assert.equal(principal.toString(), stakeAmount.toString());
+        assert.equal(
+          value("principalHaircut").toString(),
+          ((principal * BigInt(haircut) * (lock - served)) / lock / BigInt(1000000)).toString()
+        );
+        assert.equal(
+          value("rewardsForfeited").toString(),
+          (lost < pending ? lost : pending).toString()
+        );
+        assert.equal(
+          value("rewardsClawback").toString(),
+          (lost - value("rewardsForfeited")).toString()
+        );
+        assert.equal(
+          (value("principalReturned") + value("principalHaircut") + value("rewardsClawback")).toString(),
+          principal.toString()
+        );
+        assert.equal(
+          ((await userBalance()) - balanceBefore).toString(),
+          (value("principalReturned") + value("rewardsPaid")).toString()
+        );
+
+        const stakeAccount = await program.account.stakeAccount.fetch(position);
+        assert.ok(stakeAccount.claimType.earlyExit !== undefined);
+        assert.equal(stakeAccount.stakeInfo.amount.toNumber(), 0);
+        return event;
+      };
+
+      // 未领取过奖励：保留按已服务时间折算的奖励，其余没收，不扣回本金
+      const untouched = await openPosition();
+      await sleep(4000);
+      const kept = await earlyExit(untouched);
+      assert.isAbove(kept.rewardsPaid.toNumber(), 0);
+      assert.isAbove(kept.rewardsForfeited.toNumber(), 0);
+      assert.equal(kept.rewardsClawback.toNumber(), 0);
+      assert.isAbove(kept.principalHaircut.toNumber(), 0);
+
+      // 已领取的奖励超过应得部分：未领取奖励全部没收，超出部分从本金扣回
+      const claimed = await openPosition();
+      await sleep(4000);
+      await program.methods
+        .claim(new anchor.BN(600))
+        .accounts({
+          user: user.publicKey,
+          stakeAccount: claimed,
+          state: statePda,
+          wusdMint: wusdMint.publicKey,
+          userWusd: userWusdAccount,
+          rewardVault,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([user])
+        .rpc();
+      await sleep(2000);
+      const clawedBack = await earlyExit(claimed);
+      assert.equal(clawedBack.rewardsPaid.toNumber(), 0);
+      assert.isAbove(clawedBack.rewardsClawback.toNumber(), 0);
+      console.log("Early exit amounts:", {
+        kept: kept.rewardsPaid.toNumber(),
+        forfeited: kept.rewardsForfeited.toNumber(),
+        haircut: kept.principalHaircut.toNumber(),
+        clawback: clawedBack.rewardsClawback.toNumber(),
       });
+    } catch (error) {
+      console.error("Early exit failed:", error);
+      throw error;
+    }
+  });
+
+  it("Soft stake WUSD and claim rewards from the accumulator", async () => {
+    try {
+      const softStakeAmount = new anchor.BN(10000000000); // 10000 WUSD
+      const fundAmount = new anchor.BN(100000000); // 100 WUSD
+      const accessKey = Array.from(new Uint8Array(32));
 
-      // 创建质押金库的代币账户
-      const createStakeVaultAccountIx = createAssociatedTokenAccountInstruction(
+      // 无锁定期、APY 100% 的质押池，每秒约累计 317 个最小单位
+      const state = await program.account.stateAccount.fetch(statePda);
+      const stakingPoolId = new anchor.BN(state.stakingConfig.totalStakingPlans);
+      await program.methods
+        .createStakingPool(new anchor.BN(1000000), new anchor.BN(0), new anchor.BN(1000000))
+        .accounts({
+          authority: provider.wallet.publicKey,
+          state: statePda,
+        })
+        .rpc();
+
+      await mintTo(
+        provider.connection,
+        provider.wallet.payer,
+        wusdMint.publicKey,
+        userWusdAccount,
         provider.wallet.publicKey,
-        stakeVaultAccount,
-        stakeVaultPda,
+        BigInt(softStakeAmount.toString())
+      );
+      await mintTo(
+        provider.connection,
+        provider.wallet.payer,
         wusdMint.publicKey,
-        TOKEN_PROGRAM_ID
+        treasuryAccount,
+        provider.wallet.publicKey,
+        BigInt(fundAmount.toString())
       );
+      const rewardVault = rewardVaultPda(stakingPoolId);
+      await program.methods
+        .fundRewards(stakingPoolId, fundAmount)
+        .accounts({
+          funder: treasury.publicKey,
+          state: statePda,
+          wusdMint: wusdMint.publicKey,
+          treasury: treasuryAccount,
+          rewardVault,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+          rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+        })
+        .signers([treasury])
+        .rpc();
 
-      const stakeVaultTx = new anchor.web3.Transaction().add(createStakeVaultAccountIx);
-      await provider.sendAndConfirm(stakeVaultTx);
-      await sleep(1000);
+      const [stakeVaultAccount] = PublicKey.findProgramAddressSync(
+        [Buffer.from("stake_vault"), wusdMint.publicKey.toBuffer()],
+        program.programId
+      );
+      const balance = async (account: PublicKey) =>
+        BigInt((await provider.connection.getTokenAccountBalance(account)).value.amount);
+      const userBefore = await balance(userWusdAccount);
+      const vaultBefore = await balance(stakeVaultAccount);
 
       await program.methods
         .softStake(softStakeAmount, stakingPoolId, accessKey)
         .accounts({
           user: user.publicKey,
-          softStakeAccount: softStakeAccountPda,
-          state: statePda,
+          userWusd: userWusdAccount,
           wusdMint: wusdMint.publicKey,
+          stakeAccount: softStakeAccountPda,
+          stakeVault: stakeVaultAccount,
+          rewardVault,
+          state: statePda,
+          systemProgram: SystemProgram.programId,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+        })
+        .signers([user])
+        .rpc();
+
+      // 本金转入质押金库并计入质押池
+      assert.equal(
+        (vaultBefore + BigInt(softStakeAmount.toString())).toString(),
+        (await balance(stakeVaultAccount)).toString()
+      );
+      let pool = (await program.account.stateAccount.fetch(statePda))
+        .stakingPools[stakingPoolId.toNumber()];
+      assert.equal(pool.totalStaked.toString(), softStakeAmount.toString());
+
+      const claimAccounts = {
+        user: user.publicKey,
+        stakeAccount: softStakeAccountPda,
+        userWusd: userWusdAccount,
+        wusdMint: wusdMint.publicKey,
+        state: statePda,
+        rewardVault,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      };
+      const claim = (amount: number) =>
+        program.methods
+          .softClaim(new anchor.BN(amount))
+          .accounts(claimAccounts)
+          .signers([user])
+          .rpc();
+
+      await sleep(3000);
+
+      // 超过已累计奖励的领取应被拒绝
+      let rejected = false;
+      try {
+        await claim(1000000000);
+      } catch (error) {
+        rejected = true;
+        assert.include(error.toString(), "InsufficientBalance");
+      }
+      assert.ok(rejected, "claim above the accrued rewards should fail");
+
+      // 部分领取：奖励从奖励金库支付，剩余奖励保留，本金保持质押
+      const partial = 100;
+      const rewardVaultBefore = await balance(rewardVault);
+      await claim(partial);
+      assert.equal(
+        (rewardVaultBefore - (await balance(rewardVault))).toString(),
+        partial.toString()
+      );
+      let softStake = await program.account.softStakeAccount.fetch(softStakeAccountPda);
+      assert.equal(softStake.amount.toString(), softStakeAmount.toString());
+      assert.ok(softStake.status.active !== undefined);
+      assert.isAbove(softStake.rewardsEarned.toNumber(), 0);
+      const firstRemaining = softStake.rewardsEarned.toNumber();
+
+      // 本金仍在计息，再次领取时包括新累计的奖励
+      await sleep(2000);
+      await claim(firstRemaining + 1);
+      softStake = await program.account.softStakeAccount.fetch(softStakeAccountPda);
+      assert.equal(softStake.amount.toString(), softStakeAmount.toString());
+      assert.equal(
+        (await balance(stakeVaultAccount)).toString(),
+        (vaultBefore + BigInt(softStakeAmount.toString())).toString()
+      );
+
+      // 取回本金后已累计的奖励仍可领取，之后不再累计
+      await sleep(2000);
+      await program.methods
+        .softUnstake(softStakeAmount)
+        .accounts({
+          user: user.publicKey,
+          stakeAccount: softStakeAccountPda,
           userWusd: userWusdAccount,
+          wusdMint: wusdMint.publicKey,
           stakeVault: stakeVaultAccount,
+          state: statePda,
           tokenProgram: TOKEN_PROGRAM_ID,
         })
         .signers([user])
         .rpc();
+      softStake = await program.account.softStakeAccount.fetch(softStakeAccountPda);
+      assert.equal(softStake.amount.toNumber(), 0);
+      const leftover = softStake.rewardsEarned.toNumber();
+      assert.isAbove(leftover, 0);
+      await claim(leftover);
+      pool = (await program.account.stateAccount.fetch(statePda))
+        .stakingPools[stakingPoolId.toNumber()];
+      assert.equal(pool.totalStaked.toNumber(), 0);
 
-      const softStakeAccount = await program.account.softStakeAccount.fetch(
-        softStakeAccountPda
+      const paid = (await balance(userWusdAccount)) - userBefore;
+      assert.equal(
+        paid.toString(),
+        (fundAmount.toNumber() - Number(await balance(rewardVault))).toString()
       );
-      assert.equal(softStakeAccount.amount.toNumber(), softStakeAmount.toNumber());
-      console.log("Soft staking successful");
+      console.log("Soft stake rewards paid:", paid.toString());
     } catch (error) {
       console.error("Soft staking failed:", error);
       throw error;
     }
   });
 
+  it("Request unstake", async () => {
+    try {
+      await program.methods
+        .requestUnstake()
+        .accounts({
+          user: user.publicKey,
+          stakeAccount: stakeAccountPda,
+          state: statePda,
+        })
+        .signers([user])
+        .rpc();
+
+      const stakeAccount = await program.account.stakeAccount.fetch(
+        stakeAccountPda
+      );
+      assert.ok(stakeAccount.status.unlocked !== undefined);
+      console.log("Unstake requested, claimable at:", stakeAccount.timeInfo.claimableTimestamp.toNumber());
+    } catch (error) {
+      console.error("Request unstake failed:", error);
+      throw error;
+    }
+  });
+
   it("Withdraw staked tokens", async () => {
     try {
       const withdrawAmount = new anchor.BN(500000000); // 500 WUSD
-      const isEmergency = false;
 
       // 获取质押金库账户
-      const [stakeVaultPda] = PublicKey.findProgramAddressSync(
-        [Buffer.from("stake_vault"), stakeAccountPda.toBuffer()],
+      const [stakeVaultAccount] = PublicKey.findProgramAddressSync(
+        [Buffer.from("stake_vault"), wusdMint.publicKey.toBuffer()],
         program.programId
       );
 
-      const stakeVaultAccount = await anchor.utils.token.associatedAddress({
-        mint: wusdMint.publicKey,
-        owner: stakeVaultPda
-      });
-
       await program.methods
-        .withdraw(withdrawAmount, isEmergency)
+        .withdraw(withdrawAmount)
         .accounts({
           user: user.publicKey,
+          userStake: userStakePda,
           stakeAccount: stakeAccountPda,
           state: statePda,
           wusdMint: wusdMint.publicKey,
           userWusd: userWusdAccount,
           stakeVault: stakeVaultAccount,
+          treasury: treasuryAccount,
           tokenProgram: TOKEN_PROGRAM_ID,
         })
         .signers([user])
@@ -451,7 +1411,7 @@ describe("WUSD Application Tests", () => {
         stakeAccountPda
       );
       assert.equal(
-        stakeAccount.amount.toNumber(),
+        stakeAccount.stakeInfo.amount.toNumber(),
         new anchor.BN(500000000).toNumber()
       );
 
@@ -459,7 +1419,7 @@ describe("WUSD Application Tests", () => {
         userWusdAccount
       );
       console.log("Withdrawal successful");
-      console.log("Remaining staked amount:", stakeAccount.amount.toNumber());
+      console.log("Remaining staked amount:", stakeAccount.stakeInfo.amount.toNumber());
       console.log("User token balance:", tokenBalance.value.uiAmount);
     } catch (error) {
       console.error("Withdrawal failed:", error);
@@ -467,31 +1427,180 @@ describe("WUSD Application Tests", () => {
     }
   });
 
+  it("Withdraw only after the unbonding period", async () => {
+    try {
+      const stakeAmount = new anchor.BN(100000000); // 100 WUSD
+      const fundAmount = new anchor.BN(1000000); // 1 WUSD
+      const unbondingPeriod = new anchor.BN(3);
+
+      // 无锁定期、解除质押后须等待 3 秒的质押池
+      const state = await program.account.stateAccount.fetch(statePda);
+      const stakingPoolId = new anchor.BN(state.stakingConfig.totalStakingPlans);
+      await program.methods
+        .createStakingPool(new anchor.BN(100000), new anchor.BN(0), new anchor.BN(1000000))
+        .accounts({
+          authority: provider.wallet.publicKey,
+          state: statePda,
+        })
+        .rpc();
+      const periodTx = await program.methods
+        .setUnbondingPeriod(stakingPoolId, unbondingPeriod)
+        .accounts({
+          authority: provider.wallet.publicKey,
+          state: statePda,
+        })
+        .rpc({ commitment: "confirmed" });
+      const periodEvent = (await parseEvents(periodTx)).find(
+        (event) => event.name === "UnbondingPeriodSet"
+      );
+      assert.ok(periodEvent, "UnbondingPeriodSet not emitted");
+      assert.equal(periodEvent.data.stakingPoolId.toNumber(), stakingPoolId.toNumber());
+      assert.equal(periodEvent.data.unbondingPeriod.toNumber(), unbondingPeriod.toNumber());
+
+      await mintTo(
+        provider.connection,
+        provider.wallet.payer,
+        wusdMint.publicKey,
+        userWusdAccount,
+        provider.wallet.publicKey,
+        BigInt(stakeAmount.toString())
+      );
+      await mintTo(
+        provider.connection,
+        provider.wallet.payer,
+        wusdMint.publicKey,
+        treasuryAccount,
+        provider.wallet.publicKey,
+        BigInt(fundAmount.toString())
+      );
+      const rewardVault = rewardVaultPda(stakingPoolId);
+      await program.methods
+        .fundRewards(stakingPoolId, fundAmount)
+        .accounts({
+          funder: treasury.publicKey,
+          state: statePda,
+          wusdMint: wusdMint.publicKey,
+          treasury: treasuryAccount,
+          rewardVault,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+          rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+        })
+        .signers([treasury])
+        .rpc();
+
+      const [stakeVaultAccount] = PublicKey.findProgramAddressSync(
+        [Buffer.from("stake_vault"), wusdMint.publicKey.toBuffer()],
+        program.programId
+      );
+      const [poolTiersPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("pool_tiers"), stakingPoolId.toArrayLike(Buffer, "le", 8)],
+        program.programId
+      );
+      const userStake = await program.account.userStakeState.fetch(userStakePda);
+      const position = positionPda(userStake.positionCount);
+      await program.methods
+        .stake(stakeAmount, stakingPoolId)
+        .accounts({
+          user: user.publicKey,
+          userStake: userStakePda,
+          stakeAccount: position,
+          state: statePda,
+          wusdMint: wusdMint.publicKey,
+          userWusd: userWusdAccount,
+          stakeVault: stakeVaultAccount,
+          rewardVault,
+          poolTiers: poolTiersPda,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+          rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+        })
+        .signers([user])
+        .rpc();
+      await program.methods
+        .requestUnstake()
+        .accounts({
+          user: user.publicKey,
+          stakeAccount: position,
+          state: statePda,
+        })
+        .signers([user])
+        .rpc();
+
+      let stakeAccount = await program.account.stakeAccount.fetch(position);
+      assert.ok(stakeAccount.status.unlocked !== undefined);
+      assert.isAtLeast(
+        stakeAccount.timeInfo.claimableTimestamp.toNumber(),
+        stakeAccount.timeInfo.lastUpdateTime.toNumber() + unbondingPeriod.toNumber()
+      );
+
+      const withdraw = () =>
+        program.methods
+          .withdraw(stakeAmount)
+          .accounts({
+            user: user.publicKey,
+            userStake: userStakePda,
+            stakeAccount: position,
+            state: statePda,
+            wusdMint: wusdMint.publicKey,
+            userWusd: userWusdAccount,
+            stakeVault: stakeVaultAccount,
+            treasury: treasuryAccount,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          })
+          .signers([user])
+          .rpc();
+
+      // 等待期内提取应被拒绝
+      let rejected = false;
+      try {
+        await withdraw();
+      } catch (error) {
+        rejected = true;
+        assert.include(error.toString(), "ClaimableTimestampNotReached");
+      }
+      assert.ok(rejected, "withdraw should fail before the claimable timestamp");
+
+      // 等待期结束后可全额提取
+      await sleep((unbondingPeriod.toNumber() + 1) * 1000);
+      const before = BigInt(
+        (await provider.connection.getTokenAccountBalance(userWusdAccount)).value.amount
+      );
+      await withdraw();
+      const after = BigInt(
+        (await provider.connection.getTokenAccountBalance(userWusdAccount)).value.amount
+      );
+      assert.equal((after - before).toString(), stakeAmount.toString());
+      stakeAccount = await program.account.stakeAccount.fetch(position);
+      assert.equal(stakeAccount.stakeInfo.amount.toNumber(), 0);
+      console.log("Withdrawn after unbonding:", stakeAmount.toNumber());
+    } catch (error) {
+      console.error("Unbonding withdraw failed:", error);
+      throw error;
+    }
+  });
+
   it("Emergency withdraw", async () => {
     try {
       const withdrawAmount = new anchor.BN(500000000); // Remaining 500 WUSD
-      const isEmergency = true;
 
       // 获取质押金库账户
-      const [stakeVaultPda] = PublicKey.findProgramAddressSync(
-        [Buffer.from("stake_vault"), stakeAccountPda.toBuffer()],
+      const [stakeVaultAccount] = PublicKey.findProgramAddressSync(
+        [Buffer.from("stake_vault"), wusdMint.publicKey.toBuffer()],
         program.programId
       );
 
-      const stakeVaultAccount = await anchor.utils.token.associatedAddress({
-        mint: wusdMint.publicKey,
-        owner: stakeVaultPda
-      });
-
       await program.methods
-        .withdraw(withdrawAmount, isEmergency)
+        .emergencyWithdraw(withdrawAmount)
         .accounts({
           user: user.publicKey,
+          userStake: userStakePda,
           stakeAccount: stakeAccountPda,
           state: statePda,
           wusdMint: wusdMint.publicKey,
           userWusd: userWusdAccount,
           stakeVault: stakeVaultAccount,
+          treasury: treasuryAccount,
           tokenProgram: TOKEN_PROGRAM_ID,
         })
         .signers([user])
@@ -500,7 +1609,8 @@ describe("WUSD Application Tests", () => {
       const stakeAccount = await program.account.stakeAccount.fetch(
         stakeAccountPda
       );
-      assert.equal(stakeAccount.amount.toNumber(), 0);
+      assert.equal(stakeAccount.stakeInfo.amount.toNumber(), 0);
+      assert.ok(stakeAccount.emergencyCooldown.toNumber() > 0);
       console.log("Emergency withdrawal successful");
     } catch (error) {
       console.error("Emergency withdrawal failed:", error);